@@ -13,9 +13,9 @@ import { TypedDocumentNode as DocumentNode } from '@graphql-typed-document-node/
  * Therefore it is highly recommended to use the babel or swc plugin for production.
  */
 const documents = {
-    "\n  query GetAllGames {\n    games {\n      id\n    }\n  }\n": types.GetAllGamesDocument,
+    "\n  query GetAllGames {\n    games {\n      edges {\n        node {\n          id\n        }\n      }\n    }\n  }\n": types.GetAllGamesDocument,
     "\nquery GET_GAME($id: String!) {\n  game(id: $id) {\n    id\n    p1Points\n    p2Points\n    roundNum\n    currentRound {\n      letterpoolState\n      currentPlayer\n      guessNum\n      turns {\n        guessedWord\n        letterState\n      }\n    }\n  }\n}\n": types.Get_GameDocument,
-    "\n  query GetGames {\n    games {\n      id,\n      p1Points,\n      p2Points,\n      roundNum\n    }\n  }\n": types.GetGamesDocument,
+    "\n  query GetGames {\n    games(filter: {status: ACTIVE}) {\n      edges {\n        node {\n          id,\n          p1Points,\n          p2Points,\n          roundNum\n        }\n      }\n    }\n  }\n": types.GetGamesDocument,
 };
 
 /**
@@ -35,7 +35,7 @@ export function gql(source: string): unknown;
 /**
  * The gql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
-export function gql(source: "\n  query GetAllGames {\n    games {\n      id\n    }\n  }\n"): (typeof documents)["\n  query GetAllGames {\n    games {\n      id\n    }\n  }\n"];
+export function gql(source: "\n  query GetAllGames {\n    games {\n      edges {\n        node {\n          id\n        }\n      }\n    }\n  }\n"): (typeof documents)["\n  query GetAllGames {\n    games {\n      edges {\n        node {\n          id\n        }\n      }\n    }\n  }\n"];
 /**
  * The gql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
@@ -43,7 +43,7 @@ export function gql(source: "\nquery GET_GAME($id: String!) {\n  game(id: $id) {
 /**
  * The gql function is used to parse GraphQL queries into a document that can be used by GraphQL clients.
  */
-export function gql(source: "\n  query GetGames {\n    games {\n      id,\n      p1Points,\n      p2Points,\n      roundNum\n    }\n  }\n"): (typeof documents)["\n  query GetGames {\n    games {\n      id,\n      p1Points,\n      p2Points,\n      roundNum\n    }\n  }\n"];
+export function gql(source: "\n  query GetGames {\n    games(filter: {status: ACTIVE}) {\n      edges {\n        node {\n          id,\n          p1Points,\n          p2Points,\n          roundNum\n        }\n      }\n    }\n  }\n"): (typeof documents)["\n  query GetGames {\n    games(filter: {status: ACTIVE}) {\n      edges {\n        node {\n          id,\n          p1Points,\n          p2Points,\n          roundNum\n        }\n      }\n    }\n  }\n"];
 
 export function gql(source: string) {
   return (documents as any)[source] ?? {};
@@ -14,6 +14,7 @@ export type Scalars = {
   Boolean: { input: boolean; output: boolean; }
   Int: { input: number; output: number; }
   Float: { input: number; output: number; }
+  DateTime: { input: any; output: any; }
 };
 
 /** A game that is currently active/being played. */
@@ -21,6 +22,8 @@ export type Game = {
   __typename?: 'Game';
   /** The current round that is being played. */
   currentRound: Round;
+  /** When the game was created. */
+  createdAt: Scalars['DateTime']['output'];
   /** The id of a Game. Used by the database to identify each document. */
   id: Scalars['String']['output'];
   /** The points of player 1. */
@@ -32,11 +35,81 @@ export type Game = {
    * This value starts at 1.
    */
   roundNum: Scalars['Int']['output'];
+  /** Whether the game is still being played or has ended. */
+  status: GameStatus;
 };
 
+/** A page of games. */
+export type GameConnection = {
+  __typename?: 'GameConnection';
+  /** The games in this page. */
+  edges: Array<GameEdge>;
+  /** Info about this page. */
+  pageInfo: PageInfo;
+  /** The total number of games matching the filter, ignoring pagination. */
+  totalCount: Scalars['Int']['output'];
+};
+
+/** A game in a page, along with the cursor pointing to it. */
+export type GameEdge = {
+  __typename?: 'GameEdge';
+  /** The cursor of the game. */
+  cursor: Scalars['String']['output'];
+  /** The game. */
+  node: Game;
+};
+
+/**
+ * Filters for which games to get.
+ * Only games matching all filters given are returned.
+ */
+export type GameFilter = {
+  /** Only get games created at or after this time. */
+  createdAfter?: InputMaybe<Scalars['DateTime']['input']>;
+  /** Only get games created before this time. */
+  createdBefore?: InputMaybe<Scalars['DateTime']['input']>;
+  /** Only get games where it is this player's turn. */
+  currentPlayer?: InputMaybe<Player>;
+  /** Only get games with this status. */
+  status?: InputMaybe<GameStatus>;
+};
+
+/** How to sort games. */
+export type GameSort = {
+  /** The direction to sort in. */
+  direction: SortDirection;
+  /** The field to sort by. */
+  field: GameSortField;
+};
+
+/** The field to sort games by. */
+export enum GameSortField {
+  /** Sort by when the game was created. */
+  CreatedAt = 'CREATED_AT',
+  /** Sort by the round number of the game. */
+  RoundNum = 'ROUND_NUM'
+}
+
+/** The status of a game, whether it is still being played or not. */
+export enum GameStatus {
+  /** The game is still being played. */
+  Active = 'ACTIVE',
+  /** The game has ended. */
+  Finished = 'FINISHED'
+}
+
 export type Mutation = {
   __typename?: 'Mutation';
   apiVersion: Scalars['String']['output'];
+  /**
+   * Ends a game, so it is no longer being played.
+   * Returns true if a game was ended, false if there is no active game with the id.
+   *
+   * # Errors
+   *
+   * This function will return an error if failed to execute the update.
+   */
+  endGame: Scalars['Boolean']['output'];
   /**
    * Creates a new game. Returns true if successful.
    *
@@ -69,6 +142,11 @@ export type Mutation = {
 };
 
 
+export type MutationEndGameArgs = {
+  id: Scalars['String']['input'];
+};
+
+
 export type MutationRemoveGameArgs = {
   id: Scalars['String']['input'];
 };
@@ -78,6 +156,22 @@ export type MutationTestNewGameArgs = {
   id: Scalars['String']['input'];
 };
 
+/** Info about a page of results. */
+export type PageInfo = {
+  __typename?: 'PageInfo';
+  /**
+   * The cursor of the last result in this page.
+   * Pass this as ```after``` to get the next page.
+   */
+  endCursor?: Maybe<Scalars['String']['output']>;
+  /** Whether there are more results after this page. */
+  hasNextPage: Scalars['Boolean']['output'];
+  /** Whether there are results before this page. */
+  hasPreviousPage: Scalars['Boolean']['output'];
+  /** The cursor of the first result in this page. */
+  startCursor?: Maybe<Scalars['String']['output']>;
+};
+
 /** The player type, either player 1 or player 2 */
 export enum Player {
   /** Player 1 */
@@ -91,8 +185,18 @@ export type Query = {
   apiVersion: Scalars['String']['output'];
   /** Get a game */
   game: Game;
-  /** Get all games */
-  games: Array<Game>;
+  /**
+   * Get a page of games.
+   *
+   * Games can be filtered by ```filter``` and sorted by ```sort```, newest games are first by default.
+   * To get the next page, pass the ```endCursor``` of the current page as ```after```.
+   *
+   * # Errors
+   *
+   * Will throw a 400 error if ```first``` is out of range or ```after``` is not a valid cursor.
+   * Will throw a 500 error if failed to query the database.
+   */
+  games: GameConnection;
 };
 
 
@@ -100,6 +204,14 @@ export type QueryGameArgs = {
   id: Scalars['String']['input'];
 };
 
+
+export type QueryGamesArgs = {
+  after?: InputMaybe<Scalars['String']['input']>;
+  filter?: InputMaybe<GameFilter>;
+  first?: Scalars['Int']['input'];
+  sort?: InputMaybe<GameSort>;
+};
+
 /**
  * A round in a match.
  * A match can have multiple rounds
@@ -128,6 +240,14 @@ export type Round = {
   turns: Array<Turn>;
 };
 
+/** The direction to sort games in. */
+export enum SortDirection {
+  /** Smallest or oldest first. */
+  Asc = 'ASC',
+  /** Largest or newest first. */
+  Desc = 'DESC'
+}
+
 /** A turn turn made by some player. */
 export type Turn = {
   __typename?: 'Turn';
@@ -143,7 +263,7 @@ export type Turn = {
 export type GetAllGamesQueryVariables = Exact<{ [key: string]: never; }>;
 
 
-export type GetAllGamesQuery = { __typename?: 'Query', games: Array<{ __typename?: 'Game', id: string }> };
+export type GetAllGamesQuery = { __typename?: 'Query', games: { __typename?: 'GameConnection', edges: Array<{ __typename?: 'GameEdge', node: { __typename?: 'Game', id: string } }> } };
 
 export type Get_GameQueryVariables = Exact<{
   id: Scalars['String']['input'];
@@ -155,9 +275,9 @@ export type Get_GameQuery = { __typename?: 'Query', game: Get_GameQueryResult};
 export type GetGamesQueryVariables = Exact<{ [key: string]: never; }>;
 
 export type  GetGamesQueryResult = { __typename?: 'Game', id: string, p1Points: number, p2Points: number, roundNum: number }
-export type GetGamesQuery = { __typename?: 'Query', games: Array<GetGamesQueryResult> };
+export type GetGamesQuery = { __typename?: 'Query', games: { __typename?: 'GameConnection', edges: Array<{ __typename?: 'GameEdge', node: GetGamesQueryResult }> } };
 
 
-export const GetAllGamesDocument = {"kind":"Document","definitions":[{"kind":"OperationDefinition","operation":"query","name":{"kind":"Name","value":"GetAllGames"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"games"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"id"}}]}}]}}]} as unknown as DocumentNode<GetAllGamesQuery, GetAllGamesQueryVariables>;
+export const GetAllGamesDocument = {"kind":"Document","definitions":[{"kind":"OperationDefinition","operation":"query","name":{"kind":"Name","value":"GetAllGames"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"games"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"edges"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"node"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"id"}}]}}]}}]}}]}}]} as unknown as DocumentNode<GetAllGamesQuery, GetAllGamesQueryVariables>;
 export const Get_GameDocument = {"kind":"Document","definitions":[{"kind":"OperationDefinition","operation":"query","name":{"kind":"Name","value":"GET_GAME"},"variableDefinitions":[{"kind":"VariableDefinition","variable":{"kind":"Variable","name":{"kind":"Name","value":"id"}},"type":{"kind":"NonNullType","type":{"kind":"NamedType","name":{"kind":"Name","value":"String"}}}}],"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"game"},"arguments":[{"kind":"Argument","name":{"kind":"Name","value":"id"},"value":{"kind":"Variable","name":{"kind":"Name","value":"id"}}}],"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"id"}},{"kind":"Field","name":{"kind":"Name","value":"p1Points"}},{"kind":"Field","name":{"kind":"Name","value":"p2Points"}},{"kind":"Field","name":{"kind":"Name","value":"roundNum"}},{"kind":"Field","name":{"kind":"Name","value":"currentRound"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"letterpoolState"}},{"kind":"Field","name":{"kind":"Name","value":"currentPlayer"}},{"kind":"Field","name":{"kind":"Name","value":"guessNum"}},{"kind":"Field","name":{"kind":"Name","value":"turns"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"guessedWord"}},{"kind":"Field","name":{"kind":"Name","value":"letterState"}}]}}]}}]}}]}}]} as unknown as DocumentNode<Get_GameQuery, Get_GameQueryVariables>;
-export const GetGamesDocument = {"kind":"Document","definitions":[{"kind":"OperationDefinition","operation":"query","name":{"kind":"Name","value":"GetGames"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"games"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"id"}},{"kind":"Field","name":{"kind":"Name","value":"p1Points"}},{"kind":"Field","name":{"kind":"Name","value":"p2Points"}},{"kind":"Field","name":{"kind":"Name","value":"roundNum"}}]}}]}}]} as unknown as DocumentNode<GetGamesQuery, GetGamesQueryVariables>;
\ No newline at end of file
+export const GetGamesDocument = {"kind":"Document","definitions":[{"kind":"OperationDefinition","operation":"query","name":{"kind":"Name","value":"GetGames"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"games"},"arguments":[{"kind":"Argument","name":{"kind":"Name","value":"filter"},"value":{"kind":"ObjectValue","fields":[{"kind":"ObjectField","name":{"kind":"Name","value":"status"},"value":{"kind":"EnumValue","value":"ACTIVE"}}]}}],"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"edges"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"node"},"selectionSet":{"kind":"SelectionSet","selections":[{"kind":"Field","name":{"kind":"Name","value":"id"}},{"kind":"Field","name":{"kind":"Name","value":"p1Points"}},{"kind":"Field","name":{"kind":"Name","value":"p2Points"}},{"kind":"Field","name":{"kind":"Name","value":"roundNum"}}]}}]}}]}}]}}]} as unknown as DocumentNode<GetGamesQuery, GetGamesQueryVariables>;
\ No newline at end of file
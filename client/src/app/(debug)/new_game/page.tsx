@@ -10,7 +10,11 @@ import {gql} from "@/__generated__";
 const GET_ALL_GAMES = gql(/* GRAPHQL */ `
   query GetAllGames {
     games {
-      id
+      edges {
+        node {
+          id
+        }
+      }
     }
   }
 `)
@@ -68,7 +72,7 @@ function NewGame() {
           {error && <p>{error.message}</p>}
           {data && (
               <div>
-                {data.games.map(game => <p key={game.id}>{game.id}</p>)}
+                {data.games.edges.map(({node: game}) => <p key={game.id}>{game.id}</p>)}
               </div>
           )}
         </div>
@@ -11,7 +11,7 @@ const OngoingGamesList = () => {
 
   const {loading, error, data} = useContext(GamesQueryContext)
 
-  const games = data?.games
+  const games = data?.games.edges.map((edge) => edge.node)
 
   if (loading) return <h1>Loading</h1>
 
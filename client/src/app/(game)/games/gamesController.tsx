@@ -7,11 +7,15 @@ import {Children} from "@/app/types";
 
 const GET_GAMES = gql(/* GRAPHQL */ `
   query GetGames {
-    games {
-      id,
-      p1Points,
-      p2Points,
-      roundNum
+    games(filter: {status: ACTIVE}) {
+      edges {
+        node {
+          id,
+          p1Points,
+          p2Points,
+          roundNum
+        }
+      }
     }
   }
 `)
@@ -6,7 +6,8 @@ import {MATCHMAKING_PORT} from "@/app/constants";
 
 
 /** Connects the client to a new game.
- * Returns the game_id of the game the client has joined and which player they are.
+ * Returns the game_id of the game the client has joined, which player they are,
+ * and the token to send in the Player-Token header when making changes to the game.
  * */
 export async function new_game(): Promise<NewGameResponse> {
   // Send a request to matchmaking server to join a new game
@@ -29,7 +30,8 @@ export async function new_game(): Promise<NewGameResponse> {
 
   return {
     game_id: obj.game_id,
-    player_type: obj.player_type
+    player_type: obj.player_type,
+    player_token: obj.player_token
   }
 }
 
@@ -18,7 +18,7 @@ export enum Client {
 /**
  * Response from new_game route in matchmaking server
  */
-export type NewGameResponse = {game_id: string, player_type: Client}
+export type NewGameResponse = {game_id: string, player_type: Client, player_token: string | null}
 
 export type Game = GetGamesQueryResult
 
@@ -0,0 +1,361 @@
+use bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::{self, Stream};
+use juniper::{GraphQLEnum, GraphQLObject};
+use rocket_db_pools::mongodb::{
+    bson::{doc, Document},
+    options::FindOptions,
+    Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE404, CODE422, CODE429, CODE500},
+    models::{Game, Player},
+    profanity::censor,
+    store::find_game,
+    MongoClient,
+};
+
+// This file contains the chat of a game, where players can send messages and quick reactions
+// Messages are filtered for profanity, and each player can only send so many messages at a time
+
+/// The max number of characters in a chat message.
+pub const MAX_MESSAGE_LENGTH: usize = 200;
+
+/// The max number of messages returned at once.
+pub const MAX_MESSAGES: i64 = 100;
+
+/// The number of messages a player can send in a game within ```MESSAGE_RATE_WINDOW``` seconds.
+pub const MESSAGE_RATE_LIMIT: u64 = 5;
+
+/// The number of seconds ```MESSAGE_RATE_LIMIT``` applies over.
+pub const MESSAGE_RATE_WINDOW: i64 = 10;
+
+/// How often a subscription checks for new messages.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// A quick reaction that can be sent instead of a message.
+#[derive(Debug, Clone, Copy, PartialEq, GraphQLEnum, Serialize, Deserialize)]
+pub enum Reaction {
+    /// Thumbs up
+    ThumbsUp,
+    /// Laughing
+    Laugh,
+    /// Surprised
+    Wow,
+    /// Sad
+    Sad,
+    /// Good game
+    GoodGame,
+}
+
+/// A message sent in the chat of a game.
+#[derive(Debug, Clone, GraphQLObject, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// The id of the message.
+    #[serde(rename = "_id")]
+    id: String,
+
+    /// The id of the game the message was sent in.
+    game_id: String,
+
+    /// The player who sent the message.
+    player: Player,
+
+    /// The text of the message, with profanity hidden.
+    /// Is null if the message is a reaction.
+    text: Option<String>,
+
+    /// The reaction sent.
+    /// Is null if the message is text.
+    reaction: Option<Reaction>,
+
+    /// When the message was sent.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    sent_at: DateTime<Utc>,
+}
+
+impl ChatMessage {
+    /// Creates a text message, hiding any profanity in ```text```.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the text is empty or longer than ```MAX_MESSAGE_LENGTH```.
+    pub fn new_text(game_id: &str, player: Player, text: &str) -> GraphqlServerResult<Self> {
+        let text = text.trim();
+
+        if text.is_empty() || text.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(GraphqlServerError::new(
+                format!(
+                    "message should be between 1 and {} characters",
+                    MAX_MESSAGE_LENGTH
+                ),
+                &CODE422,
+            ));
+        }
+
+        Ok(Self::new(game_id, player, Some(censor(text)), None))
+    }
+
+    /// Creates a message with a quick reaction.
+    pub fn new_reaction(game_id: &str, player: Player, reaction: Reaction) -> Self {
+        Self::new(game_id, player, None, Some(reaction))
+    }
+
+    fn new(
+        game_id: &str,
+        player: Player,
+        text: Option<String>,
+        reaction: Option<Reaction>,
+    ) -> Self {
+        ChatMessage {
+            id: Uuid::new_v4().simple().to_string(),
+            game_id: game_id.to_string(),
+            player,
+            text,
+            reaction,
+            sent_at: Utc::now(),
+        }
+    }
+}
+
+/// Gets the collection of chat messages.
+fn message_collection<T: Send + Sync>(client: &MongoClient) -> Collection<T> {
+    client.database("Games").collection("Messages")
+}
+
+/// Creates the index used to find the messages of a game in the order they were sent.
+pub async fn create_indexes(client: &MongoClient) -> GraphqlServerResult<()> {
+    let messages: Collection<ChatMessage> = message_collection(client);
+
+    let game_index = IndexModel::builder()
+        .keys(doc! {"game_id": 1, "sent_at": 1, "_id": 1})
+        .build();
+
+    match messages.create_index(game_index, None).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to create message index".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Sends ```message``` in the chat of its game, which should exist.
+///
+/// # Errors
+///
+/// Will throw a 429 error if the player has sent too many messages recently,
+/// or a 500 error if failed to save the message.
+pub async fn send_message(client: &MongoClient, message: &ChatMessage) -> GraphqlServerResult<()> {
+    let messages: Collection<ChatMessage> = message_collection(client);
+
+    let since = Utc::now() - Duration::seconds(MESSAGE_RATE_WINDOW);
+    let recent_filter = doc! {
+        "game_id": &message.game_id,
+        "player": bson::to_bson(&message.player).expect("player should serialize"),
+        "sent_at": {"$gte": since},
+    };
+    let recent_count = messages
+        .count_documents(recent_filter, None)
+        .await
+        .map_err(|_| {
+            GraphqlServerError::new(
+                "Failed to execute count messages query".to_string(),
+                &CODE500,
+            )
+        })?;
+
+    if recent_count >= MESSAGE_RATE_LIMIT {
+        return Err(GraphqlServerError::new(
+            format!(
+                "players can only send {} messages every {} seconds",
+                MESSAGE_RATE_LIMIT, MESSAGE_RATE_WINDOW
+            ),
+            &CODE429,
+        ));
+    }
+
+    match messages.insert_one(message, None).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute insert message".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Where in the chat of a game to get messages after.
+#[derive(Debug, Clone)]
+struct MessagePosition {
+    sent_at: DateTime<Utc>,
+    id: String,
+}
+
+impl MessagePosition {
+    /// Creates the filter for messages of ```game_id``` after this position.
+    fn after_filter(&self, game_id: &str) -> Document {
+        doc! {
+            "game_id": game_id,
+            "$or": [
+                {"sent_at": {"$gt": self.sent_at}},
+                {"sent_at": self.sent_at, "_id": {"$gt": &self.id}},
+            ],
+        }
+    }
+}
+
+/// Finds the position of the message with ```message_id``` in the chat of ```game_id```.
+///
+/// # Errors
+///
+/// Will throw a 404 error if the game has no message with the id,
+/// or a 500 error if failed to execute the query.
+async fn message_position(
+    client: &MongoClient,
+    game_id: &str,
+    message_id: &str,
+) -> GraphqlServerResult<MessagePosition> {
+    let messages: Collection<ChatMessage> = message_collection(client);
+
+    match messages
+        .find_one(doc! {"_id": message_id, "game_id": game_id}, None)
+        .await
+    {
+        Ok(Some(message)) => Ok(MessagePosition {
+            sent_at: message.sent_at,
+            id: message.id,
+        }),
+        Ok(None) => Err(GraphqlServerError::new(
+            "No message with id found in game".to_string(),
+            &CODE404,
+        )),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute find message query".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Gets up to ```MAX_MESSAGES``` messages of a game in the order they were sent.
+async fn find_messages(
+    client: &MongoClient,
+    filter: Document,
+) -> GraphqlServerResult<Vec<ChatMessage>> {
+    let messages: Collection<ChatMessage> = message_collection(client);
+
+    let options = FindOptions::builder()
+        .sort(doc! {"sent_at": 1, "_id": 1})
+        .limit(MAX_MESSAGES)
+        .build();
+
+    let find_error = || {
+        GraphqlServerError::new(
+            "Failed to execute find messages query".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = messages
+        .find(filter, options)
+        .await
+        .map_err(|_| find_error())?;
+
+    let mut found = Vec::new();
+    while cursor.advance().await.map_err(|_| find_error())? {
+        found.push(cursor.deserialize_current().map_err(|_| find_error())?);
+    }
+
+    Ok(found)
+}
+
+/// Gets the messages of the game with ```game_id``` in the order they were sent,
+/// starting after the message with ```after``` if given.
+///
+/// # Errors
+///
+/// Will throw a 404 error if ```after``` isn't a message of the game,
+/// or a 500 error if failed to execute the query.
+pub async fn messages(
+    client: &MongoClient,
+    game_id: &str,
+    after: Option<String>,
+) -> GraphqlServerResult<Vec<ChatMessage>> {
+    let filter = match after {
+        None => doc! {"game_id": game_id},
+        Some(after) => message_position(client, game_id, &after)
+            .await?
+            .after_filter(game_id),
+    };
+
+    find_messages(client, filter).await
+}
+
+/// Streams the messages of the game with ```game_id``` as they are sent,
+/// starting after the message with ```after```, or with messages sent from now on.
+///
+/// # Errors
+///
+/// Will throw a 404 error if the game doesn't exist or ```after``` isn't a message of the game.
+/// The stream ends after an error finding new messages.
+pub async fn watch_messages(
+    client: MongoClient,
+    game_id: String,
+    after: Option<String>,
+) -> GraphqlServerResult<impl Stream<Item = GraphqlServerResult<ChatMessage>> + Send> {
+    let game_id = Game::parse_id(&game_id)?;
+    find_game(&client, &game_id).await?;
+
+    let position = match after {
+        Some(after) => message_position(&client, &game_id, &after).await?,
+        None => MessagePosition {
+            sent_at: Utc::now(),
+            id: String::new(),
+        },
+    };
+
+    let state = (client, game_id, Some(position), VecDeque::new());
+
+    Ok(stream::unfold(
+        state,
+        |(client, game_id, position, mut pending)| async move {
+            // the position is None after an error, ending the stream
+            let mut position = position?;
+
+            while pending.is_empty() {
+                tokio::time::sleep(POLL_INTERVAL).await;
+
+                match find_messages(&client, position.after_filter(&game_id)).await {
+                    Ok(found) => pending.extend(found),
+                    Err(e) => return Some((Err(e), (client, game_id, None, pending))),
+                }
+            }
+
+            let message: ChatMessage = pending.pop_front()?;
+            position = MessagePosition {
+                sent_at: message.sent_at,
+                id: message.id.clone(),
+            };
+
+            Some((Ok(message), (client, game_id, Some(position), pending)))
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn text_is_checked_and_censored() {
+        let message = ChatMessage::new_text("game", Player::P1, "  damn, nice guess ").unwrap();
+        assert_eq!(message.text.as_deref(), Some("****, nice guess"));
+
+        assert!(ChatMessage::new_text("game", Player::P1, "   ").is_err());
+        let long = "a".repeat(MAX_MESSAGE_LENGTH + 1);
+        assert!(ChatMessage::new_text("game", Player::P1, &long).is_err());
+    }
+}
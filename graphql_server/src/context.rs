@@ -0,0 +1,238 @@
+use chrono::Utc;
+use rocket::request::{FromRequest, Outcome, Request};
+use std::ops::Deref;
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE403},
+    models::{Game, Player},
+    MongoClient, ADMIN_TOKEN,
+};
+
+// This file contains the context given to every query and mutation
+// The context has the database connection and who made the request
+// Changes to a game can only be made by its players, who prove which player they are with the token they got for it
+
+/// The header players send the token of the player they are in a game in.
+pub const PLAYER_TOKEN_HEADER: &str = "Player-Token";
+
+/// The header spectators send their spectator token in.
+pub const SPECTATOR_TOKEN_HEADER: &str = "Spectator-Token";
+
+/// The header admins send the admin token in.
+pub const ADMIN_TOKEN_HEADER: &str = "Admin-Token";
+
+/// Who made a request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Role {
+    /// A player, who can make changes to the games they have the player token of, and create games.
+    Player(Option<String>),
+    /// A spectator with the token given by ```spectateGame```, who can only read games.
+    Spectator(String),
+    /// An admin with the admin token, who can make any change.
+    Admin,
+}
+
+impl Role {
+    /// Checks that the request was made by a player.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 403 error if the request was made by a spectator.
+    pub fn check_player(&self) -> GraphqlServerResult<()> {
+        match self {
+            Role::Player(_) | Role::Admin => Ok(()),
+            Role::Spectator(_) => Err(GraphqlServerError::new(
+                "spectators can't make changes to games".to_string(),
+                &CODE403,
+            )),
+        }
+    }
+
+    /// Checks that the request was made by an admin.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 403 error if the request wasn't made by an admin.
+    pub fn check_admin(&self) -> GraphqlServerResult<()> {
+        match self {
+            Role::Admin => Ok(()),
+            _ => Err(GraphqlServerError::new(
+                "only admins can make this change".to_string(),
+                &CODE403,
+            )),
+        }
+    }
+
+    /// Checks that the request was made by ```player``` of ```game```, or an admin.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 403 error if the request was made by a spectator,
+    /// or a player without the token of ```player``` (see ```Game::check_token```).
+    pub fn check_player_of(&self, game: &Game, player: Player) -> GraphqlServerResult<()> {
+        match self {
+            Role::Admin => Ok(()),
+            Role::Player(token) => game.check_token(player, token.as_deref()),
+            Role::Spectator(_) => self.check_player(),
+        }
+    }
+
+    /// Checks that the request was made by either player of ```game```, or an admin.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 403 error if the request was made by a spectator,
+    /// or a player without the token of either player.
+    pub fn check_either_player_of(&self, game: &Game) -> GraphqlServerResult<()> {
+        self.check_player_of(game, Player::P1)
+            .or_else(|_| self.check_player_of(game, Player::P2))
+    }
+
+    /// Hides the answer of a game of a daily challenge still being played (UTC) from everyone but its players and admins,
+    /// see ```Game::hide_daily_answer```.
+    pub fn hide_daily_answer(&self, game: &mut Game) {
+        if self.check_either_player_of(game).is_err() {
+            game.hide_daily_answer(Utc::now().date_naive());
+        }
+    }
+
+    /// Finds who made a request from its headers.
+    /// ```admin_token``` is the token of admins, if there are any.
+    fn from_headers(
+        admin_token: Option<&str>,
+        sent_admin_token: Option<&str>,
+        spectator_token: Option<&str>,
+        player_token: Option<&str>,
+    ) -> Role {
+        if admin_token.is_some() && sent_admin_token == admin_token {
+            return Role::Admin;
+        }
+
+        match spectator_token {
+            Some(token) => Role::Spectator(token.to_string()),
+            None => Role::Player(player_token.map(str::to_string)),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Role {
+    type Error = ();
+
+    /// A request with the admin token is made by an admin, a request with a spectator token by a spectator,
+    /// and any other request by a player, with the player token if sent.
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = request.headers();
+
+        Outcome::Success(Role::from_headers(
+            ADMIN_TOKEN.as_deref(),
+            headers.get_one(ADMIN_TOKEN_HEADER),
+            headers.get_one(SPECTATOR_TOKEN_HEADER),
+            headers.get_one(PLAYER_TOKEN_HEADER),
+        ))
+    }
+}
+
+/// The context of a graphql request.
+/// Derefs to the database connection, so it can be used wherever a ```MongoClient``` is needed.
+pub struct Context {
+    client: MongoClient,
+    role: Role,
+}
+
+impl juniper::Context for Context {}
+
+impl Context {
+    /// Creates the context of a request made by ```role```.
+    pub fn new(client: &MongoClient, role: Role) -> Self {
+        Context {
+            client: MongoClient(client.0.clone()),
+            role,
+        }
+    }
+
+    /// Gets who made the request.
+    pub fn role(&self) -> &Role {
+        &self.role
+    }
+
+    /// Checks that the request was made by a player, see ```Role::check_player```.
+    pub fn check_player(&self) -> GraphqlServerResult<()> {
+        self.role.check_player()
+    }
+
+    /// Checks that the request was made by ```player``` of ```game```, see ```Role::check_player_of```.
+    pub fn check_player_of(&self, game: &Game, player: Player) -> GraphqlServerResult<()> {
+        self.role.check_player_of(game, player)
+    }
+
+    /// Checks that the request was made by either player of ```game```, see ```Role::check_either_player_of```.
+    pub fn check_either_player_of(&self, game: &Game) -> GraphqlServerResult<()> {
+        self.role.check_either_player_of(game)
+    }
+
+    /// Hides the answer of a daily challenge still being played, see ```Role::hide_daily_answer```.
+    pub fn hide_daily_answer(&self, game: &mut Game) {
+        self.role.hide_daily_answer(game)
+    }
+
+    /// Checks that the request was made by an admin, see ```Role::check_admin```.
+    pub fn check_admin(&self) -> GraphqlServerResult<()> {
+        self.role.check_admin()
+    }
+}
+
+impl Deref for Context {
+    type Target = MongoClient;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_players_can_make_changes() {
+        assert!(Role::Player(None).check_player().is_ok());
+
+        let spectator = Role::Spectator("token".to_string());
+        let error = spectator.check_player().unwrap_err();
+        assert!(error.to_string().contains("403"));
+    }
+
+    #[test]
+    fn admin_needs_the_admin_token() {
+        let admin = Role::from_headers(Some("secret"), Some("secret"), None, None);
+        assert_eq!(admin, Role::Admin);
+        assert!(admin.check_admin().is_ok());
+
+        assert_eq!(
+            Role::from_headers(Some("secret"), Some("guess"), None, None),
+            Role::Player(None)
+        );
+        assert_eq!(
+            Role::from_headers(None, None, None, None),
+            Role::Player(None)
+        );
+        assert!(Role::Player(None).check_admin().is_err());
+    }
+
+    #[test]
+    fn players_need_their_token() {
+        let game = Game::new_game("words");
+        let new_game = game.to_new_game();
+        let p1 = Role::from_headers(None, None, None, Some(new_game.p1_token()));
+
+        assert!(p1.check_player_of(&game, Player::P1).is_ok());
+        assert!(p1.check_player_of(&game, Player::P2).is_err());
+        assert!(p1.check_either_player_of(&game).is_ok());
+        assert!(Role::Player(None).check_either_player_of(&game).is_err());
+        assert!(Role::Admin.check_player_of(&game, Player::P2).is_ok());
+
+        let spectator = Role::from_headers(None, None, Some("token"), Some(new_game.p1_token()));
+        assert!(spectator.check_player_of(&game, Player::P1).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+use chrono::NaiveDate;
+use hmac::{Hmac, Mac};
+use juniper::GraphQLObject;
+use rocket_db_pools::mongodb::{
+    bson::{doc, from_document, to_bson, Document},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use sha2::Sha256;
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE400, CODE404, CODE409, CODE500},
+    game_collection,
+    models::{Game, GameStatus, Player},
+    words::{word_at, word_count, Language},
+    MongoClient, DAILY_CHALLENGE_SECRET,
+};
+
+// This file contains the daily challenge, where every pair of players plays the same word on the same day
+// The word of a day is picked from the dictionary with an HMAC of the date, keyed by a server secret,
+// so it is the same for everyone but can't be worked out ahead of time without the secret
+// Daily challenges are always played with the English dictionary, so everyone can be ranked on the same board
+// Each player plays a daily challenge once, so they can't play it again knowing the word
+
+/// The default number of pairs on a daily board.
+pub const DEFAULT_DAILY_BOARD_SIZE: i32 = 10;
+
+/// The max number of pairs that can be on a daily board.
+pub const MAX_DAILY_BOARD_SIZE: i32 = 100;
+
+/// A pair's place on the board of a daily challenge.
+#[derive(Debug, Clone, PartialEq, GraphQLObject)]
+pub struct DailyBoardEntry {
+    /// The place of the pair, starting at 1.
+    rank: i32,
+
+    /// The id of the game the pair played.
+    game_id: String,
+
+    /// The id of the profile of player 1, if they have one.
+    p1_id: Option<String>,
+
+    /// The id of the profile of player 2, if they have one.
+    p2_id: Option<String>,
+
+    /// The player who guessed the word.
+    /// Is null if the pair didn't guess it.
+    solved_by: Option<Player>,
+
+    /// The number of guesses the pair made.
+    guesses: i32,
+
+    /// The number of seconds the pair took to play the round.
+    seconds: i32,
+}
+
+/// Works out the index of the word of the day ```date``` in a dictionary of ```word_count``` words.
+///
+/// # Panics
+///
+/// Panics if ```word_count``` is 0.
+pub fn daily_word_index(secret: &str, date: NaiveDate, word_count: u64) -> u64 {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes keys of any length");
+    mac.update(date.to_string().as_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let mut first_bytes = [0; 8];
+    first_bytes.copy_from_slice(&hash[..8]);
+    u64::from_be_bytes(first_bytes) % word_count
+}
+
+/// Gets the word of the daily challenge of ```date```.
+///
+/// # Errors
+///
+/// Will throw a 404 error if the dictionary has no words,
+/// or a 500 error if ```DAILY_CHALLENGE_SECRET``` isn't set or failed to query the dictionary.
+pub async fn daily_word(client: &MongoClient, date: NaiveDate) -> GraphqlServerResult<String> {
+    // without the secret anyone could work out the words ahead of time
+    let Some(secret) = DAILY_CHALLENGE_SECRET.as_deref() else {
+        return Err(GraphqlServerError::new(
+            "daily challenges can't be played, as DAILY_CHALLENGE_SECRET isn't set".to_string(),
+            &CODE500,
+        ));
+    };
+
+    let count = word_count(client, Language::English).await?;
+    if count == 0 {
+        return Err(GraphqlServerError::new(
+            "dictionary has no words for the daily challenge".to_string(),
+            &CODE404,
+        ));
+    }
+
+    let index = daily_word_index(secret, date, count);
+    word_at(client, Language::English, index).await
+}
+
+/// Creates the index used to find the finished games of a daily challenge,
+/// and the index that makes sure each player only has one game of each daily challenge.
+pub async fn create_indexes(client: &MongoClient) -> GraphqlServerResult<()> {
+    let games: Collection<Document> = game_collection(client);
+
+    let daily_index = IndexModel::builder()
+        .keys(doc! {"config.daily_challenge": 1, "status": 1})
+        .build();
+    // games from before players were recorded, and games that aren't daily challenges, aren't checked
+    let player_index = IndexModel::builder()
+        .keys(doc! {"config.daily_challenge": 1, "player_ids": 1})
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! {
+                    "config.daily_challenge": {"$type": "string"},
+                    "player_ids": {"$exists": true},
+                })
+                .build(),
+        )
+        .build();
+
+    match games
+        .create_indexes([daily_index, player_index], None)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to create daily challenge index".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Checks that none of the players with ```player_ids``` have a game of the daily challenge of ```date```.
+///
+/// # Errors
+///
+/// Will throw a 409 error if a player already has a game of the daily challenge,
+/// or a 500 error if failed to query the games.
+pub async fn check_not_played(
+    client: &MongoClient,
+    date: NaiveDate,
+    player_ids: &[&str],
+) -> GraphqlServerResult<()> {
+    let games: Collection<Document> = game_collection(client);
+    let filter = doc! {
+        "config.daily_challenge": date.to_string(),
+        "player_ids": {"$in": player_ids},
+    };
+
+    match games.count_documents(filter, None).await {
+        Ok(0) => Ok(()),
+        Ok(_) => Err(GraphqlServerError::new(
+            "a player already has a game of today's daily challenge".to_string(),
+            &CODE409,
+        )),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute count daily games query".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Ranks the pairs who finished the daily challenge of ```date```.
+/// Pairs who guessed the word are ranked first, then pairs with fewer guesses, then pairs who were quicker.
+///
+/// # Errors
+///
+/// Will throw a 400 error if ```limit``` is out of range,
+/// or a 500 error if failed to query the games.
+pub async fn daily_board(
+    client: &MongoClient,
+    date: NaiveDate,
+    limit: i32,
+) -> GraphqlServerResult<Vec<DailyBoardEntry>> {
+    if !(1..=MAX_DAILY_BOARD_SIZE).contains(&limit) {
+        return Err(GraphqlServerError::new(
+            format!("limit should be between 1 and {}", MAX_DAILY_BOARD_SIZE),
+            &CODE400,
+        ));
+    }
+
+    let games: Collection<Document> = game_collection(client);
+
+    let aggregate_error = || {
+        GraphqlServerError::new(
+            "Failed to execute daily board aggregation".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = games
+        .aggregate(board_pipeline(date, limit), None)
+        .await
+        .map_err(|_| aggregate_error())?;
+
+    let mut finished = Vec::new();
+    while cursor.advance().await.map_err(|_| aggregate_error())? {
+        let document = cursor
+            .deserialize_current()
+            .map_err(|_| aggregate_error())?;
+        let game: Game = from_document(document).map_err(|_| aggregate_error())?;
+        finished.push(game);
+    }
+
+    Ok(rank(&finished))
+}
+
+/// Creates the aggregation pipeline that finds the top ```limit``` pairs who finished the daily challenge of ```date```,
+/// sorted the same way as ```rank```, so only the games on the board are loaded.
+/// A daily challenge only has the one round, so it is always the current round.
+fn board_pipeline(date: NaiveDate, limit: i32) -> Vec<Document> {
+    vec![
+        doc! {"$match": {
+            "config.daily_challenge": date.to_string(),
+            "status": to_bson(&GameStatus::Finished).expect("status should serialize"),
+            "current_round.ended_at": {"$ne": null},
+        }},
+        doc! {"$addFields": {
+            "unsolved": {"$eq": [{"$ifNull": ["$current_round.solved_by", null]}, null]},
+            "guesses": {"$size": "$current_round.turns"},
+            "seconds": {"$trunc": {"$divide": [
+                {"$subtract": ["$current_round.ended_at", "$current_round.started_at"]},
+                1000,
+            ]}},
+        }},
+        doc! {"$sort": {"unsolved": 1, "guesses": 1, "seconds": 1, "_id": 1}},
+        doc! {"$limit": limit as i64},
+    ]
+}
+
+/// Ranks the results of finished daily challenge ```games```.
+fn rank(games: &[Game]) -> Vec<DailyBoardEntry> {
+    let mut entries: Vec<DailyBoardEntry> = games
+        .iter()
+        .filter_map(|game| {
+            let round = game.all_rounds().last()?;
+
+            Some(DailyBoardEntry {
+                rank: 0,
+                game_id: game.id(),
+                p1_id: game.player_id(Player::P1).map(str::to_string),
+                p2_id: game.player_id(Player::P2).map(str::to_string),
+                solved_by: round.solved_by(),
+                guesses: round.turns().len() as i32,
+                seconds: round.time_taken()?.num_seconds() as i32,
+            })
+        })
+        .collect();
+
+    entries.sort_by(|a, b| {
+        (a.solved_by.is_none(), a.guesses, a.seconds, &a.game_id).cmp(&(
+            b.solved_by.is_none(),
+            b.guesses,
+            b.seconds,
+            &b.game_id,
+        ))
+    });
+
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.rank = i as i32 + 1;
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{NewTurn, UpdateRound};
+
+    fn daily_game(guesses: &[(&str, i32)]) -> Game {
+        let date = NaiveDate::from_ymd_opt(2024, 7, 17).unwrap();
+        let mut game = Game::new_daily_challenge("words", date);
+
+        for (i, (guess, letter_state)) in guesses.iter().enumerate() {
+            let next_player = if i % 2 == 0 { Player::P2 } else { Player::P1 };
+            let update = UpdateRound::new(
+                &game.id(),
+                NewTurn::new(guess, *letter_state),
+                0,
+                next_player,
+            );
+            game.play_turn(update).unwrap();
+        }
+
+        game
+    }
+
+    #[test]
+    fn daily_word_is_same_all_day() {
+        let date = NaiveDate::from_ymd_opt(2024, 7, 17).unwrap();
+        let next_day = date.succ_opt().unwrap();
+
+        assert_eq!(
+            daily_word_index("secret", date, 5000),
+            daily_word_index("secret", date, 5000)
+        );
+        assert!(daily_word_index("secret", date, 5000) < 5000);
+
+        // a different day or secret should almost never give the same word
+        assert_ne!(
+            daily_word_index("secret", date, u64::MAX),
+            daily_word_index("secret", next_day, u64::MAX)
+        );
+        assert_ne!(
+            daily_word_index("secret", date, u64::MAX),
+            daily_word_index("other secret", date, u64::MAX)
+        );
+    }
+
+    #[test]
+    fn solvers_rank_before_fewer_guesses() {
+        let solved = 0b11_11_11_11_11;
+        let miss = 0b01_01_01_10_01;
+
+        let quick_solve = daily_game(&[("words", solved)]);
+        let slow_solve = daily_game(&[("crane", miss), ("words", solved)]);
+        let no_solve = daily_game(&[("crane", miss); 6]);
+
+        let entries = rank(&[no_solve, slow_solve, quick_solve]);
+
+        let guesses: Vec<i32> = entries.iter().map(|entry| entry.guesses).collect();
+        assert_eq!(guesses, vec![1, 2, 6]);
+        assert_eq!(entries[0].rank, 1);
+        assert_eq!(entries[2].solved_by, None);
+    }
+}
@@ -0,0 +1,225 @@
+use juniper::GraphQLEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+use super::{
+    errors::GraphqlServerResult,
+    letters::letters,
+    words::{all_words, Language},
+    MongoClient,
+};
+
+// This file contains the difficulty of target words, which the dictionary is split into tiers by
+// A word is harder to guess the rarer its letters are, the more letters it repeats,
+// and the more words differ from it by only one letter, as guesses can get stuck on them (e.g. "light", "might", "night")
+
+/// The number of neighbouring words after which more neighbours don't make a word any harder.
+const MAX_COUNTED_NEIGHBOURS: usize = 10;
+
+/// How hard the target words of a game are to guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, GraphQLEnum, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// Words with common letters and few similar words.
+    Easy,
+    /// Words in between easy and hard.
+    Medium,
+    /// Words with rare or repeated letters, or many similar words.
+    Hard,
+}
+
+/// Scores how hard the words of a dictionary are to guess.
+#[derive(Debug)]
+pub struct DifficultyScorer {
+    /// The share of words that have each letter.
+    letter_frequencies: HashMap<String, f64>,
+
+    /// The number of words matching each word with one letter blanked out.
+    patterns: HashMap<String, usize>,
+}
+
+/// Gets ```word``` with the letter at ```index``` blanked out.
+fn pattern(word: &str, index: usize) -> String {
+    letters(word)
+        .into_iter()
+        .enumerate()
+        .map(|(i, letter)| if i == index { "_" } else { letter })
+        .collect()
+}
+
+impl DifficultyScorer {
+    /// Creates a scorer for the words of ```dictionary```.
+    pub fn new(dictionary: &[String]) -> Self {
+        let mut letter_counts: HashMap<String, usize> = HashMap::new();
+        let mut patterns: HashMap<String, usize> = HashMap::new();
+
+        for word in dictionary {
+            let word_letters = letters(word);
+
+            let distinct: BTreeSet<&str> = word_letters.iter().copied().collect();
+            for letter in distinct {
+                *letter_counts.entry(letter.to_string()).or_default() += 1;
+            }
+
+            for i in 0..word_letters.len() {
+                *patterns.entry(pattern(word, i)).or_default() += 1;
+            }
+        }
+
+        let word_count = dictionary.len().max(1) as f64;
+        let letter_frequencies = letter_counts
+            .into_iter()
+            .map(|(letter, count)| (letter, count as f64 / word_count))
+            .collect();
+
+        DifficultyScorer {
+            letter_frequencies,
+            patterns,
+        }
+    }
+
+    /// Scores how hard ```word``` is to guess, from 0 (easiest) to 3 (hardest).
+    /// Letter rarity, repeated letters and neighbouring words each add up to 1.
+    pub fn score(&self, word: &str) -> f64 {
+        let word_letters = letters(word);
+        let length = word_letters.len();
+        if length == 0 {
+            return 0.0;
+        }
+
+        let distinct: BTreeSet<&str> = word_letters.into_iter().collect();
+
+        let commonness = distinct
+            .iter()
+            .map(|letter| self.letter_frequencies.get(*letter).copied().unwrap_or(0.0))
+            .sum::<f64>()
+            / distinct.len() as f64;
+        let rarity = 1.0 - commonness;
+
+        let repeats = (length - distinct.len()) as f64 / length as f64;
+
+        // every pattern matches the word itself, so that match isn't a neighbour
+        let neighbours: usize = (0..length)
+            .map(|i| self.patterns.get(&pattern(word, i)).map_or(0, |n| n - 1))
+            .sum();
+        let neighbour_share =
+            neighbours.min(MAX_COUNTED_NEIGHBOURS) as f64 / MAX_COUNTED_NEIGHBOURS as f64;
+
+        rarity + repeats + neighbour_share
+    }
+}
+
+/// The words of a dictionary split into difficulty tiers.
+#[derive(Debug, Default)]
+pub struct DifficultyTiers {
+    tiers: HashMap<Difficulty, Vec<String>>,
+}
+
+impl DifficultyTiers {
+    /// Splits ```dictionary``` into tiers by score, a third of the words in each.
+    pub fn new(dictionary: &[String]) -> Self {
+        let scorer = DifficultyScorer::new(dictionary);
+
+        let mut scored: Vec<(f64, &String)> = dictionary
+            .iter()
+            .map(|word| (scorer.score(word), word))
+            .collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+
+        let tier_size = scored.len().div_ceil(3);
+        let mut tiers = HashMap::new();
+        for (difficulty, chunk) in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+            .into_iter()
+            .zip(scored.chunks(tier_size.max(1)))
+        {
+            let words = chunk.iter().map(|(_, word)| word.to_string()).collect();
+            tiers.insert(difficulty, words);
+        }
+
+        DifficultyTiers { tiers }
+    }
+
+    /// Gets the words of the tier of ```difficulty```.
+    pub fn words(&self, difficulty: Difficulty) -> &[String] {
+        self.tiers.get(&difficulty).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// The tiers of the English dictionary, worked out the first time they are needed.
+static ENGLISH_TIERS: OnceLock<DifficultyTiers> = OnceLock::new();
+
+/// The tiers of the Spanish dictionary, worked out the first time they are needed.
+static SPANISH_TIERS: OnceLock<DifficultyTiers> = OnceLock::new();
+
+/// The tiers of the German dictionary, worked out the first time they are needed.
+static GERMAN_TIERS: OnceLock<DifficultyTiers> = OnceLock::new();
+
+/// Gets the tiers of the dictionary of ```language```.
+/// The dictionary is only read the first time, as scoring every word is slow.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to read the dictionary.
+pub async fn dictionary_tiers(
+    client: &MongoClient,
+    language: Language,
+) -> GraphqlServerResult<&'static DifficultyTiers> {
+    let tiers = match language {
+        Language::English => &ENGLISH_TIERS,
+        Language::Spanish => &SPANISH_TIERS,
+        Language::German => &GERMAN_TIERS,
+    };
+    if let Some(tiers) = tiers.get() {
+        return Ok(tiers);
+    }
+
+    let dictionary = all_words(client, language).await?;
+
+    // another request may have set the tiers while the dictionary was read, which is fine as they are the same
+    Ok(tiers.get_or_init(|| DifficultyTiers::new(&dictionary)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dictionary(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn harder_words_score_higher() {
+        let words = dictionary(&[
+            "crane", "crate", "trace", "react", "light", "might", "night", "sight", "fight",
+            "jazzy", "fuzzy",
+        ]);
+        let scorer = DifficultyScorer::new(&words);
+
+        // repeated rare letters
+        assert!(scorer.score("jazzy") > scorer.score("crane"));
+        // many neighbours
+        assert!(scorer.score("night") > scorer.score("react"));
+    }
+
+    #[test]
+    fn tiers_split_dictionary_in_thirds() {
+        let words = dictionary(&[
+            "crane", "trace", "react", "light", "night", "fight", "jazzy",
+        ]);
+        let tiers = DifficultyTiers::new(&words);
+
+        assert_eq!(tiers.words(Difficulty::Easy).len(), 3);
+        assert_eq!(tiers.words(Difficulty::Medium).len(), 3);
+        assert_eq!(tiers.words(Difficulty::Hard), ["jazzy".to_string()]);
+    }
+
+    #[test]
+    fn letters_with_accents_are_one_letter() {
+        let words = dictionary(&["años", "anos", "paño"]);
+        let scorer = DifficultyScorer::new(&words);
+
+        // "ñ" is only in two words, while "n" is only in one
+        assert_eq!(scorer.letter_frequencies["ñ"], 2.0 / 3.0);
+        assert_eq!(pattern("años", 1), "a_os");
+    }
+}
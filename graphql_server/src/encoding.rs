@@ -0,0 +1,127 @@
+use juniper::GraphQLEnum;
+use serde::{Deserialize, Serialize};
+
+use super::letters::letters;
+
+// This file contains the encoding of letter states, the same encoding clients use
+// Each letter of a guess takes 2 bits, starting from the least significant bits for the first letter:
+// Green (letter in correct spot): 0b11
+// Yellow (letter in word, but incorrect spot): 0b10
+// Black (letter not in word): 0b01
+// Unused bits: 0b00
+// The letterpool of a round has a bit for each letter from a to z, starting from the least significant bit for "a",
+// which is set once a guess shows the letter isn't in the word, or a hint eliminates the letter
+// Other letters, such as accented letters, aren't in the letterpool
+
+/// The state of a letter of a guess, compared to the target word.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, GraphQLEnum, Serialize, Deserialize,
+)]
+pub enum LetterState {
+    /// The letter is not in the word.
+    Black = 0b01,
+    /// The letter is in the word, but not in this spot.
+    Yellow = 0b10,
+    /// The letter is in this spot of the word.
+    Green = 0b11,
+}
+
+/// Encodes the states of the letters of a guess.
+pub fn encode(letter_states: &[LetterState]) -> i32 {
+    letter_states
+        .iter()
+        .enumerate()
+        .fold(0, |encoded, (i, state)| {
+            encoded | (*state as i32) << (2 * i)
+        })
+}
+
+/// Decodes the states of the ```word_length``` letters of a guess.
+/// Returns None if any of the letters has no state.
+pub fn decode(encoded: i32, word_length: usize) -> Option<Vec<LetterState>> {
+    (0..word_length)
+        .map(|i| match (encoded >> (2 * i)) & 0b11 {
+            0b01 => Some(LetterState::Black),
+            0b10 => Some(LetterState::Yellow),
+            0b11 => Some(LetterState::Green),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Adds the letters of ```guess``` that ```letter_states``` show aren't in the word to ```letterpool```.
+/// A letter is only ruled out if none of its spots in the guess are green or yellow,
+/// as a black letter can also mean the word has the letter fewer times than the guess.
+pub fn rule_out_letters(letterpool: i32, guess: &str, letter_states: &[LetterState]) -> i32 {
+    let guess_letters = letters(guess);
+    let in_word = |letter: &str| {
+        guess_letters
+            .iter()
+            .zip(letter_states)
+            .any(|(other, state)| *other == letter && *state != LetterState::Black)
+    };
+
+    guess_letters
+        .iter()
+        .filter(|letter| !in_word(letter))
+        .filter_map(|letter| letterpool_bit(letter))
+        .fold(letterpool, |letterpool, bit| letterpool | 1 << bit)
+}
+
+/// Adds ```eliminated``` letters, which a hint showed aren't in the word, to ```letterpool```.
+pub fn eliminate_letters(letterpool: i32, eliminated: &[String]) -> i32 {
+    eliminated
+        .iter()
+        .filter_map(|letter| letterpool_bit(letter))
+        .fold(letterpool, |letterpool, bit| letterpool | 1 << bit)
+}
+
+/// Gets the bit of ```letter``` in the letterpool, or None if the letter isn't in the letterpool.
+fn letterpool_bit(letter: &str) -> Option<u8> {
+    match letter.as_bytes() {
+        [byte @ b'a'..=b'z'] => Some(byte - b'a'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encoding_matches_clients() {
+        use LetterState::*;
+
+        // "crane" guessed against "words"
+        let states = [Black, Yellow, Black, Black, Black];
+        assert_eq!(encode(&states), 0b01_01_01_10_01);
+        assert_eq!(decode(0b01_01_01_10_01, 5).unwrap(), states);
+    }
+
+    #[test]
+    fn letterpool_rules_out_black_letters() {
+        use LetterState::*;
+
+        // "speed" guessed against "crepe", the second "e" is black as "crepe" only has two
+        let states = [Black, Yellow, Green, Yellow, Black];
+        let letterpool = rule_out_letters(0, "speed", &states);
+        assert_eq!(letterpool, 1 << (b's' - b'a') | 1 << (b'd' - b'a'));
+
+        // letters already ruled out stay ruled out, and accented letters aren't in the letterpool
+        let letterpool = rule_out_letters(letterpool, "años", &[Black, Black, Black, Black]);
+        assert_eq!(letterpool & 1 << (b's' - b'a'), 1 << (b's' - b'a'));
+        assert_eq!(letterpool.count_ones(), 4);
+    }
+
+    #[test]
+    fn eliminated_letters_are_ruled_out() {
+        let eliminated = ["b".to_string(), "ñ".to_string(), "z".to_string()];
+        let letterpool = eliminate_letters(1, &eliminated);
+        assert_eq!(letterpool, 1 | 1 << (b'b' - b'a') | 1 << (b'z' - b'a'));
+    }
+
+    #[test]
+    fn letters_without_state_dont_decode() {
+        assert!(decode(0b11_11, 3).is_none());
+    }
+}
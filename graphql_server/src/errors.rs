@@ -35,10 +35,16 @@ impl From<&StaticCode> for Code {
 
 /// 400: Bad Request
 pub static CODE400: StaticCode = (400, "Bad Request");
+/// 403: Forbidden
+pub static CODE403: StaticCode = (403, "Forbidden");
 /// 404: Not Found
 pub static CODE404: StaticCode = (404, "Not Found");
-/// 404: Not Found
+/// 409: Conflict
+pub static CODE409: StaticCode = (409, "Conflict");
+/// 422: Unprocessable Content
 pub static CODE422: StaticCode = (422, "Unprocessable Content");
+/// 429: Too Many Requests
+pub static CODE429: StaticCode = (429, "Too Many Requests");
 /// 500: Internal Server Error
 pub static CODE500: StaticCode = (500, "Internal Server Error");
 
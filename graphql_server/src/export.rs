@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE400, CODE422},
+    hints::HintKind,
+    models::{Game, GameConfig, GameStatus, Player},
+};
+
+// This file contains the JSON format games are exported in, so they can be shared and analysed
+//
+// A game is exported as a document like:
+// {
+//   "format_version": 1,
+//   "id": "<id of the exported game>",
+//   "config": { "max_guesses": 6, "daily_challenge": "<YYYY-MM-DD>" | null,
+//               "difficulty": "Easy" | "Medium" | "Hard" | null, "word_pack": "<pack name>" | null,
+//               "language": "English" | "Spanish" | "German", "mode": "Versus" | "CoOp" | "Race" | "SecretWord",
+//               "best_of": 5 | null,
+//               "scoring": { "points_by_guesses": [1], "own_turn_bonus": 0, "reveal_penalty": 0, "hint_cost": 1 },
+//               "bot": "RandomValid" | "LetterFrequency" | "MaxEntropy" | null },
+//   "p1_id": "<player id>" | null, "p2_id": "<player id>" | null,
+//   "p1_points": 1, "p2_points": 0, "team_points": 0,
+//   "status": "Active" | "Finished",
+//   "created_at": "<RFC 3339 time>", "finished_at": "<RFC 3339 time>" | null,
+//   "rounds": [{
+//     "target_word": "words",
+//     "current_player": "P1" | "P2",
+//     "letterpool_state": 0,
+//     "started_at": "<RFC 3339 time>", "ended_at": "<RFC 3339 time>" | null,
+//     "solved_by": "P1" | "P2" | null,
+//     "turns": [{ "player": "P1", "guessed_word": "crane", "letter_state": 341, "played_at": "<RFC 3339 time>" }],
+//     "boards": [{ "player": "P1", "letterpool_state": 0 }],
+//     "setter": "P1" | "P2" | null,
+//     "hints": [{ "player": "P1", "kind": "RevealLetter" | "EliminateLetters", "position": 0 | null,
+//                 "letters": ["w"], "requested_at": "<RFC 3339 time>" }]
+//   }]
+// }
+//
+// Rounds are in the order they were played, the last round is the round being played when exported.
+// Letter states use the same encoding clients send with a turn.
+// Points are checked against the rounds scored with the scoring rules, less the cost of every hint, see ```scoring```.
+// Co-op games score team points instead of player points, and guesses waiting to be confirmed aren't exported.
+// Only race rounds have boards, a board's turns are the round's turns made by its player.
+// Only secret word rounds have a setter, and games can't be exported while the word of a round is hidden.
+// Words are normalized (NFC), and a letter with an accent is one letter, see ```letters```.
+
+/// The version of the export format made by this server.
+/// Increase it when the format changes in a way older importers can't read.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// A turn of an exported game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnExport {
+    /// The player who made the turn.
+    pub player: Player,
+    /// The word guessed.
+    pub guessed_word: String,
+    /// The encoded states of each letter of the guess.
+    pub letter_state: i32,
+    /// When the turn was played.
+    pub played_at: DateTime<Utc>,
+}
+
+/// A round of an exported game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundExport {
+    /// The word players were trying to guess.
+    pub target_word: String,
+    /// The player whose turn it is, or who made the last turn if the round has ended.
+    pub current_player: Player,
+    /// The encoded state of all the letters after the last turn.
+    pub letterpool_state: i32,
+    /// When the round started.
+    pub started_at: DateTime<Utc>,
+    /// When the round ended, if it has.
+    pub ended_at: Option<DateTime<Utc>>,
+    /// The player who guessed the target word, if anyone did.
+    pub solved_by: Option<Player>,
+    /// The turns of the round in the order they were played.
+    pub turns: Vec<TurnExport>,
+    /// The boards of the players of a race, the rest of each board is worked out from the turns.
+    #[serde(default)]
+    pub boards: Vec<BoardExport>,
+    /// The player who set the word of a secret word round.
+    #[serde(default)]
+    pub setter: Option<Player>,
+    /// The hints given in the round in the order they were requested.
+    #[serde(default)]
+    pub hints: Vec<HintExport>,
+}
+
+/// A hint given in a round of an exported game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HintExport {
+    /// The player who requested the hint.
+    pub player: Player,
+    /// What the hint gives away.
+    pub kind: HintKind,
+    /// The spot of the letter revealed, if the hint reveals a letter.
+    pub position: Option<i32>,
+    /// The letter revealed, or the letters eliminated.
+    pub letters: Vec<String>,
+    /// When the hint was requested.
+    pub requested_at: DateTime<Utc>,
+}
+
+/// A player's board in a round of an exported race game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardExport {
+    /// The player guessing on the board.
+    pub player: Player,
+    /// The encoded state of all the letters on the board.
+    pub letterpool_state: i32,
+}
+
+/// An exported game, see the top of this file for the format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameExport {
+    /// The version of the format, see ```EXPORT_FORMAT_VERSION```.
+    pub format_version: u32,
+    /// The id of the game that was exported.
+    pub id: String,
+    /// The settings the game was played with.
+    pub config: GameConfig,
+    /// The id of the profile of player 1, if they had one.
+    pub p1_id: Option<String>,
+    /// The id of the profile of player 2, if they had one.
+    pub p2_id: Option<String>,
+    /// The points of player 1.
+    pub p1_points: i32,
+    /// The points of player 2.
+    pub p2_points: i32,
+    /// The points the players of a co-op game scored together.
+    #[serde(default)]
+    pub team_points: i32,
+    /// Whether the game was still being played.
+    pub status: GameStatus,
+    /// When the game was created.
+    pub created_at: DateTime<Utc>,
+    /// When the game ended, if it has.
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Every round of the game in the order they were played.
+    pub rounds: Vec<RoundExport>,
+}
+
+/// Exports ```game``` as a JSON document.
+pub fn export_json(game: &Game) -> String {
+    serde_json::to_string_pretty(&game.to_export()).expect("export should serialize")
+}
+
+/// Reads a game from a JSON document made by ```export_json```.
+/// The game read has a new id, so the same document can be imported more than once.
+///
+/// # Errors
+///
+/// Will throw a 400 error if ```json``` isn't an export document,
+/// or a 422 error if the format version isn't supported or the game isn't valid (see ```Game::from_export```).
+pub fn import_json(json: &str) -> GraphqlServerResult<Game> {
+    let export: GameExport = serde_json::from_str(json).map_err(|e| {
+        GraphqlServerError::new(format!("json isn't a game export: {}", e), &CODE400)
+    })?;
+
+    if export.format_version != EXPORT_FORMAT_VERSION {
+        return Err(GraphqlServerError::new(
+            format!(
+                "export format version {} isn't supported, only version {} is",
+                export.format_version, EXPORT_FORMAT_VERSION
+            ),
+            &CODE422,
+        ));
+    }
+
+    let mut game = Game::from_export(export)?;
+    game.new_id();
+    Ok(game)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{NewTurn, UpdateRound};
+
+    fn play(game: &mut Game, guess: &str, letter_state: i32, next_player: Player) {
+        let update = UpdateRound::new(
+            &game.id(),
+            NewTurn::new(guess, letter_state),
+            0,
+            next_player,
+        );
+        game.play_turn(update).unwrap();
+    }
+
+    fn played_game() -> Game {
+        let mut game = Game::new_game("words");
+        game.set_players(Some("me".to_string()), None).unwrap();
+
+        play(&mut game, "crane", 0b01_01_10_01_01, Player::P2);
+        play(&mut game, "words", 0b11_11_11_11_11, Player::P1);
+        game.start_round("crane").unwrap();
+        play(&mut game, "crate", 0b11_11_11_01_11, Player::P2);
+
+        game.finish();
+        game
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let game = played_game();
+
+        let json = export_json(&game);
+        let imported = import_json(&json).unwrap();
+
+        assert_ne!(imported.id(), game.id());
+        assert_eq!(
+            export_json(&imported).replace(&imported.id(), &game.id()),
+            json
+        );
+    }
+
+    #[test]
+    fn import_checks_format_version() {
+        let mut export = played_game().to_export();
+        export.format_version = EXPORT_FORMAT_VERSION + 1;
+
+        let json = serde_json::to_string(&export).unwrap();
+        assert!(import_json(&json).is_err());
+        assert!(import_json("{}").is_err());
+    }
+
+    #[test]
+    fn import_checks_game_is_valid() {
+        let game = played_game();
+
+        let mut extra_point = game.to_export();
+        extra_point.p1_points += 1;
+        assert!(Game::from_export(extra_point).is_err());
+
+        let mut wrong_length = game.to_export();
+        wrong_length.rounds[0].turns[0].guessed_word = "cranes".to_string();
+        assert!(Game::from_export(wrong_length).is_err());
+
+        let mut no_rounds = game.to_export();
+        no_rounds.rounds.clear();
+        assert!(Game::from_export(no_rounds).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use juniper::GraphQLEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::{letters::letters, models::Hint};
+
+// This file contains the hints players stuck on a word can request, at a cost of points
+// Only the guesses and hints a player can see are taken into account, which in a race are those on their own board
+// Hints are worked out from the target word on the server, so they don't rely on the letter states clients send
+
+/// The max number of letters eliminated by a hint.
+pub const HINT_ELIMINATED_LETTERS: usize = 3;
+
+/// What a hint gives away.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, GraphQLEnum, Serialize, Deserialize)]
+pub enum HintKind {
+    /// Reveals the letter in a spot of the word that hasn't been guessed correctly yet.
+    #[default]
+    RevealLetter,
+    /// Eliminates letters that aren't in the word and haven't been guessed yet.
+    EliminateLetters,
+}
+
+/// Gets the spots of ```answer``` that no guess got right and no hint revealed, in order.
+pub fn unrevealed_positions(answer: &str, guesses: &[&str], hints: &[&Hint]) -> Vec<usize> {
+    let guess_letters: Vec<Vec<&str>> = guesses.iter().map(|guess| letters(guess)).collect();
+
+    letters(answer)
+        .into_iter()
+        .enumerate()
+        .filter(|(i, letter)| {
+            !guess_letters
+                .iter()
+                .any(|guess| guess.get(*i) == Some(letter))
+        })
+        .filter(|(i, _)| !hints.iter().any(|hint| hint.position() == Some(*i as i32)))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Picks up to ```HINT_ELIMINATED_LETTERS``` letters of ```alphabet``` to eliminate,
+/// which aren't in ```answer```, any of ```guesses```, or eliminated by ```hints``` already.
+/// ```seed``` picks the letters at random, so the letters eliminated don't give away where the word is in the alphabet.
+/// The letters picked are in the order of the alphabet.
+pub fn letters_to_eliminate(
+    alphabet: &[&str],
+    answer: &str,
+    guesses: &[&str],
+    hints: &[&Hint],
+    seed: u64,
+) -> Vec<String> {
+    let mut known: HashSet<&str> = letters(answer).into_iter().collect();
+    known.extend(guesses.iter().flat_map(|guess| letters(guess)));
+    for hint in hints {
+        known.extend(hint.letters().iter().map(String::as_str));
+    }
+
+    let mut unused: Vec<&str> = alphabet
+        .iter()
+        .copied()
+        .filter(|letter| !known.contains(letter))
+        .collect();
+
+    // each pick takes the next digit of the seed, in the base of the letters left to pick from
+    let mut seed = seed;
+    let mut picked = Vec::new();
+    while picked.len() < HINT_ELIMINATED_LETTERS && !unused.is_empty() {
+        let left = unused.len() as u64;
+        picked.push(unused.remove((seed % left) as usize));
+        seed /= left;
+    }
+
+    alphabet
+        .iter()
+        .filter(|letter| picked.contains(letter))
+        .map(|letter| letter.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn revealed_positions_are_skipped() {
+        // "boats" gets the "o" and "s" right
+        let positions = unrevealed_positions("words", &["boats"], &[]);
+        assert_eq!(positions, vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn eliminated_letters_are_unknown() {
+        let alphabet = ["a", "b", "c", "d", "e", "f", "ñ"];
+
+        let eliminated = letters_to_eliminate(&alphabet, "dados", &["caber"], &[], 0);
+        assert_eq!(eliminated, vec!["f", "ñ"]);
+    }
+
+    #[test]
+    fn eliminated_letters_are_picked_by_seed() {
+        let alphabet = letters("abcdefghijklmnopqrstuvwxyz");
+
+        let first = letters_to_eliminate(&alphabet, "words", &[], &[], 0);
+        assert_eq!(first, vec!["a", "b", "c"]);
+
+        // the seed picks other unused letters, not the first ones of the alphabet
+        let other = letters_to_eliminate(&alphabet, "words", &[], &[], 20);
+        assert_eq!(other, vec!["a", "b", "z"]);
+    }
+}
@@ -0,0 +1,334 @@
+use bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use chrono::{DateTime, Utc};
+use rocket_db_pools::mongodb::{
+    bson::{doc, from_bson, to_bson, Bson},
+    error::{ErrorKind, WriteFailure},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::time::Duration;
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE400, CODE409, CODE422, CODE500},
+    MongoClient, IDEMPOTENCY_KEY_TTL,
+};
+
+// This file contains the idempotency keys that let clients safely retry mutations
+// A mutation given a key is only applied once, retries with the same key get the original result
+// Keys should be unique per client, e.g. a uuid, as a key reused with different arguments is refused
+
+/// The max length of an idempotency key.
+pub const MAX_KEY_LENGTH: usize = 128;
+
+/// How many seconds a key is claimed for while its mutation is applied,
+/// after which the claim is treated as left behind by a request that never finished.
+pub const CLAIM_LEASE_SECONDS: i64 = 60;
+
+/// The error code mongodb uses when inserting a duplicate key.
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+/// A record of a mutation made with an idempotency key.
+#[derive(Debug, Serialize, Deserialize)]
+struct IdempotencyRecord {
+    /// Made from the scope, mutation and key, see ```record_id```.
+    _id: String,
+
+    /// The game the key is for, or the mutation name if the mutation doesn't have a game yet.
+    scope: String,
+
+    /// The fingerprint of the arguments the mutation was given, see ```IdempotencyKey```.
+    /// Is empty for records saved before arguments were fingerprinted.
+    #[serde(default)]
+    fingerprint: String,
+
+    /// The result of the mutation.
+    /// Is null while the mutation is still being applied.
+    result: Option<Bson>,
+
+    /// When the key was claimed. The record is removed by the database after the TTL.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    created_at: DateTime<Utc>,
+}
+
+/// A key given to a mutation, along with a fingerprint of the arguments the mutation was given,
+/// so the key can't be reused for a mutation with different arguments.
+#[derive(Debug, PartialEq)]
+pub struct IdempotencyKey {
+    key: String,
+    fingerprint: String,
+}
+
+impl IdempotencyKey {
+    /// Checks ```key``` and fingerprints ```arguments```, which should be every argument of the mutation.
+    /// Returns None if no key was given.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 400 error if the key is empty or longer than ```MAX_KEY_LENGTH```,
+    /// or a 500 error if failed to serialize the arguments.
+    pub fn new<A: Serialize>(
+        key: Option<String>,
+        arguments: &A,
+    ) -> GraphqlServerResult<Option<Self>> {
+        let Some(key) = key else {
+            return Ok(None);
+        };
+        let key = check_key(key)?;
+
+        let arguments = serde_json::to_vec(arguments).map_err(|_| {
+            GraphqlServerError::new("Failed to serialize arguments".to_string(), &CODE500)
+        })?;
+        let fingerprint = Sha256::digest(arguments)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Ok(Some(IdempotencyKey { key, fingerprint }))
+    }
+}
+
+/// Gets the collection of idempotency records.
+fn record_collection(client: &MongoClient) -> Collection<IdempotencyRecord> {
+    client.database("Games").collection("IdempotencyKeys")
+}
+
+/// Creates the id of a record. Keys are per scope and per mutation.
+fn record_id(scope: &str, mutation: &str, key: &str) -> String {
+    format!("{}:{}:{}", scope, mutation, key)
+}
+
+/// Creates the index that removes records once they are older than the TTL.
+pub async fn create_indexes(client: &MongoClient) -> GraphqlServerResult<()> {
+    let ttl_index = IndexModel::builder()
+        .keys(doc! {"created_at": 1})
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(*IDEMPOTENCY_KEY_TTL))
+                .build(),
+        )
+        .build();
+
+    match record_collection(client).create_index(ttl_index, None).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to create idempotency key index".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Applies ```mutation``` at most once for ```key```, returning its result.
+///
+/// If ```key``` is None, the mutation is simply applied.
+/// Otherwise, the result is stored under ```scope``` and ```mutation_name```,
+/// and later calls with the same key and arguments return the stored result without applying the mutation again.
+/// If the mutation fails, nothing is stored, so it can be retried with the same key.
+/// If the mutation was applied but its result couldn't be stored, the key is released,
+/// so a retry isn't refused until the key expires.
+///
+/// # Errors
+///
+/// Will throw a 409 error if a mutation with the same key is still being applied,
+/// a 422 error if the key was used with different arguments,
+/// or any error thrown by ```mutation```.
+pub async fn apply_once<T, F, Fut>(
+    client: &MongoClient,
+    scope: &str,
+    mutation_name: &str,
+    key: Option<IdempotencyKey>,
+    mutation: F,
+) -> GraphqlServerResult<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = GraphqlServerResult<T>>,
+{
+    let Some(key) = key else {
+        return mutation().await;
+    };
+
+    let records = record_collection(client);
+    let id = record_id(scope, mutation_name, &key.key);
+
+    // claim the key before applying, so a concurrent retry can't also apply the mutation
+    if let Some(result) = claim(&records, &id, scope, &key.fingerprint).await? {
+        return from_bson(result).map_err(|_| {
+            GraphqlServerError::new("Failed to read stored result".to_string(), &CODE500)
+        });
+    }
+
+    let result = mutation().await;
+
+    let stored = match &result {
+        Ok(value) => match to_bson(value) {
+            Ok(value) => {
+                let update = doc! {"$set": {"result": value}};
+                records
+                    .update_one(doc! {"_id": &id}, update, None)
+                    .await
+                    .is_ok()
+            }
+            Err(_) => false,
+        },
+        Err(_) => false,
+    };
+
+    if !stored {
+        if result.is_ok() {
+            warn!(
+                "Failed to store the result of {}, releasing its idempotency key",
+                id
+            );
+        }
+        // release the key so the mutation can be retried
+        let _ = records.delete_one(doc! {"_id": &id}, None).await;
+    }
+
+    result
+}
+
+/// Claims the key of the record with ```id``` for a mutation whose arguments have ```fingerprint```.
+/// Returns the stored result if the key was already used by a mutation that was applied,
+/// or None once the key is claimed.
+/// A claim that has been held for longer than ```CLAIM_LEASE_SECONDS``` was left by a request that never finished,
+/// so it is taken over.
+///
+/// # Errors
+///
+/// Will throw a 409 error if a mutation with the same key is still being applied,
+/// a 422 error if the key was used with different arguments,
+/// or a 500 error if failed to save or find the key.
+async fn claim(
+    records: &Collection<IdempotencyRecord>,
+    id: &str,
+    scope: &str,
+    fingerprint: &str,
+) -> GraphqlServerResult<Option<Bson>> {
+    let now = Utc::now();
+    let claim = IdempotencyRecord {
+        _id: id.to_string(),
+        scope: scope.to_string(),
+        fingerprint: fingerprint.to_string(),
+        result: None,
+        created_at: now,
+    };
+
+    match records.insert_one(&claim, None).await {
+        Ok(_) => return Ok(None),
+        Err(e) => match *e.kind {
+            ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                if write_error.code == DUPLICATE_KEY_CODE => {}
+            _ => {
+                return Err(GraphqlServerError::new(
+                    "Failed to save idempotency key".to_string(),
+                    &CODE500,
+                ))
+            }
+        },
+    }
+
+    let still_processing = || {
+        GraphqlServerError::new(
+            "A request with this idempotency key is still being processed, retry later".to_string(),
+            &CODE409,
+        )
+    };
+
+    let record = records
+        .find_one(doc! {"_id": id}, None)
+        .await
+        .map_err(|_| {
+            GraphqlServerError::new(
+                "Failed to execute find idempotency key query".to_string(),
+                &CODE500,
+            )
+        })?
+        // the record was removed since the claim failed, so the request can simply be retried
+        .ok_or_else(still_processing)?;
+
+    if !record.fingerprint.is_empty() && record.fingerprint != fingerprint {
+        return Err(GraphqlServerError::new(
+            "idempotency key was already used with different arguments".to_string(),
+            &CODE422,
+        ));
+    }
+
+    if let Some(result) = record.result {
+        return Ok(Some(result));
+    }
+
+    if now - record.created_at < chrono::Duration::seconds(CLAIM_LEASE_SECONDS) {
+        return Err(still_processing());
+    }
+
+    // only take over the claim if no other request took it over first
+    let stale_claim = doc! {
+        "_id": id,
+        "result": Bson::Null,
+        "created_at": bson::DateTime::from_chrono(record.created_at),
+    };
+    let take_over = doc! {"$set": {"created_at": bson::DateTime::from_chrono(now)}};
+
+    match records.update_one(stale_claim, take_over, None).await {
+        Ok(update_result) if update_result.modified_count == 1 => Ok(None),
+        Ok(_) => Err(still_processing()),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to save idempotency key".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Checks that an idempotency key is usable.
+///
+/// # Errors
+///
+/// Will throw a 400 error if the key is empty or longer than ```MAX_KEY_LENGTH```.
+fn check_key(key: String) -> GraphqlServerResult<String> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(GraphqlServerError::new(
+            format!(
+                "idempotency key should be between 1 and {} characters",
+                MAX_KEY_LENGTH
+            ),
+            &CODE400,
+        ));
+    }
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keys_are_per_scope_and_mutation() {
+        let play_turn = record_id("game", "playTurn", "key");
+        let new_round = record_id("game", "newRound", "key");
+        let other_game = record_id("other", "playTurn", "key");
+
+        assert_ne!(play_turn, new_round);
+        assert_ne!(play_turn, other_game);
+    }
+
+    #[test]
+    fn arguments_are_fingerprinted() {
+        let key = || Some("retry-1".to_string());
+
+        let first = IdempotencyKey::new(key(), &("words", 1)).unwrap();
+        assert_eq!(first, IdempotencyKey::new(key(), &("words", 1)).unwrap());
+        assert_ne!(first, IdempotencyKey::new(key(), &("crane", 1)).unwrap());
+        assert_eq!(IdempotencyKey::new(None, &("words", 1)).unwrap(), None);
+    }
+
+    #[test]
+    fn key_length_is_checked() {
+        assert!(check_key(String::new()).is_err());
+        assert!(check_key("a".repeat(MAX_KEY_LENGTH + 1)).is_err());
+        assert!(check_key("retry-1".to_string()).is_ok());
+    }
+}
@@ -0,0 +1,397 @@
+use chrono::{DateTime, Datelike, Duration, Utc};
+use juniper::{GraphQLEnum, GraphQLObject};
+use rocket_db_pools::mongodb::{
+    bson::{doc, from_document, to_bson, Document},
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE400, CODE500},
+    game_collection,
+    models::{GameMode, GameStatus},
+    MongoClient,
+};
+
+// This file contains the leaderboard, which ranks players by the games they finished
+// Co-op games aren't won or lost by either player, so they are ranked on their own leaderboard of teams
+
+/// The default number of players on a leaderboard.
+pub const DEFAULT_LEADERBOARD_SIZE: i32 = 10;
+
+/// The max number of players that can be on a leaderboard.
+pub const MAX_LEADERBOARD_SIZE: i32 = 100;
+
+/// The period of time a leaderboard ranks games from.
+#[derive(Debug, Clone, Copy, PartialEq, GraphQLEnum)]
+pub enum LeaderboardPeriod {
+    /// Games finished today (UTC).
+    Day,
+    /// Games finished this week (UTC), weeks start on Monday.
+    Week,
+    /// All games ever finished.
+    All,
+}
+
+impl LeaderboardPeriod {
+    /// Gets when the period started, or None if the period has no start.
+    pub fn start(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let today = now.date_naive().and_hms_opt(0, 0, 0)?.and_utc();
+
+        match self {
+            LeaderboardPeriod::Day => Some(today),
+            LeaderboardPeriod::Week => {
+                let days_since_monday = now.weekday().num_days_from_monday() as i64;
+                Some(today - Duration::days(days_since_monday))
+            }
+            LeaderboardPeriod::All => None,
+        }
+    }
+}
+
+/// A player's place on a leaderboard.
+#[derive(Debug, GraphQLObject)]
+pub struct LeaderboardEntry {
+    /// The place of the player, starting at 1.
+    rank: i32,
+
+    /// The id of the player's profile.
+    player_id: String,
+
+    /// The display name of the player.
+    /// Is null if the player's profile no longer exists.
+    display_name: Option<String>,
+
+    /// The number of games the player finished in the period.
+    games_played: i32,
+
+    /// The number of those games the player won.
+    wins: i32,
+
+    /// The total points the player earned in those games.
+    points: i32,
+
+    /// The average number of guesses made in the rounds the player solved.
+    /// Is null if the player didn't solve any rounds.
+    average_guesses: Option<f64>,
+}
+
+/// A team's place on the co-op leaderboard.
+#[derive(Debug, GraphQLObject)]
+pub struct CoopLeaderboardEntry {
+    /// The place of the team, starting at 1.
+    rank: i32,
+
+    /// The ids of the profiles of the two players of the team, in alphabetical order.
+    player_ids: Vec<String>,
+
+    /// The number of co-op games the team finished in the period.
+    games_played: i32,
+
+    /// The total points the team earned together in those games.
+    team_points: i32,
+}
+
+/// The totals of a team, as returned by the co-op leaderboard aggregation.
+#[derive(Debug, Deserialize)]
+struct TeamTotals {
+    _id: Vec<String>,
+    games_played: i32,
+    team_points: i32,
+}
+
+/// The totals of a player, as returned by the leaderboard aggregation.
+#[derive(Debug, Deserialize)]
+struct PlayerTotals {
+    _id: String,
+    display_name: Option<String>,
+    games_played: i32,
+    wins: i32,
+    points: i32,
+    solves: i32,
+    solve_guesses: i32,
+}
+
+impl PlayerTotals {
+    /// Converts into an entry with ```rank``` on the leaderboard.
+    fn into_entry(self, rank: i32) -> LeaderboardEntry {
+        let average_guesses = match self.solves {
+            0 => None,
+            solves => Some(self.solve_guesses as f64 / solves as f64),
+        };
+
+        LeaderboardEntry {
+            rank,
+            player_id: self._id,
+            display_name: self.display_name,
+            games_played: self.games_played,
+            wins: self.wins,
+            points: self.points,
+            average_guesses,
+        }
+    }
+}
+
+/// Creates the index used to find the games finished within a period.
+pub async fn create_indexes(client: &MongoClient) -> GraphqlServerResult<()> {
+    let games: Collection<Document> = game_collection(client);
+
+    let finished_index = IndexModel::builder()
+        .keys(doc! {"status": 1, "finished_at": -1})
+        .build();
+
+    match games.create_index(finished_index, None).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to create finished games index".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Ranks the players of the versus and race games finished in ```period```.
+/// Players are ranked by wins, then by points.
+/// Only players with a profile are ranked.
+///
+/// # Errors
+///
+/// Will throw a 400 error if ```limit``` is out of range,
+/// or a 500 error if failed to execute the aggregation.
+pub async fn leaderboard(
+    client: &MongoClient,
+    period: LeaderboardPeriod,
+    limit: i32,
+) -> GraphqlServerResult<Vec<LeaderboardEntry>> {
+    check_limit(limit)?;
+
+    let games: Collection<Document> = game_collection(client);
+    let pipeline = pipeline(period.start(Utc::now()), limit);
+
+    let aggregate_error = || {
+        GraphqlServerError::new(
+            "Failed to execute leaderboard aggregation".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = games
+        .aggregate(pipeline, None)
+        .await
+        .map_err(|_| aggregate_error())?;
+
+    let mut entries = Vec::new();
+    while cursor.advance().await.map_err(|_| aggregate_error())? {
+        let document = cursor
+            .deserialize_current()
+            .map_err(|_| aggregate_error())?;
+        let totals: PlayerTotals = from_document(document).map_err(|_| aggregate_error())?;
+
+        entries.push(totals.into_entry(entries.len() as i32 + 1));
+    }
+
+    Ok(entries)
+}
+
+/// Checks that ```limit``` is a valid number of entries for a leaderboard.
+///
+/// # Errors
+///
+/// Will throw a 400 error if ```limit``` is out of range.
+fn check_limit(limit: i32) -> GraphqlServerResult<()> {
+    if !(1..=MAX_LEADERBOARD_SIZE).contains(&limit) {
+        return Err(GraphqlServerError::new(
+            format!("limit should be between 1 and {}", MAX_LEADERBOARD_SIZE),
+            &CODE400,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Ranks the teams of the co-op games finished in ```period```.
+/// Teams are ranked by team points, then by fewest games played.
+/// Only teams where both players have a profile are ranked.
+///
+/// # Errors
+///
+/// Will throw a 400 error if ```limit``` is out of range,
+/// or a 500 error if failed to execute the aggregation.
+pub async fn coop_leaderboard(
+    client: &MongoClient,
+    period: LeaderboardPeriod,
+    limit: i32,
+) -> GraphqlServerResult<Vec<CoopLeaderboardEntry>> {
+    check_limit(limit)?;
+
+    let games: Collection<Document> = game_collection(client);
+    let pipeline = coop_pipeline(period.start(Utc::now()), limit);
+
+    let aggregate_error = || {
+        GraphqlServerError::new(
+            "Failed to execute co-op leaderboard aggregation".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = games
+        .aggregate(pipeline, None)
+        .await
+        .map_err(|_| aggregate_error())?;
+
+    let mut entries = Vec::new();
+    while cursor.advance().await.map_err(|_| aggregate_error())? {
+        let document = cursor
+            .deserialize_current()
+            .map_err(|_| aggregate_error())?;
+        let totals: TeamTotals = from_document(document).map_err(|_| aggregate_error())?;
+
+        entries.push(CoopLeaderboardEntry {
+            rank: entries.len() as i32 + 1,
+            player_ids: totals._id,
+            games_played: totals.games_played,
+            team_points: totals.team_points,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Creates the filter matching the games finished since ```since```,
+/// either only co-op games or only games the players played against each other.
+fn finished_games(co_op: bool, since: Option<DateTime<Utc>>) -> Document {
+    let co_op_mode = to_bson(&GameMode::CoOp).expect("mode should serialize");
+
+    let mut finished = doc! {
+        "status": to_bson(&GameStatus::Finished).expect("status should serialize"),
+    };
+    if let Some(since) = since {
+        finished.insert("finished_at", doc! {"$gte": since});
+    }
+    // games from before co-op was added have no mode, and are versus games
+    if co_op {
+        finished.insert("config.mode", co_op_mode);
+    } else {
+        finished.insert("config.mode", doc! {"$ne": co_op_mode});
+    }
+
+    finished
+}
+
+/// Creates the aggregation pipeline that totals the results of each team.
+///
+/// The first stage matches on the status and finish time, so it uses the index from ```create_indexes```.
+fn coop_pipeline(since: Option<DateTime<Utc>>, limit: i32) -> Vec<Document> {
+    vec![
+        doc! {"$match": finished_games(true, since)},
+        doc! {"$match": {"p1_id": {"$ne": null}, "p2_id": {"$ne": null}}},
+        // the same two players are the same team, whoever was player 1
+        doc! {"$project": {
+            "team": {"$cond": [
+                {"$lt": ["$p1_id", "$p2_id"]},
+                ["$p1_id", "$p2_id"],
+                ["$p2_id", "$p1_id"],
+            ]},
+            "team_points": "$team_points",
+        }},
+        doc! {"$group": {
+            "_id": "$team",
+            "games_played": {"$sum": 1},
+            "team_points": {"$sum": "$team_points"},
+        }},
+        doc! {"$sort": {"team_points": -1, "games_played": 1, "_id": 1}},
+        doc! {"$limit": limit},
+    ]
+}
+
+/// Creates the aggregation pipeline that totals the results of each player.
+///
+/// The first stage matches on the status and finish time, so it uses the index from ```create_indexes```.
+fn pipeline(since: Option<DateTime<Utc>>, limit: i32) -> Vec<Document> {
+    vec![
+        doc! {"$match": finished_games(false, since)},
+        // one document per player of each game
+        doc! {"$project": {
+            "results": [
+                {"player_id": "$p1_id", "player": "P1", "points": "$p1_points", "opponent_points": "$p2_points"},
+                {"player_id": "$p2_id", "player": "P2", "points": "$p2_points", "opponent_points": "$p1_points"},
+            ],
+            "rounds": {"$concatArrays": [{"$ifNull": ["$rounds", []]}, ["$current_round"]]},
+        }},
+        doc! {"$unwind": "$results"},
+        doc! {"$match": {"results.player_id": {"$ne": null}}},
+        doc! {"$project": {
+            "player_id": "$results.player_id",
+            "points": "$results.points",
+            "win": {"$cond": [{"$gt": ["$results.points", "$results.opponent_points"]}, 1, 0]},
+            "solved": {"$filter": {
+                "input": "$rounds",
+                "as": "round",
+                "cond": {"$eq": ["$$round.solved_by", "$results.player"]},
+            }},
+        }},
+        doc! {"$group": {
+            "_id": "$player_id",
+            "games_played": {"$sum": 1},
+            "wins": {"$sum": "$win"},
+            "points": {"$sum": "$points"},
+            "solves": {"$sum": {"$size": "$solved"}},
+            // in a race, only the player's own guesses are on their board
+            "solve_guesses": {"$sum": {"$sum": {"$map": {
+                "input": "$solved",
+                "as": "round",
+                "in": {"$size": {"$filter": {
+                    "input": "$$round.turns",
+                    "as": "turn",
+                    "cond": {"$or": [
+                        {"$eq": [{"$size": {"$ifNull": ["$$round.boards", []]}}, 0]},
+                        {"$eq": ["$$turn.player", "$$round.solved_by"]},
+                    ]},
+                }}},
+            }}}},
+        }},
+        doc! {"$sort": {"wins": -1, "points": -1, "_id": 1}},
+        doc! {"$limit": limit},
+        doc! {"$lookup": {
+            "from": "Players",
+            "localField": "_id",
+            "foreignField": "_id",
+            "as": "profile",
+        }},
+        doc! {"$set": {"display_name": {"$first": "$profile.display_name"}}},
+        doc! {"$unset": "profile"},
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn periods_start_at_midnight() {
+        // a wednesday
+        let now = Utc.with_ymd_and_hms(2024, 7, 17, 15, 30, 0).unwrap();
+
+        let day = LeaderboardPeriod::Day.start(now).unwrap();
+        let week = LeaderboardPeriod::Week.start(now).unwrap();
+
+        assert_eq!(day, Utc.with_ymd_and_hms(2024, 7, 17, 0, 0, 0).unwrap());
+        assert_eq!(week, Utc.with_ymd_and_hms(2024, 7, 15, 0, 0, 0).unwrap());
+        assert_eq!(LeaderboardPeriod::All.start(now), None);
+    }
+
+    #[test]
+    fn average_guesses_only_counts_solves() {
+        let totals = PlayerTotals {
+            _id: "player".to_string(),
+            display_name: None,
+            games_played: 2,
+            wins: 1,
+            points: 3,
+            solves: 3,
+            solve_guesses: 12,
+        };
+
+        assert_eq!(totals.into_entry(1).average_guesses, Some(4.0));
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::encoding::LetterState;
+
+// This file contains how the letters of words are read, so words of any language can be played
+// Words are normalized before they are compared, so the same word typed on different keyboards is the same word,
+// e.g. "ñ" can be sent as one character, or as "n" followed by a combining tilde
+// A letter is a grapheme (what a reader sees as one letter), not a character, so "ñ" is always one letter
+
+/// Normalizes ```word``` so it can be compared with other words.
+/// The word is trimmed, lowercased and composed (NFC), e.g. "Ñandú" becomes "ñandú".
+pub fn normalize(word: &str) -> String {
+    // lowercasing can decompose some letters, so the word is composed after
+    word.trim().to_lowercase().nfc().collect()
+}
+
+/// Splits ```word``` into its letters.
+pub fn letters(word: &str) -> Vec<&str> {
+    word.graphemes(true).collect()
+}
+
+/// Counts the letters of ```word```.
+pub fn letter_count(word: &str) -> usize {
+    word.graphemes(true).count()
+}
+
+/// Checks if ```word``` is made of only letters, which can have accents.
+pub fn is_word(word: &str) -> bool {
+    !word.is_empty()
+        && letters(word).iter().all(|letter| {
+            let mut chars = letter.chars();
+            chars.next().is_some_and(char::is_alphabetic) && chars.all(is_combining_mark)
+        })
+}
+
+/// Works out the state of each letter of ```guess``` compared to ```target_word```, both normalized.
+/// A letter guessed more times than it is in the word is only yellow as many times as it is left over
+/// after the letters in the correct spot, e.g. "sassy" against "stars" is green, yellow, yellow, black, black.
+pub fn letter_states(guess: &str, target_word: &str) -> Vec<LetterState> {
+    let guess_letters = letters(guess);
+    let target_letters = letters(target_word);
+
+    let mut states = vec![LetterState::Black; guess_letters.len()];
+    let mut left_over: HashMap<&str, usize> = HashMap::new();
+
+    for (i, letter) in target_letters.iter().enumerate() {
+        if guess_letters.get(i) == Some(letter) {
+            states[i] = LetterState::Green;
+        } else {
+            *left_over.entry(letter).or_default() += 1;
+        }
+    }
+
+    for (i, letter) in guess_letters.iter().enumerate() {
+        if states[i] == LetterState::Green {
+            continue;
+        }
+        if let Some(count) = left_over.get_mut(letter).filter(|count| **count > 0) {
+            *count -= 1;
+            states[i] = LetterState::Yellow;
+        }
+    }
+
+    states
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decomposed_letters_are_one_letter() {
+        // "n" followed by a combining tilde
+        let decomposed = "an\u{303}os";
+
+        assert_eq!(normalize(decomposed), "años");
+        assert_eq!(letter_count(decomposed), 4);
+        assert_eq!(letters("años"), vec!["a", "ñ", "o", "s"]);
+        assert_eq!(normalize(" GRÜẞE "), "grüße");
+    }
+
+    #[test]
+    fn words_only_have_letters() {
+        assert!(is_word("straße"));
+        assert!(is_word("an\u{303}os"));
+        assert!(!is_word("rust y"));
+        assert!(!is_word("c3po"));
+        assert!(!is_word(""));
+    }
+
+    #[test]
+    fn repeated_letters_are_only_yellow_once() {
+        use LetterState::*;
+
+        assert_eq!(
+            letter_states("sassy", "stars"),
+            vec![Green, Yellow, Yellow, Black, Black]
+        );
+        assert_eq!(letter_states("años", "años"), vec![Green; 4]);
+    }
+}
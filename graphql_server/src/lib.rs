@@ -5,9 +5,12 @@
 #![warn(missing_docs)]
 
 use lazy_static::lazy_static;
-use juniper::{EmptySubscription, RootNode};
+use futures::StreamExt;
+use juniper::{http::GraphQLResponse, InputValue, RootNode};
+use rocket::fairing::AdHoc;
 use rocket::response::content::RawHtml;
-use rocket::{Build, State};
+use rocket::response::stream::{Event, EventStream};
+use rocket::{Build, Orbit, Rocket, State};
 use rocket_db_pools::{
     mongodb::{Client, Collection},
     Database,
@@ -37,13 +40,47 @@ lazy_static! {
         .unwrap_or("10000".to_string()) // default port is 10000
         .parse()
         .expect("GRAPHQL_PORT should be an unsigned integer.");
+
+    /// Seconds an idempotency key is kept for, after which a mutation with the same key is applied again
+    pub static ref IDEMPOTENCY_KEY_TTL: u64 = env::var("IDEMPOTENCY_KEY_TTL")
+        .unwrap_or("86400".to_string()) // default is 1 day
+        .parse()
+        .expect("IDEMPOTENCY_KEY_TTL should be an unsigned integer.");
+
+    /// Seconds a spectator is counted as watching a game after they last queried it
+    pub static ref SPECTATOR_TTL: u64 = env::var("SPECTATOR_TTL")
+        .unwrap_or("300".to_string()) // default is 5 minutes
+        .parse()
+        .expect("SPECTATOR_TTL should be an unsigned integer.");
+
+    /// Number of each player's most recent games whose words aren't picked again for them
+    pub static ref RECENT_GAMES_WINDOW: i64 = env::var("RECENT_GAMES_WINDOW")
+        .unwrap_or("20".to_string()) // default is 20 games
+        .parse()
+        .expect("RECENT_GAMES_WINDOW should be an unsigned integer.");
+
+    /// Directory the word pack files loaded when the server starts are in
+    pub static ref WORD_PACK_DIR: String = env::var("WORD_PACK_DIR")
+        .unwrap_or("word_packs".to_string()); // default is the word_packs directory the server is run in
+
+    /// Token admins send to make admin only changes, such as importing games
+    /// If not set, no one is an admin
+    pub static ref ADMIN_TOKEN: Option<String> = env::var("ADMIN_TOKEN").ok();
+
+    /// Secret the word of each daily challenge is picked with, so players can't work out future words
+    /// If not set, or empty, daily challenges can't be played
+    pub static ref DAILY_CHALLENGE_SECRET: Option<String> = env::var("DAILY_CHALLENGE_SECRET")
+        .ok()
+        .filter(|secret| !secret.is_empty());
 }
 
 /// mongodb connection
 #[derive(Database)]
 #[database("mongodb")]
 pub struct MongoClient(Client);
-impl juniper::Context for MongoClient {}
+
+/// module for the context of graphql requests, and who made them
+pub mod context;
 
 /// module for the graphql server queries
 pub mod query;
@@ -51,9 +88,75 @@ pub mod query;
 /// module for the graphql server mutations
 pub mod mutation;
 
+/// module for the graphql server subscriptions
+pub mod subscription;
+
 /// module for the models (graphql types) that will be represented in this server
 pub mod models;
 
+/// module for reading and saving games and players in the database
+pub mod store;
+
+/// module for rating the skill of players
+pub mod rating;
+
+/// module for the spectators of games
+pub mod spectator;
+
+/// module for the chat of games
+pub mod chat;
+
+/// module for filtering profanity out of chat messages
+pub mod profanity;
+
+/// module for idempotency keys, which let mutations be safely retried
+pub mod idempotency;
+
+/// module for the encoding of letter states shared with clients
+pub mod encoding;
+
+/// module for replaying finished games turn by turn
+pub mod replay;
+
+/// module for exporting and importing games as JSON
+pub mod export;
+
+/// module for the dictionaries of words of each language, shared with the word server
+pub mod words;
+
+/// module for reading the letters of words in any language
+pub mod letters;
+
+/// module for the difficulty of target words
+pub mod difficulty;
+
+/// module for word packs, themed lists of words games can be played with
+pub mod packs;
+
+/// module for picking the target words of games, avoiding words players have recently seen
+pub mod selection;
+
+/// module for the daily challenge, which everyone plays with the same word
+pub mod daily;
+
+/// module for the statistics of players
+pub mod stats;
+
+/// module for ranking players on leaderboards
+pub mod leaderboard;
+
+/// module for the scoring rules games are played with, and scoring rounds with them
+pub mod scoring;
+
+/// module for the hints players can request when stuck on a word
+pub mod hints;
+
+/// module for the solver bots pick their guesses with
+pub mod solver;
+
+/// module for paginating, filtering and sorting queries
+pub mod pagination;
+
 /// module for the errors that will be used within this server
 pub mod errors;
 
@@ -67,7 +170,12 @@ fn game_collection<T: DeserializeOwned + Send + Sync>(client: &MongoClient) -> C
     client.database("Games").collection("Games")
 }
 
-type Schema = RootNode<'static, query::Query, mutation::Mutation, EmptySubscription<MongoClient>>;
+/// Gets the Players Collection from the Games database, which has the profiles of players.
+fn player_collection<T: DeserializeOwned + Send + Sync>(client: &MongoClient) -> Collection<T> {
+    client.database("Games").collection("Players")
+}
+
+type Schema = RootNode<'static, query::Query, mutation::Mutation, subscription::Subscription>;
 
 /// Builds a rocket server, so all main has to do is launch it
 pub fn build_rocket() -> rocket::Rocket<Build> {
@@ -75,16 +183,57 @@ pub fn build_rocket() -> rocket::Rocket<Build> {
 	
     rocket::build()
         .attach(MongoClient::init())
+        .attach(AdHoc::on_liftoff("Create indexes", |rocket| {
+            Box::pin(create_indexes(rocket))
+        }))
         .manage(Schema::new(
             query::Query,
             mutation::Mutation,
-            EmptySubscription::new(),
+            subscription::Subscription,
         ))
-        .mount("/", routes![index, get_graphql, post_graphql])
+        .mount("/", routes![index, get_graphql, post_graphql, subscribe_graphql])
         .mount("/debug", routes![debug, graphiql, playground])
 		.attach(cors)
 }
 
+/// Creates the indexes used by the database, backfills fields of games saved before they were recorded,
+/// and loads the word pack files.
+///
+/// Failing to create or load them is only logged, so the server can still start without a database connection.
+async fn create_indexes(rocket: &Rocket<Orbit>) {
+    let Some(client) = MongoClient::fetch(rocket) else {
+        return;
+    };
+
+    if let Err(e) = idempotency::create_indexes(client).await {
+        warn!("{}", e.message);
+    }
+
+    if let Err(e) = leaderboard::create_indexes(client).await {
+        warn!("{}", e.message);
+    }
+
+    if let Err(e) = spectator::create_indexes(client).await {
+        warn!("{}", e.message);
+    }
+
+    if let Err(e) = chat::create_indexes(client).await {
+        warn!("{}", e.message);
+    }
+
+    if let Err(e) = daily::create_indexes(client).await {
+        warn!("{}", e.message);
+    }
+
+    if let Err(e) = store::backfill_timestamps(client).await {
+        warn!("{}", e.message);
+    }
+
+    for e in packs::load_pack_files(client, std::path::Path::new(WORD_PACK_DIR.as_str())).await {
+        warn!("{}", e.message);
+    }
+}
+
 /// Index route that is simply used to tell that the server is running
 #[get("/")]
 fn index() -> &'static str {
@@ -114,17 +263,73 @@ fn playground() -> RawHtml<String> {
 #[get("/graphql?<request..>")]
 async fn get_graphql(
     db: &MongoClient,
+    role: context::Role,
     request: juniper_rocket::GraphQLRequest,
     schema: &State<Schema>,
 ) -> juniper_rocket::GraphQLResponse {
-    request.execute(schema, db).await
+    request.execute(schema, &context::Context::new(db, role)).await
 }
 
 #[post("/graphql", data = "<request>")]
 async fn post_graphql(
     db: &MongoClient,
+    role: context::Role,
     request: juniper_rocket::GraphQLRequest,
     schema: &State<Schema>,
 ) -> juniper_rocket::GraphQLResponse {
-    request.execute(schema, db).await
+    request.execute(schema, &context::Context::new(db, role)).await
+}
+
+/// A graphql subscription request, given as query parameters like a GET graphql request.
+#[derive(FromForm)]
+struct SubscriptionRequest {
+    query: String,
+    variables: Option<String>,
+    #[field(name = "operationName")]
+    operation_name: Option<String>,
+}
+
+/// Runs a graphql subscription, sending each result as a server-sent event.
+/// Sends a single event with the error if the subscription can't be started.
+#[get("/graphql/subscriptions?<request..>")]
+fn subscribe_graphql<'r>(
+    db: &MongoClient,
+    role: context::Role,
+    request: SubscriptionRequest,
+    schema: &'r State<Schema>,
+) -> EventStream![Event + 'r] {
+    let context = context::Context::new(db, role);
+
+    EventStream! {
+        let variables = match request.variables.as_deref().map(serde_json::from_str::<InputValue>) {
+            None => None,
+            Some(Ok(variables)) => Some(variables),
+            Some(Err(_)) => {
+                let error = errors::GraphqlServerError::new(
+                    "variables should be a json object".to_string(),
+                    &errors::CODE400,
+                );
+                yield Event::data(error.to_string()).event("error");
+                return;
+            }
+        };
+        let request =
+            juniper::http::GraphQLRequest::new(request.query, request.operation_name, variables);
+
+        let resolved = juniper::http::resolve_into_stream(&request, schema.inner(), &context).await;
+        match resolved {
+            Err(e) => {
+                let response = GraphQLResponse::<juniper::DefaultScalarValue>::from_result(Err(e));
+                yield Event::data(serde_json::to_string(&response).unwrap_or_default());
+            }
+            Ok((values, errors)) => {
+                let mut outputs = juniper_subscriptions::Connection::from_stream(values, errors);
+
+                while let Some(output) = outputs.next().await {
+                    let response = GraphQLResponse::from_result(Ok((output.data, output.errors)));
+                    yield Event::data(serde_json::to_string(&response).unwrap_or_default());
+                }
+            }
+        }
+    }
 }
@@ -1,11 +1,40 @@
+use bson::serde_helpers::{
+    chrono_datetime_as_bson_datetime, chrono_datetime_as_bson_datetime_optional,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use juniper::{GraphQLEnum, GraphQLInputObject, GraphQLObject};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use uuid::Uuid;
 
-use super::errors::{GraphqlServerError, GraphqlServerResult, CODE422};
+use super::difficulty::Difficulty;
+use super::encoding::{eliminate_letters, encode};
+use super::errors::{GraphqlServerError, GraphqlServerResult, CODE400, CODE403, CODE422};
+use super::export::{
+    BoardExport, GameExport, HintExport, RoundExport, TurnExport, EXPORT_FORMAT_VERSION,
+};
+use super::hints::{letters_to_eliminate, unrevealed_positions, HintKind};
+use super::letters::{is_word, letter_count, letter_states, letters, normalize};
+use super::rating::{Outcome, INITIAL_RATING};
+use super::scoring::{hint_score, score_rounds, NewScoringRules, RoundScore, ScoringRules};
+use super::solver::SolverStrength;
+use super::words::Language;
 
 // This file contains the models/objects represented within the graphql server
 
+/// The max number of guesses that can be made in a round before the round ends.
+pub const MAX_GUESSES: i32 = 6;
+
+/// The max number of letters in a word, where a letter with an accent is one letter.
+/// Each letter state takes 2 bits, so the letter states of longer words don't fit in an ```int```.
+pub const MAX_WORD_LENGTH: usize = 15;
+
+/// The max number of rounds of a best-of match, not counting tiebreak rounds.
+pub const MAX_BEST_OF: i32 = 15;
+
+/// The max number of characters in a player's display name.
+pub const MAX_DISPLAY_NAME_LENGTH: usize = 32;
+
 /// A turn turn made by some player.
 #[derive(Debug, GraphQLObject, Serialize, Deserialize)]
 pub struct Turn {
@@ -15,6 +44,44 @@ pub struct Turn {
     /// the states of each letter of the word.
     /// Clients need to decode this ```int``` to actually read the state of each letter.
     letter_state: i32,
+
+    /// The player who made the turn.
+    /// Is player 1 for turns saved before the player was recorded.
+    #[serde(default = "legacy_player")]
+    player: Player,
+
+    /// When the turn was played.
+    /// Is the unix epoch for turns saved before the time was recorded.
+    #[serde(default = "legacy_timestamp", with = "chrono_datetime_as_bson_datetime")]
+    played_at: DateTime<Utc>,
+}
+
+impl Turn {
+    /// Checks if every letter of the guess was in the correct spot.
+    fn is_solved(&self, word_length: usize) -> bool {
+        // each letter takes 2 bits, and a correct letter is 0b11
+        self.letter_state == (1 << (2 * word_length)) - 1
+    }
+
+    /// Gets the word guessed by the player.
+    pub fn guessed_word(&self) -> &str {
+        &self.guessed_word
+    }
+
+    /// Gets the player who made the turn.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Gets the encoded states of each letter of the guess.
+    pub fn letter_state(&self) -> i32 {
+        self.letter_state
+    }
+
+    /// Gets when the turn was played.
+    pub fn played_at(&self) -> DateTime<Utc> {
+        self.played_at
+    }
 }
 
 /// A new turn made by some player. Essentially same as ```Turn```, but used for graphql arguments.
@@ -28,8 +95,18 @@ pub struct NewTurn {
     letter_state: i32,
 }
 
+impl NewTurn {
+    /// Creates a turn guessing ```guess```.
+    pub fn new(guess: &str, letter_state: i32) -> Self {
+        NewTurn {
+            guess: guess.to_string(),
+            letter_state,
+        }
+    }
+}
+
 /// The player type, either player 1 or player 2
-#[derive(Debug, GraphQLEnum, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, GraphQLEnum, Serialize, Deserialize)]
 pub enum Player {
     /// Player 1
     P1,
@@ -37,6 +114,533 @@ pub enum Player {
     P2,
 }
 
+impl Player {
+    /// Gets the opponent of this player.
+    pub fn other(&self) -> Player {
+        match self {
+            Player::P1 => Player::P2,
+            Player::P2 => Player::P1,
+        }
+    }
+}
+
+/// The profile of a person, kept across all the games they play.
+#[derive(Debug, GraphQLObject, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    /// The id of the player. Used by the database to identify each document.
+    _id: String,
+
+    /// The name of the player shown to others.
+    display_name: String,
+
+    /// When the profile was created.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    created_at: DateTime<Utc>,
+
+    /// The skill rating of the player, updated after each game they finish.
+    #[serde(default = "initial_rating")]
+    rating: f64,
+
+    /// The number of finished games that have changed the player's rating.
+    #[serde(default)]
+    rated_games: i32,
+
+    /// The ids of the latest games that changed the player's rating, up to ```RATED_GAME_IDS_KEPT```.
+    /// Used so a game rated again, e.g. when retrying a failed rating update, doesn't change the rating twice.
+    #[graphql(skip)]
+    #[serde(default)]
+    rated_game_ids: Vec<String>,
+
+    /// The secret given to the player when the profile was created, needed to add the profile to a game.
+    /// Is None for profiles created before profiles had secrets, until an admin issues them one.
+    #[graphql(skip)]
+    #[serde(default)]
+    secret: Option<String>,
+}
+
+/// A profile that was just created, along with the secret needed to add it to games.
+#[derive(Debug, GraphQLObject)]
+pub struct NewPlayer {
+    /// The profile of the player.
+    player: PlayerProfile,
+
+    /// The secret sent along with the id of the profile to add the profile to a game.
+    secret: String,
+}
+
+/// A player added to a game by their profile, who proves it is theirs with their secret.
+#[derive(Debug, GraphQLInputObject)]
+pub struct ProfileCredentials {
+    /// The id of the profile of the player.
+    pub id: String,
+
+    /// The secret of the player, given when the profile was created.
+    pub secret: String,
+}
+
+impl NewPlayer {
+    /// Gets the secret of the player.
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+}
+
+/// Used by serde for profiles saved before players had ratings.
+fn initial_rating() -> f64 {
+    INITIAL_RATING
+}
+
+/// Used by serde for games, rounds and turns saved before their timestamps were recorded.
+fn legacy_timestamp() -> DateTime<Utc> {
+    DateTime::<Utc>::default()
+}
+
+/// Used by serde for games saved before whether they were rated was recorded.
+fn legacy_rated() -> bool {
+    true
+}
+
+/// Used by serde for turns saved before the player who made them was recorded.
+fn legacy_player() -> Player {
+    Player::P1
+}
+
+impl PlayerProfile {
+    /// Used to create a profile for a new player.
+    /// Surrounding whitespace is removed from ```display_name```.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the display name is empty or longer than ```MAX_DISPLAY_NAME_LENGTH```.
+    pub fn new_player(display_name: &str) -> GraphqlServerResult<Self> {
+        let display_name = display_name.trim();
+        let length = display_name.chars().count();
+
+        if length == 0 || length > MAX_DISPLAY_NAME_LENGTH {
+            return Err(GraphqlServerError::new(
+                format!(
+                    "display name should be between 1 and {} characters",
+                    MAX_DISPLAY_NAME_LENGTH
+                ),
+                &CODE422,
+            ));
+        }
+
+        Ok(PlayerProfile {
+            _id: Uuid::new_v4().simple().to_string(),
+            display_name: display_name.to_string(),
+            created_at: Utc::now(),
+            rating: INITIAL_RATING,
+            rated_games: 0,
+            rated_game_ids: Vec::new(),
+            secret: Some(Uuid::new_v4().simple().to_string()),
+        })
+    }
+
+    /// Gives the player a new secret, so only the new secret can add the profile to games.
+    pub fn new_secret(&mut self) {
+        self.secret = Some(Uuid::new_v4().simple().to_string());
+    }
+
+    /// Gets the profile along with its secret, for giving the secret to the player.
+    pub fn to_new_player(self) -> NewPlayer {
+        let secret = self.secret.clone().unwrap_or_default();
+        NewPlayer {
+            player: self,
+            secret,
+        }
+    }
+
+    /// Checks that ```secret``` is the secret of the player, so the profile can be added to a game.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 403 error if the secret isn't the player's, or the player doesn't have a secret yet.
+    pub fn check_secret(&self, secret: Option<&str>) -> GraphqlServerResult<()> {
+        match (&self.secret, secret) {
+            (Some(player_secret), Some(secret)) if player_secret == secret => Ok(()),
+            _ => Err(GraphqlServerError::new(
+                format!(
+                    "the secret of player {} is needed to add them to a game",
+                    self._id
+                ),
+                &CODE403,
+            )),
+        }
+    }
+
+    /// Gets the id of the player
+    pub fn id(&self) -> String {
+        self._id.clone()
+    }
+
+    /// Gets the skill rating of the player
+    pub fn rating(&self) -> f64 {
+        self.rating
+    }
+
+    /// Gets the number of games that have changed the player's rating
+    pub fn rated_games(&self) -> i32 {
+        self.rated_games
+    }
+
+    /// Parses an id in some string format into the string format ```PlayerProfile``` uses.
+    /// Players use the same id format as games.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the id provided isn't a valid uuid.
+    pub fn parse_id(id: &str) -> GraphqlServerResult<String> {
+        Game::parse_id(id)
+    }
+}
+
+/// The status of a game, whether it is still being played or not.
+#[derive(Debug, Default, Clone, Copy, PartialEq, GraphQLEnum, Serialize, Deserialize)]
+pub enum GameStatus {
+    /// The game is still being played.
+    #[default]
+    Active,
+    /// The game has ended.
+    Finished,
+}
+
+/// How the players of a game play with each other.
+#[derive(Debug, Default, Clone, Copy, PartialEq, GraphQLEnum, Serialize, Deserialize)]
+pub enum GameMode {
+    /// The players take turns guessing, and score points for the words they guess.
+    #[default]
+    Versus,
+    /// The players guess each word together on a shared board, and score points as a team.
+    /// A guess is proposed by one player and only played once the other player confirms it.
+    CoOp,
+    /// The players guess the same word at the same time on their own boards,
+    /// and whoever guesses it in fewer guesses, or quicker, wins the round.
+    Race,
+    /// Each round one player sets a word for the other player to guess, taking turns to set the word.
+    /// After each pair of rounds, whoever guessed their word in fewer guesses gets a point.
+    SecretWord,
+}
+
+/// The settings a game is played with.
+#[derive(Debug, Clone, PartialEq, GraphQLObject, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// The max number of guesses that can be made in a round before the round ends.
+    max_guesses: i32,
+
+    /// The day of the daily challenge the game plays.
+    /// A daily challenge has a single round, and the game ends with the round.
+    /// Is null if the game isn't a daily challenge.
+    #[serde(default)]
+    daily_challenge: Option<NaiveDate>,
+
+    /// How hard the target words picked for the game are.
+    /// Is null if words are picked from the whole dictionary.
+    #[serde(default)]
+    difficulty: Option<Difficulty>,
+
+    /// The name of the word pack the target words are picked from, and guesses have to be in.
+    /// Is null if the game is played with the dictionary.
+    #[serde(default)]
+    word_pack: Option<String>,
+
+    /// The language of the dictionary the target words are picked from.
+    #[serde(default)]
+    language: Language,
+
+    /// How the players play with each other.
+    #[serde(default)]
+    mode: GameMode,
+
+    /// The number of rounds of a best-of match.
+    /// The game ends as soon as a player can't be caught in the rounds left,
+    /// and tiebreak rounds are played until someone scores if the points are level after the last round.
+    /// Is null if the game has no set number of rounds, and ends when a player ends it.
+    #[serde(default)]
+    best_of: Option<i32>,
+
+    /// The rules the rounds of the game are scored with.
+    #[serde(default)]
+    scoring: ScoringRules,
+
+    /// How well the bot playing as player 2 guesses, see ```solver```.
+    /// Is null if player 2 isn't a bot.
+    #[serde(default)]
+    bot: Option<SolverStrength>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            max_guesses: MAX_GUESSES,
+            daily_challenge: None,
+            difficulty: None,
+            word_pack: None,
+            language: Language::English,
+            mode: GameMode::Versus,
+            best_of: None,
+            scoring: ScoringRules::default(),
+            bot: None,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Gets the language of the dictionary the game is played with.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Gets how hard the target words picked for the game are, if the game has a difficulty.
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.difficulty
+    }
+
+    /// Gets the name of the word pack the game is played with, if it has one.
+    pub fn word_pack(&self) -> Option<&str> {
+        self.word_pack.as_deref()
+    }
+
+    /// Gets how the players play with each other.
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    /// Gets the rules the rounds of the game are scored with.
+    pub fn scoring(&self) -> &ScoringRules {
+        &self.scoring
+    }
+
+    /// Gets how well the bot playing as player 2 guesses, if player 2 is a bot.
+    pub fn bot(&self) -> Option<SolverStrength> {
+        self.bot
+    }
+
+    /// Checks if a best-of match has been decided after ```rounds_played``` rounds ended with the points given.
+    /// A player clinches the match once the other player can't catch up in the rounds left,
+    /// which after the last round, or in a tiebreak round, is as soon as the points aren't level.
+    /// A game that isn't a best-of match is never decided by its rounds.
+    fn match_decided(&self, rounds_played: i32, p1_points: i32, p2_points: i32) -> bool {
+        let Some(best_of) = self.best_of else {
+            return false;
+        };
+
+        // best-of matches are scored with the default rules, so each round gives at most a point, to one player
+        let rounds_left = (best_of - rounds_played).max(0);
+        (p1_points - p2_points).abs() > rounds_left
+    }
+}
+
+/// The settings a new game can be created with, every setting is optional.
+/// Used for graphql arguments, see ```GameConfig``` for the settings a game has.
+#[derive(Debug, Default, GraphQLInputObject)]
+pub struct GameSettings {
+    /// How hard the target words picked for the game are.
+    /// Can't be given with a word or word pack.
+    difficulty: Option<Difficulty>,
+
+    /// The name of the word pack the target words are picked from, and guesses have to be in.
+    word_pack: Option<String>,
+
+    /// The language of the dictionary the target words are picked from. Defaults to English.
+    language: Option<Language>,
+
+    /// How the players play with each other. Defaults to versus.
+    mode: Option<GameMode>,
+
+    /// The number of rounds of a best-of match, between 1 and ```MAX_BEST_OF```.
+    /// Can't be given for co-op or secret word games.
+    best_of: Option<i32>,
+
+    /// The rules the rounds of the game are scored with. Defaults to a point for whoever guesses the word.
+    /// Can't be given for best-of matches.
+    scoring: Option<NewScoringRules>,
+
+    /// How well a bot playing as player 2 guesses, if player 2 should be a bot.
+    /// Bots only play versus games.
+    bot: Option<SolverStrength>,
+}
+
+impl GameSettings {
+    /// Checks that the settings can be used together, and with the target word ```word``` if given.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 400 error if a difficulty is given with a word or word pack,
+    /// a word or difficulty is given for a secret word game,
+    /// the number of rounds of a best-of match is out of range or given for a co-op or secret word game,
+    /// the scoring rules can't be played with or are given for a best-of match,
+    /// or a bot is given for a game that isn't versus.
+    pub fn check(&self, word: Option<&str>) -> GraphqlServerResult<()> {
+        if self.difficulty.is_some() && (word.is_some() || self.word_pack.is_some()) {
+            return Err(GraphqlServerError::new(
+                "a difficulty can't be given with a word or word pack".to_string(),
+                &CODE400,
+            ));
+        }
+
+        if self.mode == Some(GameMode::SecretWord) && (word.is_some() || self.difficulty.is_some())
+        {
+            return Err(GraphqlServerError::new(
+                "words of secret word games are set by the players".to_string(),
+                &CODE400,
+            ));
+        }
+
+        if let Some(best_of) = self.best_of {
+            if !(1..=MAX_BEST_OF).contains(&best_of) {
+                return Err(GraphqlServerError::new(
+                    format!("best of should be between 1 and {}", MAX_BEST_OF),
+                    &CODE400,
+                ));
+            }
+
+            // co-op players don't score against each other, and secret word games score a point every 2 rounds
+            if matches!(self.mode, Some(GameMode::CoOp | GameMode::SecretWord)) {
+                return Err(GraphqlServerError::new(
+                    "best-of matches can only be played in versus or race games".to_string(),
+                    &CODE400,
+                ));
+            }
+        }
+
+        if let Some(scoring) = &self.scoring {
+            scoring.to_rules().check()?;
+
+            // a best-of match is decided by rounds won, which other rules wouldn't keep track of
+            if self.best_of.is_some() {
+                return Err(GraphqlServerError::new(
+                    "scoring rules can't be given for best-of matches".to_string(),
+                    &CODE400,
+                ));
+            }
+        }
+
+        // bots play their turns with ```playTurn```, which only versus games are played with
+        if self.bot.is_some() && !matches!(self.mode, None | Some(GameMode::Versus)) {
+            return Err(GraphqlServerError::new(
+                "bots can only play versus games".to_string(),
+                &CODE400,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Gets the name of the word pack to play with, if one was given.
+    pub fn word_pack(&self) -> Option<&str> {
+        self.word_pack.as_deref()
+    }
+
+    /// Converts into the settings of a game, using the defaults for settings that weren't given.
+    pub fn into_config(self) -> GameConfig {
+        GameConfig {
+            difficulty: self.difficulty,
+            word_pack: self.word_pack,
+            language: self.language.unwrap_or_default(),
+            mode: self.mode.unwrap_or_default(),
+            best_of: self.best_of,
+            scoring: self
+                .scoring
+                .map(|scoring| scoring.to_rules())
+                .unwrap_or_default(),
+            bot: self.bot,
+            ..GameConfig::default()
+        }
+    }
+}
+
+/// A guess proposed by a player of a co-op game, waiting for their partner to confirm it.
+#[derive(Debug, Clone, GraphQLObject, Serialize, Deserialize)]
+pub struct Proposal {
+    /// The player who proposed the guess.
+    player: Player,
+
+    /// The word proposed.
+    guessed_word: String,
+
+    /// The states of each letter of the proposed word.
+    letter_state: i32,
+
+    /// The state of all the letters in the round if the guess is played.
+    letterpool_state: i32,
+
+    /// When the guess was proposed.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    proposed_at: DateTime<Utc>,
+}
+
+/// A player's own board in a round of a race game.
+#[derive(Debug, Clone, GraphQLObject, Serialize, Deserialize)]
+pub struct RaceBoard {
+    /// The player guessing on the board.
+    player: Player,
+
+    /// The number of guesses the player has made on the board.
+    guess_num: i32,
+
+    /// The state of all the letters on the board.
+    letterpool_state: i32,
+
+    /// When the player guessed the word or ran out of guesses.
+    /// Is null while the player is still guessing.
+    #[serde(default, with = "chrono_datetime_as_bson_datetime_optional")]
+    finished_at: Option<DateTime<Utc>>,
+
+    /// Whether the player guessed the word.
+    solved: bool,
+}
+
+impl RaceBoard {
+    /// Gets the number of guesses the player has made on the board.
+    pub fn guess_num(&self) -> i32 {
+        self.guess_num
+    }
+}
+
+/// A hint given to a player stuck on the word of a round, see ```hints```.
+#[derive(Debug, Clone, GraphQLObject, Serialize, Deserialize)]
+pub struct Hint {
+    /// The player who requested the hint, and paid for it.
+    player: Player,
+
+    /// What the hint gives away.
+    kind: HintKind,
+
+    /// The spot of the letter revealed, starting at 0.
+    /// Is null if the hint eliminates letters.
+    position: Option<i32>,
+
+    /// The letter revealed, or the letters eliminated.
+    letters: Vec<String>,
+
+    /// When the hint was requested.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    requested_at: DateTime<Utc>,
+}
+
+impl Hint {
+    /// Gets the player who requested the hint.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Gets the spot of the letter revealed, if the hint reveals a letter.
+    pub fn position(&self) -> Option<i32> {
+        self.position
+    }
+
+    /// Gets the letter revealed, or the letters eliminated.
+    pub fn letters(&self) -> &[String] {
+        &self.letters
+    }
+
+    /// Gets when the hint was requested.
+    pub fn requested_at(&self) -> DateTime<Utc> {
+        self.requested_at
+    }
+}
+
 /// A round in a match.
 /// A match can have multiple rounds
 #[derive(Debug, GraphQLObject, Serialize, Deserialize)]
@@ -48,6 +652,7 @@ pub struct Round {
 
     /// The state of all the letters in the round.
     /// This is an encoded value, clients are responsible for encoding and decoding the bytes
+    /// Bots encode it as described in ```encoding```.
     letterpool_state: i32,
 
     /// The current guess number the round is on.
@@ -59,6 +664,52 @@ pub struct Round {
 
     /// The target word that players are trying to guess for the round.
     target_word: String,
+
+    /// When the round started.
+    /// Is the unix epoch for rounds saved before the time was recorded.
+    #[serde(default = "legacy_timestamp", with = "chrono_datetime_as_bson_datetime")]
+    started_at: DateTime<Utc>,
+
+    /// When the round ended.
+    /// Is null while the round is still being played.
+    #[serde(default, with = "chrono_datetime_as_bson_datetime_optional")]
+    ended_at: Option<DateTime<Utc>>,
+
+    /// The player who guessed the target word.
+    /// Is null if the round is still being played or no one guessed the word.
+    #[serde(default)]
+    solved_by: Option<Player>,
+
+    /// The guess proposed in a co-op game that the partner hasn't confirmed or rejected yet.
+    /// Is null if there is no proposed guess, or the game isn't co-op.
+    #[serde(default)]
+    proposal: Option<Proposal>,
+
+    /// The board of each player in a race game, added when the player makes their first guess.
+    /// Turns on every board are in ```turns```, in the order they were played.
+    /// Is empty if the game isn't a race.
+    #[serde(default)]
+    boards: Vec<RaceBoard>,
+
+    /// The player who sets the word of the round in a secret word game, the other player guesses it.
+    /// Is null if the game isn't a secret word game.
+    #[serde(default)]
+    setter: Option<Player>,
+
+    /// The word set by the setter of a secret word round, or the word of a daily challenge,
+    /// hidden from the players until the round ends.
+    /// ```target_word``` is empty until then.
+    #[graphql(skip)]
+    #[serde(default)]
+    secret_word: Option<String>,
+
+    /// Whether the round is a sudden-death tiebreak of a best-of match, played as the points were level.
+    #[serde(default)]
+    tiebreak: bool,
+
+    /// The hints given in the round, in the order they were requested.
+    #[serde(default)]
+    hints: Vec<Hint>,
 }
 
 impl Round {
@@ -69,72 +720,1717 @@ impl Round {
             letterpool_state: 0,
             guess_num,
             current_player,
-            target_word,
+            target_word: normalize(&target_word),
+            started_at: Utc::now(),
+            ended_at: None,
+            solved_by: None,
+            proposal: None,
+            boards: Vec::new(),
+            setter: None,
+            secret_word: None,
+            tiebreak: false,
+            hints: Vec::new(),
         }
     }
-}
-
-/// An argument with info needed to update a round whenever a turn is made.
-#[derive(Debug, GraphQLInputObject, Serialize, Deserialize)]
-pub struct UpdateRound {
-    game_id: String,
-    turn: NewTurn,
-    letterpool: i32,
-    next_player: Player,
-}
-
-/// A game that is currently active/being played.
-#[derive(Debug, GraphQLObject, Serialize, Deserialize)]
-pub struct Game {
-    /// The id of a Game. Used by the database to identify each document.
-    _id: String,
-    // work with string instead of Uuid as mongodb stores a Uuid as some sort of object
-    // that is hard to recreate as a rust object when trying to query from this server
-    // as uuid doesn't properly serialize into bson for queries
-    // and can't use bson::uuid type for a GraphQLObject which does serialize well
-    /// The current round that is being played.
-    current_round: Round,
 
-    /// The points of player 1.
-    p1_points: i32,
+    /// Used when a new round of a secret word game has started, waiting for ```setter``` to set the word.
+    fn new_secret_word_round(setter: Player) -> Self {
+        Round {
+            setter: Some(setter),
+            ..Round::new_round(0, setter.other(), String::new())
+        }
+    }
 
-    /// The points of player 2.
-    p2_points: i32,
+    /// Gets the word players are trying to guess, even if it is hidden.
+    /// Is empty while the setter of a secret word round hasn't set the word.
+    fn answer(&self) -> &str {
+        self.secret_word.as_deref().unwrap_or(&self.target_word)
+    }
 
-    /// The current round number that is currently being made.
-    /// This value starts at 1.
-    round_num: i32,
-}
+    /// Reveals the word of a secret word round, once the round has ended.
+    fn reveal(&mut self) {
+        if let Some(secret_word) = self.secret_word.take() {
+            self.target_word = secret_word;
+        }
+    }
 
-impl Game {
-    /// Used to create when a new game is started.
-    pub fn new_game(target_word: &str) -> Self {
-        Game {
-            _id: Uuid::new_v4().simple().to_string(),
-            current_round: Round::new_round(0, Player::P1, target_word.to_string()),
-            p1_points: 0,
-            p2_points: 0,
-            round_num: 0,
+    /// Hides the word of the round, along with the words guessed and the letters given by hints,
+    /// leaving only the letter states of the guesses.
+    fn hide_answer(&mut self) {
+        self.target_word.clear();
+        self.secret_word = None;
+        for turn in &mut self.turns {
+            turn.guessed_word.clear();
+        }
+        for hint in &mut self.hints {
+            hint.letters.clear();
         }
     }
 
-    /// Gets the id of the game
-    pub fn id(&self) -> String {
-        self._id.clone()
+    /// Checks if the round has ended.
+    pub fn is_over(&self) -> bool {
+        self.ended_at.is_some()
     }
 
-    /// Provide a new id for the game, as if making a new game.
-    pub fn new_id(&mut self) {
-        self._id = Uuid::new_v4().simple().to_string();
+    /// Gets the turns played in the round, in the order they were played.
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
     }
 
-    /// Sets the id of the game.
-    /// Does nothing if id format is incorrect.
-    pub fn set_id(&mut self, id: &String) {
-        let check = Self::parse_id(id);
+    /// Gets the player who guessed the target word, if anyone did.
+    pub fn solved_by(&self) -> Option<Player> {
+        self.solved_by
+    }
 
-        if check.is_ok() {
-            self._id = id.clone();
+    /// Gets how long the round took to play, or None if it is still being played.
+    pub fn time_taken(&self) -> Option<Duration> {
+        self.ended_at.map(|ended_at| ended_at - self.started_at)
+    }
+
+    /// Gets the word players are trying to guess.
+    pub fn target_word(&self) -> &str {
+        &self.target_word
+    }
+
+    /// Gets the encoded state of all the letters in the round.
+    pub fn letterpool_state(&self) -> i32 {
+        self.letterpool_state
+    }
+
+    /// Gets the player whose turn it is.
+    pub fn current_player(&self) -> Player {
+        self.current_player
+    }
+
+    /// Gets the board of ```player``` in a race game, if they have made a guess.
+    pub fn board(&self, player: Player) -> Option<&RaceBoard> {
+        self.boards.iter().find(|board| board.player == player)
+    }
+
+    /// Gets the board of ```player``` in a race game, adding it if they haven't made a guess yet.
+    fn board_mut(&mut self, player: Player) -> &mut RaceBoard {
+        let index = match self.boards.iter().position(|board| board.player == player) {
+            Some(index) => index,
+            None => {
+                self.boards.push(RaceBoard {
+                    player,
+                    guess_num: 0,
+                    letterpool_state: 0,
+                    finished_at: None,
+                    solved: false,
+                });
+                self.boards.len() - 1
+            }
+        };
+
+        &mut self.boards[index]
+    }
+
+    /// Works out if a race round is over, and who won it.
+    /// Returns None while the round is still being raced, otherwise the winner, who is None if no one guessed the word.
+    ///
+    /// The player who guessed the word in the fewest guesses wins, and if both took as many guesses, whoever was quicker.
+    /// So the round is over once a player guessed the word and the other player can no longer do it in fewer guesses.
+    fn race_result(&self) -> Option<Option<Player>> {
+        let best = self
+            .boards
+            .iter()
+            .filter(|board| board.solved)
+            .min_by_key(|board| (board.guess_num, board.finished_at));
+
+        let Some(best) = best else {
+            let both_finished = self.boards.len() == 2
+                && self.boards.iter().all(|board| board.finished_at.is_some());
+            return both_finished.then_some(None);
+        };
+
+        let other_finished = self
+            .board(best.player.other())
+            .is_some_and(|board| board.finished_at.is_some());
+        let other_guesses = self
+            .board(best.player.other())
+            .map_or(0, |board| board.guess_num);
+
+        // the other player's next guess is too late if it wouldn't take fewer guesses
+        (other_finished || other_guesses + 1 >= best.guess_num).then_some(Some(best.player))
+    }
+
+    /// Gets the guess waiting to be confirmed, if there is one.
+    pub fn proposal(&self) -> Option<&Proposal> {
+        self.proposal.as_ref()
+    }
+
+    /// Gets the hints given in the round, in the order they were requested.
+    pub fn hints(&self) -> &[Hint] {
+        &self.hints
+    }
+
+    /// Gets the player who made the first guess of the round.
+    pub fn starting_player(&self) -> Player {
+        match self.turns.first() {
+            Some(turn) => turn.player,
+            None => self.current_player,
+        }
+    }
+
+    /// Exports the round, see ```export``` for the format.
+    fn to_export(&self) -> RoundExport {
+        RoundExport {
+            target_word: self.target_word.clone(),
+            current_player: self.current_player,
+            letterpool_state: self.letterpool_state,
+            started_at: self.started_at,
+            ended_at: self.ended_at,
+            solved_by: self.solved_by,
+            turns: self
+                .turns
+                .iter()
+                .map(|turn| TurnExport {
+                    player: turn.player,
+                    guessed_word: turn.guessed_word.clone(),
+                    letter_state: turn.letter_state,
+                    played_at: turn.played_at,
+                })
+                .collect(),
+            boards: self
+                .boards
+                .iter()
+                .map(|board| BoardExport {
+                    player: board.player,
+                    letterpool_state: board.letterpool_state,
+                })
+                .collect(),
+            setter: self.setter,
+            hints: self
+                .hints
+                .iter()
+                .map(|hint| HintExport {
+                    player: hint.player,
+                    kind: hint.kind,
+                    position: hint.position,
+                    letters: hint.letters.clone(),
+                    requested_at: hint.requested_at,
+                })
+                .collect(),
+        }
+    }
+
+    /// Reconstructs an exported round, checking that it could have been played with ```config```.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if there are more turns than guesses allowed,
+    /// a guess isn't the length of the target word, a letter state can't be decoded,
+    /// the round ending doesn't match its turns, or a hint doesn't match the target word.
+    /// Secret word rounds should have a setter, turns only by the guesser, and letter states worked out by the server.
+    fn from_export(export: RoundExport, config: &GameConfig) -> GraphqlServerResult<Round> {
+        let invalid = |reason: String| {
+            Err(GraphqlServerError::new(
+                format!("exported round isn't valid: {}", reason),
+                &CODE422,
+            ))
+        };
+
+        let setter = match (config.mode, export.setter) {
+            (GameMode::SecretWord, Some(setter)) => Some(setter),
+            (GameMode::SecretWord, None) => {
+                return invalid("secret word rounds should have a setter".to_string())
+            }
+            (_, _) => None,
+        };
+        if let Some(setter) = setter {
+            if export.turns.iter().any(|turn| turn.player == setter) {
+                return invalid("the setter shouldn't guess their own word".to_string());
+            }
+            // a round waiting for its word has nothing else to check
+            if export.target_word.is_empty() && export.turns.is_empty() && export.ended_at.is_none()
+            {
+                return Ok(Round {
+                    started_at: export.started_at,
+                    ..Round::new_secret_word_round(setter)
+                });
+            }
+        }
+
+        let word_length = letter_count(&export.target_word);
+        if word_length == 0 || word_length > MAX_WORD_LENGTH {
+            return invalid(format!(
+                "target word should be between 1 and {} letters",
+                MAX_WORD_LENGTH
+            ));
+        }
+
+        let turns: Vec<Turn> = export
+            .turns
+            .into_iter()
+            .map(|turn| Turn {
+                guessed_word: normalize(&turn.guessed_word),
+                letter_state: turn.letter_state,
+                player: turn.player,
+                played_at: turn.played_at,
+            })
+            .collect();
+
+        let target_word = normalize(&export.target_word);
+        let max_letter_state = (1 << (2 * word_length)) - 1;
+        for turn in &turns {
+            if letter_count(&turn.guessed_word) != word_length {
+                return invalid(format!("guess should be {} letters long", word_length));
+            }
+            if !(0..=max_letter_state).contains(&turn.letter_state) {
+                return invalid("letter state doesn't fit the guess".to_string());
+            }
+            if setter.is_some()
+                && turn.letter_state != encode(&letter_states(&turn.guessed_word, &target_word))
+            {
+                return invalid("letter state doesn't match the secret word".to_string());
+            }
+        }
+
+        let target_letters = letters(&target_word);
+        for hint in &export.hints {
+            let matches_word = match (hint.kind, hint.position) {
+                (HintKind::RevealLetter, Some(position)) => target_letters
+                    .get(position as usize)
+                    .is_some_and(|letter| hint.letters == [*letter]),
+                (HintKind::EliminateLetters, None) => {
+                    !hint.letters.is_empty()
+                        && hint
+                            .letters
+                            .iter()
+                            .all(|letter| !target_letters.contains(&letter.as_str()))
+                }
+                (_, _) => false,
+            };
+            if !matches_word {
+                return invalid("hint doesn't match the target word".to_string());
+            }
+        }
+        let hints = export
+            .hints
+            .into_iter()
+            .map(|hint| Hint {
+                player: hint.player,
+                kind: hint.kind,
+                position: hint.position,
+                letters: hint.letters,
+                requested_at: hint.requested_at,
+            })
+            .collect();
+
+        let mut round = Round {
+            guess_num: turns.len() as i32,
+            turns,
+            letterpool_state: export.letterpool_state,
+            current_player: export.current_player,
+            target_word,
+            started_at: export.started_at,
+            ended_at: export.ended_at,
+            solved_by: export.solved_by,
+            proposal: None,
+            boards: Vec::new(),
+            setter,
+            secret_word: None,
+            tiebreak: false,
+            hints,
+        };
+
+        // each player of a race guesses on their own board, so the round is checked board by board
+        if config.mode == GameMode::Race {
+            round.boards = match race_boards(&round.turns, &export.boards, config.max_guesses) {
+                Ok(boards) => boards,
+                Err(reason) => return invalid(reason),
+            };
+
+            let result = round.race_result();
+            if export.solved_by != result.flatten() {
+                return invalid(
+                    "round should be won by the player who guessed the word first".to_string(),
+                );
+            }
+            if export.ended_at.is_some() != result.is_some() {
+                return invalid(
+                    "round should end once it is won or both boards are finished".to_string(),
+                );
+            }
+            return Ok(round);
+        }
+
+        let turns = &round.turns;
+        if turns.len() > config.max_guesses as usize {
+            return invalid(format!("round has more than {} turns", config.max_guesses));
+        }
+
+        // only the last turn can solve the round, and the round ends when it is solved or out of guesses
+        let solved_turn = turns.iter().position(|turn| turn.is_solved(word_length));
+        let solved_by = solved_turn.map(|i| turns[i].player);
+        if solved_turn.is_some_and(|i| i + 1 != turns.len()) {
+            return invalid("no turns should be played after the word is guessed".to_string());
+        }
+        if export.solved_by != solved_by {
+            return invalid(
+                "round should be solved by the player who guessed the word".to_string(),
+            );
+        }
+
+        let should_end = solved_by.is_some() || turns.len() == config.max_guesses as usize;
+        if export.ended_at.is_some() != should_end {
+            return invalid("round should end once solved or out of guesses".to_string());
+        }
+
+        // the word of a secret word round or daily challenge is hidden until the round ends
+        if (setter.is_some() || config.daily_challenge.is_some()) && !round.is_over() {
+            round.secret_word = Some(std::mem::take(&mut round.target_word));
+        }
+
+        Ok(round)
+    }
+}
+
+/// Rebuilds the boards of an exported race round from its ```turns```,
+/// with the letter pools of ```exports```.
+/// Returns why the boards aren't valid if a board has more than ```max_guesses``` turns,
+/// or has turns after the word was guessed on it.
+fn race_boards(
+    turns: &[Turn],
+    exports: &[BoardExport],
+    max_guesses: i32,
+) -> Result<Vec<RaceBoard>, String> {
+    let mut boards = Vec::new();
+
+    for player in [Player::P1, Player::P2] {
+        let board_turns: Vec<&Turn> = turns.iter().filter(|turn| turn.player == player).collect();
+        let Some(last_turn) = board_turns.last() else {
+            continue;
+        };
+
+        if board_turns.len() > max_guesses as usize {
+            return Err(format!("a board has more than {} turns", max_guesses));
+        }
+
+        let word_length = letter_count(&last_turn.guessed_word);
+        let solved_turn = board_turns
+            .iter()
+            .position(|turn| turn.is_solved(word_length));
+        if solved_turn.is_some_and(|i| i + 1 != board_turns.len()) {
+            return Err(
+                "no turns should be played on a board after the word is guessed".to_string(),
+            );
+        }
+
+        let solved = solved_turn.is_some();
+        let finished = solved || board_turns.len() == max_guesses as usize;
+        let letterpool_state = exports
+            .iter()
+            .find(|board| board.player == player)
+            .map_or(0, |board| board.letterpool_state);
+
+        boards.push(RaceBoard {
+            player,
+            guess_num: board_turns.len() as i32,
+            letterpool_state,
+            finished_at: finished.then_some(last_turn.played_at),
+            solved,
+        });
+    }
+
+    Ok(boards)
+}
+
+/// An argument with info needed to update a round whenever a turn is made.
+#[derive(Debug, GraphQLInputObject, Serialize, Deserialize)]
+pub struct UpdateRound {
+    /// The id of the game the turn is made in.
+    game_id: String,
+    /// The turn made.
+    turn: NewTurn,
+    /// The state of all the letters in the round after the turn.
+    letterpool: i32,
+    /// The player whose turn it is after this turn.
+    next_player: Player,
+}
+
+impl UpdateRound {
+    /// Creates an update playing ```turn``` in the game with ```game_id```.
+    pub fn new(game_id: &str, turn: NewTurn, letterpool: i32, next_player: Player) -> Self {
+        UpdateRound {
+            game_id: game_id.to_string(),
+            turn,
+            letterpool,
+            next_player,
+        }
+    }
+
+    /// Gets the id of the game the turn is made in.
+    pub fn game_id(&self) -> &str {
+        &self.game_id
+    }
+
+    /// Gets the word guessed in the turn.
+    pub fn guess(&self) -> &str {
+        &self.turn.guess
+    }
+}
+
+/// A game that was just created, along with the tokens its players make changes to it with.
+#[derive(Debug, GraphQLObject, Serialize, Deserialize)]
+pub struct NewGame {
+    /// The id of the game.
+    game_id: String,
+
+    /// The token player 1 sends in the ```Player-Token``` header to make changes to the game.
+    p1_token: String,
+
+    /// The token player 2 sends in the ```Player-Token``` header to make changes to the game.
+    p2_token: String,
+}
+
+impl NewGame {
+    /// Creates a new game with the id ```game_id``` but without the tokens of its players,
+    /// for when the game was already created and its tokens aren't given out again.
+    pub fn without_tokens(game_id: String) -> Self {
+        NewGame {
+            game_id,
+            p1_token: String::new(),
+            p2_token: String::new(),
+        }
+    }
+
+    /// Gets the id of the game.
+    pub fn game_id(&self) -> &str {
+        &self.game_id
+    }
+
+    /// Gets the token of player 1.
+    pub fn p1_token(&self) -> &str {
+        &self.p1_token
+    }
+
+    /// Gets the token of player 2.
+    pub fn p2_token(&self) -> &str {
+        &self.p2_token
+    }
+}
+
+/// A game that is currently active/being played.
+#[derive(Debug, GraphQLObject, Serialize, Deserialize)]
+pub struct Game {
+    /// The id of a Game. Used by the database to identify each document.
+    _id: String,
+    // work with string instead of Uuid as mongodb stores a Uuid as some sort of object
+    // that is hard to recreate as a rust object when trying to query from this server
+    // as uuid doesn't properly serialize into bson for queries
+    // and can't use bson::uuid type for a GraphQLObject which does serialize well
+    /// The current round that is being played.
+    current_round: Round,
+
+    /// The rounds that have already been played, in the order they were played.
+    #[serde(default)]
+    rounds: Vec<Round>,
+
+    /// The settings the game is played with.
+    #[serde(default)]
+    config: GameConfig,
+
+    /// The id of the profile of player 1.
+    /// Is null if player 1 didn't play with a profile.
+    #[serde(default)]
+    p1_id: Option<String>,
+
+    /// The id of the profile of player 2.
+    /// Is null if player 2 didn't play with a profile.
+    #[serde(default)]
+    p2_id: Option<String>,
+
+    /// The points of player 1.
+    p1_points: i32,
+
+    /// The points of player 2.
+    p2_points: i32,
+
+    /// The points both players of a co-op game scored together.
+    /// Is always 0 if the game isn't co-op.
+    #[serde(default)]
+    team_points: i32,
+
+    /// The current round number that is currently being made.
+    /// This value starts at 1.
+    round_num: i32,
+
+    /// Whether the game is still being played or has ended.
+    #[serde(default)]
+    status: GameStatus,
+
+    /// When the game was created.
+    /// Is the unix epoch for games saved before the time was recorded.
+    #[serde(default = "legacy_timestamp", with = "chrono_datetime_as_bson_datetime")]
+    created_at: DateTime<Utc>,
+
+    /// When the game was last changed.
+    /// Is the unix epoch for games saved before the time was recorded.
+    #[serde(default = "legacy_timestamp", with = "chrono_datetime_as_bson_datetime")]
+    updated_at: DateTime<Utc>,
+
+    /// When the game ended.
+    /// Is null while the game is still being played.
+    #[serde(default, with = "chrono_datetime_as_bson_datetime_optional")]
+    finished_at: Option<DateTime<Utc>>,
+
+    /// The number of times the game has been changed.
+    /// Used to make sure a change isn't saved over another change it hasn't seen.
+    #[serde(default)]
+    version: i32,
+
+    /// The id of the game this game is a rematch of.
+    /// Is null if the game isn't a rematch.
+    #[serde(default)]
+    previous_game_id: Option<String>,
+
+    /// The player who asked for a rematch after the game ended.
+    /// Is null if no one has asked for a rematch.
+    #[serde(default)]
+    rematch_requested_by: Option<Player>,
+
+    /// The id of the rematch, once it has been accepted.
+    #[serde(default)]
+    rematch_game_id: Option<String>,
+
+    /// The ids of the profiles of both players, used to make sure a player only plays each daily challenge once.
+    /// Isn't stored for games without profiles.
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    player_ids: Vec<String>,
+
+    /// The token player 1 sends in the ```Player-Token``` header to make changes to the game, see ```check_token```.
+    /// Is null for games saved before players had tokens, and imported games, which any player can change.
+    #[graphql(skip)]
+    #[serde(default)]
+    p1_token: Option<String>,
+
+    /// The token player 2 sends in the ```Player-Token``` header to make changes to the game.
+    #[graphql(skip)]
+    #[serde(default)]
+    p2_token: Option<String>,
+
+    /// Whether the ratings of the players have been updated with the result of the game.
+    /// Games saved before this was recorded are treated as rated, so they are never rated again.
+    #[graphql(skip)]
+    #[serde(default = "legacy_rated")]
+    rated: bool,
+
+    /// The number of spectators currently watching the game.
+    /// Isn't stored with the game, it is counted when the game is queried with ```game``` or ```games```.
+    #[serde(skip)]
+    spectator_count: i32,
+}
+
+impl Game {
+    /// Used to create when a new game is started.
+    pub fn new_game(target_word: &str) -> Self {
+        let now = Utc::now();
+
+        Game {
+            _id: Uuid::new_v4().simple().to_string(),
+            current_round: Round::new_round(0, Player::P1, target_word.to_string()),
+            rounds: Vec::new(),
+            config: GameConfig::default(),
+            p1_id: None,
+            p2_id: None,
+            p1_points: 0,
+            p2_points: 0,
+            team_points: 0,
+            round_num: 0,
+            status: GameStatus::Active,
+            created_at: now,
+            updated_at: now,
+            finished_at: None,
+            version: 0,
+            previous_game_id: None,
+            rematch_requested_by: None,
+            rematch_game_id: None,
+            player_ids: Vec::new(),
+            p1_token: Some(Uuid::new_v4().simple().to_string()),
+            p2_token: Some(Uuid::new_v4().simple().to_string()),
+            rated: false,
+            spectator_count: 0,
+        }
+    }
+
+    /// Used to create a game playing the daily challenge of ```date```, whose word is ```target_word```.
+    /// The word is hidden until the round ends, as everyone plays the same word that day.
+    pub fn new_daily_challenge(target_word: &str, date: NaiveDate) -> Self {
+        let mut game = Game::new_game(target_word);
+        game.config.daily_challenge = Some(date);
+        let round = &mut game.current_round;
+        round.secret_word = Some(std::mem::take(&mut round.target_word));
+        game
+    }
+
+    /// Plays a turn in the current round.
+    ///
+    /// The round ends if the turn guessed the target word, giving a point to the player who guessed it,
+    /// or if there are no guesses left.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game is co-op, where guesses are proposed instead,
+    /// or a race, where guesses are played on each player's board,
+    /// if the game or round has already ended, the word of a secret word round hasn't been set,
+    /// or if the guess is not the same length as the target word.
+    pub fn play_turn(&mut self, update: UpdateRound) -> GraphqlServerResult<()> {
+        match self.config.mode {
+            GameMode::Versus | GameMode::SecretWord => (),
+            GameMode::CoOp => {
+                return Err(GraphqlServerError::new(
+                    "guesses of co-op games should be proposed, then confirmed".to_string(),
+                    &CODE422,
+                ))
+            }
+            GameMode::Race => {
+                return Err(GraphqlServerError::new(
+                    "guesses of race games should be played on the player's board".to_string(),
+                    &CODE422,
+                ))
+            }
+        }
+
+        let guess = self.check_guess(&update.turn.guess)?;
+        let player = self.current_round.current_player;
+
+        // players don't know a hidden word, so can't work out the letter states
+        let letter_state = match self.current_round.secret_word {
+            Some(_) => encode(&letter_states(&guess, self.current_round.answer())),
+            None => update.turn.letter_state,
+        };
+        let next_player = match self.config.mode {
+            // the guesser keeps guessing until the round ends
+            GameMode::SecretWord => player,
+            _ => update.next_player,
+        };
+
+        self.apply_turn(player, guess, letter_state, update.letterpool, next_player);
+        Ok(())
+    }
+
+    /// Sets the word of the current round of a secret word game on behalf of ```player```, who sets it.
+    /// The word is hidden from the guesser until the round ends.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game isn't a secret word game, the game has ended,
+    /// ```player``` doesn't set the word of the round, the word has already been set,
+    /// or the word isn't made of 1 to ```MAX_WORD_LENGTH``` letters.
+    pub fn set_secret_word(&mut self, player: Player, word: &str) -> GraphqlServerResult<()> {
+        if self.config.mode != GameMode::SecretWord {
+            return Err(GraphqlServerError::new(
+                "only secret word games have words set by the players".to_string(),
+                &CODE422,
+            ));
+        }
+        self.check_active()?;
+
+        let round = &mut self.current_round;
+        if round.setter != Some(player) {
+            return Err(GraphqlServerError::new(
+                "only the setter of the round can set its word".to_string(),
+                &CODE422,
+            ));
+        }
+        if !round.answer().is_empty() {
+            return Err(GraphqlServerError::new(
+                "word of the round has already been set".to_string(),
+                &CODE422,
+            ));
+        }
+
+        let word = normalize(word);
+        if !is_word(&word) || letter_count(&word) > MAX_WORD_LENGTH {
+            return Err(GraphqlServerError::new(
+                format!("word should be between 1 and {} letters", MAX_WORD_LENGTH),
+                &CODE422,
+            ));
+        }
+
+        round.secret_word = Some(word);
+        // the round starts once there is a word to guess
+        round.started_at = Utc::now();
+        Ok(())
+    }
+
+    /// Plays a guess of ```player``` on their own board in the current round of a race game.
+    ///
+    /// A board is finished once its player guesses the word or has no guesses left.
+    /// The round ends once it is won (see ```Round::race_result```), giving a point to the winner,
+    /// or once both boards are finished without the word being guessed.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game isn't a race, the game or round has already ended,
+    /// the guess is not the same length as the target word, or the player's board is already finished.
+    pub fn play_race_turn(
+        &mut self,
+        player: Player,
+        update: UpdateRound,
+    ) -> GraphqlServerResult<()> {
+        if self.config.mode != GameMode::Race {
+            return Err(GraphqlServerError::new(
+                "only race games have a board for each player".to_string(),
+                &CODE422,
+            ));
+        }
+
+        let guess = self.check_guess(&update.turn.guess)?;
+
+        let max_guesses = self.config.max_guesses;
+        let round = &mut self.current_round;
+        if round
+            .board(player)
+            .is_some_and(|board| board.finished_at.is_some())
+        {
+            return Err(GraphqlServerError::new(
+                "board is already finished, wait for the other player".to_string(),
+                &CODE422,
+            ));
+        }
+
+        let now = Utc::now();
+        let turn = Turn {
+            guessed_word: guess,
+            letter_state: update.turn.letter_state,
+            player,
+            played_at: now,
+        };
+        let solved = turn.is_solved(letter_count(round.answer()));
+
+        round.turns.push(turn);
+        round.guess_num += 1;
+
+        let board = round.board_mut(player);
+        board.guess_num += 1;
+        board.letterpool_state = update.letterpool;
+        if solved || board.guess_num >= max_guesses {
+            board.finished_at = Some(now);
+            board.solved = solved;
+        }
+
+        if let Some(winner) = round.race_result() {
+            round.ended_at = Some(now);
+            round.solved_by = winner;
+            self.end_round();
+        }
+
+        Ok(())
+    }
+
+    /// Gives ```player``` a hint of ```kind``` in the current round, see ```hints```.
+    /// The hint is recorded in the round, and costs the player the hint cost of the scoring rules,
+    /// or the team in a co-op game.
+    /// Letters eliminated by the hint are picked with ```seed```, and ruled out in the letterpool,
+    /// which in a race is the letterpool of ```player```'s board.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game is a best-of match, the game or round has ended,
+    /// the word hasn't been set, it isn't ```player```'s turn, ```player```'s race board is finished,
+    /// only one letter of the word is left to reveal, or no letters are left to eliminate.
+    pub fn request_hint(
+        &mut self,
+        player: Player,
+        kind: HintKind,
+        seed: u64,
+    ) -> GraphqlServerResult<()> {
+        // a best-of match is decided by rounds won, which hint costs would get in the way of
+        if self.config.best_of.is_some() {
+            return Err(GraphqlServerError::new(
+                "hints can't be requested in best-of matches".to_string(),
+                &CODE422,
+            ));
+        }
+        self.check_playing()?;
+
+        let round = &self.current_round;
+        let own_board = match self.config.mode {
+            GameMode::Versus | GameMode::SecretWord => {
+                if player != round.current_player {
+                    return Err(GraphqlServerError::new(
+                        "only the player whose turn it is can request a hint".to_string(),
+                        &CODE422,
+                    ));
+                }
+                false
+            }
+            GameMode::CoOp => false,
+            GameMode::Race => {
+                if round
+                    .board(player)
+                    .is_some_and(|board| board.finished_at.is_some())
+                {
+                    return Err(GraphqlServerError::new(
+                        "board is already finished, wait for the other player".to_string(),
+                        &CODE422,
+                    ));
+                }
+                true
+            }
+        };
+
+        // in a race, players only see the guesses and hints on their own board
+        let guesses: Vec<&str> = round
+            .turns
+            .iter()
+            .filter(|turn| !own_board || turn.player == player)
+            .map(|turn| turn.guessed_word.as_str())
+            .collect();
+        let hints: Vec<&Hint> = round
+            .hints
+            .iter()
+            .filter(|hint| !own_board || hint.player == player)
+            .collect();
+
+        let answer = round.answer();
+        let (position, letters) = match kind {
+            HintKind::RevealLetter => {
+                let positions = unrevealed_positions(answer, &guesses, &hints);
+                // revealing the last letter would give the word away
+                if positions.len() <= 1 {
+                    return Err(GraphqlServerError::new(
+                        "only one letter of the word is left to reveal".to_string(),
+                        &CODE422,
+                    ));
+                }
+                let position = positions[0];
+                let letter = letters(answer)[position].to_string();
+                (Some(position as i32), vec![letter])
+            }
+            HintKind::EliminateLetters => {
+                let alphabet = self.config.language.alphabet();
+                let eliminated = letters_to_eliminate(&alphabet, answer, &guesses, &hints, seed);
+                if eliminated.is_empty() {
+                    return Err(GraphqlServerError::new(
+                        "no letters are left to eliminate".to_string(),
+                        &CODE422,
+                    ));
+                }
+                (None, eliminated)
+            }
+        };
+
+        let hint = Hint {
+            player,
+            kind,
+            position,
+            letters,
+            requested_at: Utc::now(),
+        };
+        let cost = hint_score(&self.config, &hint);
+        if kind == HintKind::EliminateLetters {
+            let letterpool = match own_board {
+                true => &mut self.current_round.board_mut(player).letterpool_state,
+                false => &mut self.current_round.letterpool_state,
+            };
+            *letterpool = eliminate_letters(*letterpool, &hint.letters);
+        }
+        self.current_round.hints.push(hint);
+        self.add_score(cost);
+
+        Ok(())
+    }
+
+    /// Proposes a guess in the current round of a co-op game on behalf of ```player```.
+    /// The guess is only played once the partner of ```player``` confirms it.
+    /// Replaces the guess ```player``` proposed before, if it hasn't been confirmed yet.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game isn't co-op, the game or round has already ended,
+    /// the guess is not the same length as the target word,
+    /// or the partner has proposed a guess that should be confirmed or rejected first.
+    pub fn propose_guess(
+        &mut self,
+        player: Player,
+        update: UpdateRound,
+    ) -> GraphqlServerResult<()> {
+        self.check_co_op()?;
+        let guess = self.check_guess(&update.turn.guess)?;
+
+        if self
+            .current_round
+            .proposal
+            .as_ref()
+            .is_some_and(|proposal| proposal.player != player)
+        {
+            return Err(GraphqlServerError::new(
+                "partner has already proposed a guess, confirm or reject it first".to_string(),
+                &CODE422,
+            ));
+        }
+
+        self.current_round.proposal = Some(Proposal {
+            player,
+            guessed_word: guess,
+            letter_state: update.turn.letter_state,
+            letterpool_state: update.letterpool,
+            proposed_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    /// Confirms the guess proposed by the partner of ```player```, playing it as the partner's turn.
+    /// The points of a solved round go to the team.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game isn't co-op, the game has already ended,
+    /// or the partner hasn't proposed a guess.
+    pub fn confirm_guess(&mut self, player: Player) -> GraphqlServerResult<()> {
+        self.check_co_op()?;
+        self.check_active()?;
+        let proposal = self.take_partner_proposal(player)?;
+
+        self.apply_turn(
+            proposal.player,
+            proposal.guessed_word,
+            proposal.letter_state,
+            proposal.letterpool_state,
+            player,
+        );
+        Ok(())
+    }
+
+    /// Rejects the guess proposed by the partner of ```player```, so another guess can be proposed.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game isn't co-op, the game has already ended,
+    /// or the partner hasn't proposed a guess.
+    pub fn reject_guess(&mut self, player: Player) -> GraphqlServerResult<()> {
+        self.check_co_op()?;
+        self.check_active()?;
+        self.take_partner_proposal(player)?;
+        Ok(())
+    }
+
+    /// Removes the guess proposed by the partner of ```player``` from the current round.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the partner hasn't proposed a guess.
+    fn take_partner_proposal(&mut self, player: Player) -> GraphqlServerResult<Proposal> {
+        let round = &mut self.current_round;
+        match round.proposal.take() {
+            Some(proposal) if proposal.player == player.other() => Ok(proposal),
+            proposal => {
+                round.proposal = proposal;
+                Err(GraphqlServerError::new(
+                    "partner hasn't proposed a guess".to_string(),
+                    &CODE422,
+                ))
+            }
+        }
+    }
+
+    /// Checks that the game is co-op.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game isn't co-op.
+    fn check_co_op(&self) -> GraphqlServerResult<()> {
+        match self.config.mode {
+            GameMode::CoOp => Ok(()),
+            GameMode::Versus | GameMode::Race | GameMode::SecretWord => {
+                Err(GraphqlServerError::new(
+                    "guesses can only be proposed in co-op games".to_string(),
+                    &CODE422,
+                ))
+            }
+        }
+    }
+
+    /// Checks that the current round is being played, so guesses and hints can be made in it.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game or round has already ended, or the word of a secret word round hasn't been set.
+    fn check_playing(&self) -> GraphqlServerResult<()> {
+        self.check_active()?;
+
+        let round = &self.current_round;
+        if round.is_over() {
+            return Err(GraphqlServerError::new(
+                "round has already ended, start a new round to keep playing".to_string(),
+                &CODE422,
+            ));
+        }
+        if round.answer().is_empty() {
+            return Err(GraphqlServerError::new(
+                "waiting for the setter to set the word of the round".to_string(),
+                &CODE422,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that ```guess``` can be played in the current round, returning the normalized guess.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game or round has already ended, the word of a secret word round hasn't been set,
+    /// or if the guess is not the same length as the target word.
+    fn check_guess(&self, guess: &str) -> GraphqlServerResult<String> {
+        self.check_playing()?;
+
+        // the guess is compared letter by letter, so it has to be written the same way as the target word
+        let guess = normalize(guess);
+        let word_length = letter_count(self.current_round.answer());
+        if letter_count(&guess) != word_length {
+            return Err(GraphqlServerError::new(
+                format!("guess should be {} letters long", word_length),
+                &CODE422,
+            ));
+        }
+
+        Ok(guess)
+    }
+
+    /// Plays the checked ```guess``` of ```player``` in the current round, see ```check_guess```.
+    fn apply_turn(
+        &mut self,
+        player: Player,
+        guess: String,
+        letter_state: i32,
+        letterpool: i32,
+        next_player: Player,
+    ) {
+        let round = &mut self.current_round;
+        let word_length = letter_count(round.answer());
+
+        let now = Utc::now();
+        let turn = Turn {
+            guessed_word: guess,
+            letter_state,
+            player,
+            played_at: now,
+        };
+        let solved = turn.is_solved(word_length);
+
+        round.turns.push(turn);
+        round.letterpool_state = letterpool;
+        round.guess_num += 1;
+        round.current_player = player;
+
+        if solved {
+            round.ended_at = Some(now);
+            round.solved_by = Some(player);
+        } else if round.guess_num >= self.config.max_guesses {
+            round.ended_at = Some(now);
+        } else {
+            round.current_player = next_player;
+        }
+
+        if round.is_over() {
+            self.end_round();
+        }
+    }
+
+    /// Wraps up the current round once it has ended, revealing a secret word and scoring the round with the rules of the game.
+    /// Ends the game if a best-of match has been decided, or the game is a daily challenge.
+    fn end_round(&mut self) {
+        self.current_round.reveal();
+
+        let rounds: Vec<&Round> = self.all_rounds().collect();
+        if let Some(score) = score_rounds(&self.config, &rounds).last().copied() {
+            self.add_score(score);
+        }
+
+        let rounds_played = self.rounds.len() as i32 + 1;
+        if self
+            .config
+            .match_decided(rounds_played, self.p1_points, self.p2_points)
+        {
+            self.finish();
+        }
+
+        // a daily challenge only has the one round
+        if self.config.daily_challenge.is_some() {
+            self.finish();
+        }
+    }
+
+    /// Adds the points scored in a round to the points of the game.
+    fn add_score(&mut self, score: RoundScore) {
+        self.p1_points += score.p1;
+        self.p2_points += score.p2;
+        self.team_points += score.team;
+    }
+
+    /// Starts the next round with a new target word.
+    /// The player who did not start the previous round starts this round.
+    /// The next round of a secret word game has no target word yet,
+    /// and waits for the player who guessed the previous word to set one.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game has ended, the current round is still being played,
+    /// or a target word is given for a secret word game.
+    pub fn start_round(&mut self, target_word: &str) -> GraphqlServerResult<()> {
+        self.check_active()?;
+        self.check_target_word(target_word)?;
+
+        if self.config.daily_challenge.is_some() {
+            return Err(GraphqlServerError::new(
+                "daily challenges only have one round".to_string(),
+                &CODE422,
+            ));
+        }
+
+        if !self.current_round.is_over() {
+            return Err(GraphqlServerError::new(
+                "current round is still being played".to_string(),
+                &CODE422,
+            ));
+        }
+
+        let next_round = match self.current_round.setter {
+            Some(setter) => Round::new_secret_word_round(setter.other()),
+            None => {
+                let starting_player = self.current_round.starting_player().other();
+                Round::new_round(0, starting_player, target_word.to_string())
+            }
+        };
+
+        let previous_round = std::mem::replace(&mut self.current_round, next_round);
+        self.rounds.push(previous_round);
+        self.round_num += 1;
+
+        // the points are level after the last round of the match, or the game would have ended
+        self.current_round.tiebreak = self
+            .config
+            .best_of
+            .is_some_and(|best_of| self.rounds.len() as i32 >= best_of);
+
+        Ok(())
+    }
+
+    /// Checks that ```target_word``` can start a round, as the players set the words of secret word games.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if a target word is given for a secret word game.
+    fn check_target_word(&self, target_word: &str) -> GraphqlServerResult<()> {
+        if self.config.mode == GameMode::SecretWord && !target_word.is_empty() {
+            return Err(GraphqlServerError::new(
+                "words of secret word games are set by the players".to_string(),
+                &CODE422,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Gets whether the word of the current round is hidden,
+    /// as it is the word of a secret word round or daily challenge still being played.
+    pub fn hides_word(&self) -> bool {
+        self.current_round.secret_word.is_some()
+    }
+
+    /// Checks if the game plays the daily challenge of ```today``` or a later day,
+    /// whose answer is still being played by other pairs of players.
+    pub fn has_daily_answer(&self, today: NaiveDate) -> bool {
+        self.config
+            .daily_challenge
+            .is_some_and(|date| date >= today)
+    }
+
+    /// Hides the answer of the game if it plays the daily challenge of ```today``` or a later day,
+    /// leaving the letter states of the guesses, see ```Game::has_daily_answer```.
+    /// Guessed words are hidden too, as the last guess of a solved round is the answer.
+    pub fn hide_daily_answer(&mut self, today: NaiveDate) {
+        if !self.has_daily_answer(today) {
+            return;
+        }
+        for round in self
+            .rounds
+            .iter_mut()
+            .chain(std::iter::once(&mut self.current_round))
+        {
+            round.hide_answer();
+        }
+    }
+
+    /// Gets the number of times the game has been changed
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// Gets the game with the tokens its players make changes to it with, to give to the players who created it.
+    pub fn to_new_game(&self) -> NewGame {
+        NewGame {
+            game_id: self.id(),
+            p1_token: self.p1_token.clone().unwrap_or_default(),
+            p2_token: self.p2_token.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Checks that ```token``` is the token of ```player```, so the request was made by them.
+    /// Any token is accepted for a game without player tokens.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 403 error if ```token``` isn't the token of the player.
+    pub fn check_token(&self, player: Player, token: Option<&str>) -> GraphqlServerResult<()> {
+        let player_token = match player {
+            Player::P1 => &self.p1_token,
+            Player::P2 => &self.p2_token,
+        };
+
+        match player_token {
+            Some(player_token) if token != Some(player_token.as_str()) => {
+                Err(GraphqlServerError::new(
+                    format!(
+                        "only {:?} can make this change, send their token in the Player-Token header",
+                        player
+                    ),
+                    &CODE403,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the number of spectators currently watching the game
+    pub fn set_spectator_count(&mut self, spectator_count: i32) {
+        self.spectator_count = spectator_count;
+    }
+
+    /// Records that the game was just changed.
+    pub fn touch(&mut self) {
+        self.updated_at = Utc::now();
+        self.version += 1;
+    }
+
+    /// Checks that the game is still being played.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game has ended.
+    fn check_active(&self) -> GraphqlServerResult<()> {
+        match self.status {
+            GameStatus::Active => Ok(()),
+            GameStatus::Finished => Err(GraphqlServerError::new(
+                "game has already ended".to_string(),
+                &CODE422,
+            )),
+        }
+    }
+
+    /// Gets the id of the game
+    pub fn id(&self) -> String {
+        self._id.clone()
+    }
+
+    /// Gets which player of the game has the profile with ```player_id```, if either does
+    pub fn player_of(&self, player_id: &str) -> Option<Player> {
+        [Player::P1, Player::P2]
+            .into_iter()
+            .find(|player| self.player_id(*player) == Some(player_id))
+    }
+
+    /// Gets the round being played, or the last round played if it has ended
+    pub fn current_round(&self) -> &Round {
+        &self.current_round
+    }
+
+    /// Gets every round of the game in the order they were played, including the current round
+    pub fn all_rounds(&self) -> impl Iterator<Item = &Round> {
+        self.rounds
+            .iter()
+            .chain(std::iter::once(&self.current_round))
+    }
+
+    /// Gets the id of the profile of ```player```, if they have one
+    pub fn player_id(&self, player: Player) -> Option<&str> {
+        match player {
+            Player::P1 => self.p1_id.as_deref(),
+            Player::P2 => self.p2_id.as_deref(),
+        }
+    }
+
+    /// Sets the profiles of the players of the game.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if both players are given the same profile,
+    /// or player 2 is given a profile in a game against a bot.
+    pub fn set_players(
+        &mut self,
+        p1_id: Option<String>,
+        p2_id: Option<String>,
+    ) -> GraphqlServerResult<()> {
+        if p1_id.is_some() && p1_id == p2_id {
+            return Err(GraphqlServerError::new(
+                "players of a game should have different profiles".to_string(),
+                &CODE422,
+            ));
+        }
+        if self.config.bot.is_some() && p2_id.is_some() {
+            return Err(GraphqlServerError::new(
+                "player 2 of a game against a bot is the bot".to_string(),
+                &CODE422,
+            ));
+        }
+
+        self.p1_id = p1_id;
+        self.p2_id = p2_id;
+        self.player_ids = self.p1_id.iter().chain(&self.p2_id).cloned().collect();
+        Ok(())
+    }
+
+    /// Gets the result of the game for ```player```, decided by who has more points.
+    pub fn outcome(&self, player: Player) -> Outcome {
+        let (points, opponent_points) = match player {
+            Player::P1 => (self.p1_points, self.p2_points),
+            Player::P2 => (self.p2_points, self.p1_points),
+        };
+
+        match points.cmp(&opponent_points) {
+            Ordering::Greater => Outcome::Win,
+            Ordering::Equal => Outcome::Draw,
+            Ordering::Less => Outcome::Loss,
+        }
+    }
+
+    /// Ends the game, so it is no longer being played.
+    /// Returns false if the game had already ended.
+    pub fn finish(&mut self) -> bool {
+        if self.status == GameStatus::Finished {
+            return false;
+        }
+
+        self.status = GameStatus::Finished;
+        self.finished_at = Some(Utc::now());
+        // no one can guess the word anymore
+        self.current_round.reveal();
+        true
+    }
+
+    /// Checks if the game has finished, but the ratings of its players haven't been updated yet.
+    pub fn needs_rating(&self) -> bool {
+        self.status == GameStatus::Finished && !self.rated
+    }
+
+    /// Records that the ratings of the players have been updated with the result of the game.
+    pub fn set_rated(&mut self) {
+        self.rated = true;
+    }
+
+    /// Asks for a rematch of the game on behalf of ```player```.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game is still being played or against a bot, a rematch was already accepted,
+    /// or the other player already asked for a rematch, which should be accepted instead.
+    pub fn request_rematch(&mut self, player: Player) -> GraphqlServerResult<()> {
+        self.check_rematch_open()?;
+
+        if self.rematch_requested_by == Some(player.other()) {
+            return Err(GraphqlServerError::new(
+                "the other player already asked for a rematch, accept it instead".to_string(),
+                &CODE422,
+            ));
+        }
+
+        self.rematch_requested_by = Some(player);
+        Ok(())
+    }
+
+    /// Accepts the rematch asked for by the other player of ```player```.
+    /// Returns the rematch, which has the same players and is started by the player who didn't start this game.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game is still being played, a rematch was already accepted,
+    /// the other player hasn't asked for a rematch, or a target word is given for a secret word game.
+    pub fn accept_rematch(
+        &mut self,
+        player: Player,
+        target_word: &str,
+    ) -> GraphqlServerResult<Game> {
+        self.check_rematch_open()?;
+        self.check_target_word(target_word)?;
+
+        if self.rematch_requested_by != Some(player.other()) {
+            return Err(GraphqlServerError::new(
+                "the other player hasn't asked for a rematch".to_string(),
+                &CODE422,
+            ));
+        }
+
+        let first_round = self.rounds.first().unwrap_or(&self.current_round);
+        let starting_player = first_round.starting_player().other();
+
+        let mut rematch = Game::new_game(target_word);
+        rematch.current_round = match first_round.setter {
+            Some(setter) => Round::new_secret_word_round(setter.other()),
+            None => Round::new_round(0, starting_player, target_word.to_string()),
+        };
+        rematch.p1_id = self.p1_id.clone();
+        rematch.p2_id = self.p2_id.clone();
+        rematch.player_ids = self.player_ids.clone();
+        // the rematch is played the same way, but isn't part of a daily challenge
+        rematch.config = GameConfig {
+            daily_challenge: None,
+            ..self.config.clone()
+        };
+        rematch.previous_game_id = Some(self.id());
+        // the players keep their tokens, so they can play the rematch straight away
+        rematch.p1_token = self.p1_token.clone();
+        rematch.p2_token = self.p2_token.clone();
+
+        self.rematch_game_id = Some(rematch.id());
+        Ok(rematch)
+    }
+
+    /// Checks that the game has ended and hasn't been rematched yet.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game is still being played, a rematch was already accepted,
+    /// or the game was played against a bot.
+    fn check_rematch_open(&self) -> GraphqlServerResult<()> {
+        // a bot only plays the game matchmaking paired it into
+        if self.config.bot.is_some() {
+            return Err(GraphqlServerError::new(
+                "games against a bot can't be rematched".to_string(),
+                &CODE422,
+            ));
+        }
+
+        if self.status != GameStatus::Finished {
+            return Err(GraphqlServerError::new(
+                "game should end before a rematch".to_string(),
+                &CODE422,
+            ));
+        }
+
+        if self.rematch_game_id.is_some() {
+            return Err(GraphqlServerError::new(
+                "rematch has already been accepted".to_string(),
+                &CODE422,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Exports the game, see ```export``` for the format.
+    pub fn to_export(&self) -> GameExport {
+        GameExport {
+            format_version: EXPORT_FORMAT_VERSION,
+            id: self.id(),
+            config: self.config.clone(),
+            p1_id: self.p1_id.clone(),
+            p2_id: self.p2_id.clone(),
+            p1_points: self.p1_points,
+            p2_points: self.p2_points,
+            team_points: self.team_points,
+            status: self.status,
+            created_at: self.created_at,
+            finished_at: self.finished_at,
+            rounds: self.all_rounds().map(Round::to_export).collect(),
+        }
+    }
+
+    /// Reconstructs an exported game, checking that it could have been played.
+    /// The game keeps the id it was exported with.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the game has no rounds, a round or turn isn't valid
+    /// (see ```Round::from_export```), the points don't match the rounds solved,
+    /// or the players or status don't make sense.
+    pub fn from_export(export: GameExport) -> GraphqlServerResult<Game> {
+        let invalid = |reason: &str| {
+            Err(GraphqlServerError::new(
+                format!("exported game isn't valid: {}", reason),
+                &CODE422,
+            ))
+        };
+
+        if export.config.max_guesses < 1 {
+            return invalid("max guesses should be at least 1");
+        }
+        if export.p1_id.is_some() && export.p1_id == export.p2_id {
+            return invalid("players should have different profiles");
+        }
+        if export.config.bot.is_some()
+            && (export.config.mode != GameMode::Versus || export.p2_id.is_some())
+        {
+            return invalid("only player 2 of a versus game can be a bot");
+        }
+        if (export.status == GameStatus::Finished) != export.finished_at.is_some() {
+            return invalid("only finished games should have a finish time");
+        }
+
+        let round_count = export.rounds.len();
+        if export.config.daily_challenge.is_some() && round_count > 1 {
+            return invalid("daily challenges should only have 1 round");
+        }
+
+        let mut rounds = Vec::with_capacity(round_count);
+        if let Some(best_of) = export.config.best_of {
+            if !(1..=MAX_BEST_OF).contains(&best_of)
+                || matches!(export.config.mode, GameMode::CoOp | GameMode::SecretWord)
+            {
+                return invalid("best-of match isn't valid");
+            }
+        }
+        if export.config.scoring.check().is_err()
+            || (export.config.best_of.is_some() && !export.config.scoring.is_default())
+        {
+            return invalid("scoring rules aren't valid");
+        }
+
+        let (mut p1_wins, mut p2_wins) = (0, 0);
+        for (i, round) in export.rounds.into_iter().enumerate() {
+            let mut round = Round::from_export(round, &export.config)?;
+
+            // only the round being played when exported can still be going
+            if i + 1 < round_count && !round.is_over() {
+                return invalid("every round but the last should have ended");
+            }
+
+            // a best-of match ends as soon as it is decided
+            match round.solved_by {
+                Some(Player::P1) => p1_wins += 1,
+                Some(Player::P2) => p2_wins += 1,
+                None => (),
+            }
+            if round.is_over() && export.config.match_decided(i as i32 + 1, p1_wins, p2_wins) {
+                if i + 1 < round_count {
+                    return invalid("no rounds should be played after the match is decided");
+                }
+                if export.status != GameStatus::Finished {
+                    return invalid("match should end once it is decided");
+                }
+            }
+            round.tiebreak = export
+                .config
+                .best_of
+                .is_some_and(|best_of| i as i32 >= best_of);
+
+            rounds.push(round);
+        }
+
+        let Some(current_round) = rounds.pop() else {
+            return invalid("game should have at least 1 round");
+        };
+
+        let all_rounds: Vec<&Round> = rounds
+            .iter()
+            .chain(std::iter::once(&current_round))
+            .collect();
+        if export.config.best_of.is_some() && all_rounds.iter().any(|round| !round.hints.is_empty())
+        {
+            return invalid("hints can't be requested in best-of matches");
+        }
+        let hint_scores = all_rounds
+            .iter()
+            .flat_map(|round| &round.hints)
+            .map(|hint| hint_score(&export.config, hint));
+        let (p1_points, p2_points, team_points) = score_rounds(&export.config, &all_rounds)
+            .into_iter()
+            .chain(hint_scores)
+            .fold((0, 0, 0), |(p1, p2, team), score| {
+                (p1 + score.p1, p2 + score.p2, team + score.team)
+            });
+        if (export.p1_points, export.p2_points, export.team_points)
+            != (p1_points, p2_points, team_points)
+        {
+            return invalid("points should match the rounds scored with the scoring rules");
+        }
+
+        let player_ids = export.p1_id.iter().chain(&export.p2_id).cloned().collect();
+
+        Ok(Game {
+            _id: export.id,
+            round_num: rounds.len() as i32,
+            current_round,
+            rounds,
+            config: export.config,
+            p1_id: export.p1_id,
+            p2_id: export.p2_id,
+            p1_points: export.p1_points,
+            p2_points: export.p2_points,
+            team_points: export.team_points,
+            status: export.status,
+            created_at: export.created_at,
+            updated_at: Utc::now(),
+            finished_at: export.finished_at,
+            version: 0,
+            previous_game_id: None,
+            rematch_requested_by: None,
+            rematch_game_id: None,
+            player_ids,
+            p1_token: None,
+            p2_token: None,
+            // imported games are records of games played elsewhere, so they don't change ratings
+            rated: true,
+            spectator_count: 0,
+        })
+    }
+
+    /// Gets the day of the daily challenge the game plays, if it is a daily challenge.
+    pub fn daily_challenge(&self) -> Option<NaiveDate> {
+        self.config.daily_challenge
+    }
+
+    /// Gets the points both players of a co-op game scored together.
+    pub fn team_points(&self) -> i32 {
+        self.team_points
+    }
+
+    /// Gets the settings the game is played with.
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Sets the settings the game is played with.
+    /// A secret word game starts waiting for player 1 to set the word of the first round.
+    pub fn set_config(&mut self, config: GameConfig) {
+        if config.mode == GameMode::SecretWord && self.current_round.turns.is_empty() {
+            self.current_round = Round::new_secret_word_round(Player::P1);
+        }
+        self.config = config;
+    }
+
+    /// Gets whether the game is still being played
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    /// Gets when the game was created
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// Gets the current round number of the game
+    pub fn round_num(&self) -> i32 {
+        self.round_num
+    }
+
+    /// Provide a new id for the game, as if making a new game.
+    pub fn new_id(&mut self) {
+        self._id = Uuid::new_v4().simple().to_string();
+    }
+
+    /// Sets the id of the game.
+    /// Does nothing if id format is incorrect.
+    pub fn set_id(&mut self, id: &str) {
+        let check = Self::parse_id(id);
+
+        if check.is_ok() {
+            self._id = id.to_string();
         }
 
         // else do nothing
@@ -145,10 +2441,10 @@ impl Game {
     /// # Errors
     ///
     /// Will throw a 422 error if the id provided isn't a valid uuid.
-    pub fn parse_id(id: &String) -> GraphqlServerResult<String> {
+    pub fn parse_id(id: &str) -> GraphqlServerResult<String> {
         // will convert into a uuid, then back into the simple string format
 
-        let uuid_result = Uuid::try_parse(&id);
+        let uuid_result = Uuid::try_parse(id);
 
         match uuid_result {
             Ok(uuid) => Ok(uuid.simple().to_string()),
@@ -160,3 +2456,396 @@ impl Game {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn update(game: &Game, guess: &str, letter_state: i32, next_player: Player) -> UpdateRound {
+        UpdateRound {
+            game_id: game.id(),
+            turn: NewTurn {
+                guess: guess.to_string(),
+                letter_state,
+            },
+            letterpool: 0,
+            next_player,
+        }
+    }
+
+    #[test]
+    fn solving_ends_round_and_gives_point() {
+        let mut game = Game::new_game("words");
+
+        let miss = update(&game, "crane", 0b01_01_10_01_01, Player::P2);
+        game.play_turn(miss).unwrap();
+        assert_eq!(game.current_round.current_player, Player::P2);
+        assert!(!game.current_round.is_over());
+
+        let solve = update(&game, "words", 0b11_11_11_11_11, Player::P1);
+        game.play_turn(solve).unwrap();
+        assert!(game.current_round.is_over());
+        assert_eq!(game.p1_points, 0);
+        assert_eq!(game.p2_points, 1);
+        assert_eq!(game.current_round.turns[1].player, Player::P2);
+        assert_eq!(game.current_round.solved_by, Some(Player::P2));
+    }
+
+    #[test]
+    fn running_out_of_guesses_ends_round() {
+        let mut game = Game::new_game("words");
+
+        for _ in 0..MAX_GUESSES {
+            let next_player = game.current_round.current_player.other();
+            let miss = update(&game, "crane", 0b01_01_01_01_01, next_player);
+            game.play_turn(miss).unwrap();
+        }
+
+        assert!(game.current_round.is_over());
+        assert_eq!(game.p1_points + game.p2_points, 0);
+
+        let late = update(&game, "words", 0b11_11_11_11_11, Player::P1);
+        assert!(game.play_turn(late).is_err());
+    }
+
+    #[test]
+    fn games_saved_before_timestamps_are_read() {
+        let saved = bson::doc! {
+            "_id": Game::new_game("words").id(),
+            "current_round": {
+                "turns": [{"guessed_word": "crane", "letter_state": 0b01_01_10_01_01}],
+                "letterpool_state": 0,
+                "guess_num": 1,
+                "current_player": "P2",
+                "target_word": "words",
+            },
+            "p1_points": 0,
+            "p2_points": 0,
+            "round_num": 0,
+        };
+
+        let game: Game = bson::from_document(saved).unwrap();
+        assert_eq!(game.created_at, DateTime::<Utc>::default());
+        assert_eq!(game.updated_at, DateTime::<Utc>::default());
+        assert_eq!(game.current_round.turns[0].player, Player::P1);
+        assert_eq!(game.version, 0);
+    }
+
+    #[test]
+    fn display_name_is_checked() {
+        let player = PlayerProfile::new_player("  wordsmith ").unwrap();
+        assert_eq!(player.display_name, "wordsmith");
+
+        assert!(PlayerProfile::new_player("   ").is_err());
+        assert!(PlayerProfile::new_player(&"a".repeat(MAX_DISPLAY_NAME_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn only_the_secret_of_a_player_adds_them_to_games() {
+        let mut player = PlayerProfile::new_player("wordsmith").unwrap();
+        let secret = player.secret.clone();
+        assert!(player.check_secret(secret.as_deref()).is_ok());
+        assert!(player.check_secret(Some("guess")).is_err());
+        assert!(player.check_secret(None).is_err());
+
+        player.new_secret();
+        assert!(player.check_secret(secret.as_deref()).is_err());
+
+        // profiles created before secrets can't be added until they are issued one
+        player.secret = None;
+        assert!(player.check_secret(None).is_err());
+    }
+
+    #[test]
+    fn new_round_only_starts_after_round_ends() {
+        let mut game = Game::new_game("words");
+        assert!(game.start_round("crane").is_err());
+
+        let solve = update(&game, "words", 0b11_11_11_11_11, Player::P2);
+        game.play_turn(solve).unwrap();
+        game.start_round("crane").unwrap();
+
+        assert_eq!(game.rounds.len(), 1);
+        assert_eq!(game.round_num, 1);
+        assert_eq!(game.current_round.current_player, Player::P2);
+        assert_eq!(game.current_round.target_word, "crane");
+    }
+
+    #[test]
+    fn rematch_swaps_starting_player() {
+        let mut game = Game::new_game("words");
+        game.set_players(Some("me".to_string()), Some("them".to_string()))
+            .unwrap();
+        assert!(game.request_rematch(Player::P1).is_err());
+
+        game.finish();
+        game.request_rematch(Player::P1).unwrap();
+        assert!(game.accept_rematch(Player::P1, "crane").is_err());
+
+        let rematch = game.accept_rematch(Player::P2, "crane").unwrap();
+        assert_eq!(rematch.current_round.current_player, Player::P2);
+        assert_eq!(rematch.p1_id.as_deref(), Some("me"));
+        assert_eq!(rematch.previous_game_id, Some(game.id()));
+        assert_eq!(game.rematch_game_id, Some(rematch.id()));
+        assert!(game.accept_rematch(Player::P2, "crane").is_err());
+    }
+
+    #[test]
+    fn daily_answer_is_hidden_until_the_day_is_over() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let mut game = Game::new_daily_challenge("words", date);
+        let solve = update(&game, "words", 0, Player::P1);
+        game.play_turn(solve).unwrap();
+        let letter_state = game.current_round.turns[0].letter_state;
+
+        // the day is over, so everyone has played it
+        game.hide_daily_answer(date.succ_opt().unwrap());
+        assert_eq!(game.current_round.target_word, "words");
+
+        game.hide_daily_answer(date);
+        assert_eq!(game.current_round.target_word, "");
+        assert_eq!(game.current_round.turns[0].guessed_word, "");
+        assert_eq!(game.current_round.turns[0].letter_state, letter_state);
+    }
+
+    #[test]
+    fn daily_challenge_ends_with_its_round() {
+        let date = NaiveDate::from_ymd_opt(2024, 7, 17).unwrap();
+        let mut game = Game::new_daily_challenge("words", date);
+        assert_eq!(game.current_round.target_word, "");
+        assert!(game.hides_word());
+
+        // letter states are worked out by the server, as the players don't know the word
+        let miss = update(&game, "crane", 0b11_11_11_11_11, Player::P2);
+        game.play_turn(miss).unwrap();
+        assert_eq!(game.current_round.turns[0].letter_state, 0b01_01_01_10_01);
+        assert_eq!(game.status, GameStatus::Active);
+
+        let solve = update(&game, "words", 0, Player::P1);
+        game.play_turn(solve).unwrap();
+
+        assert_eq!(game.status, GameStatus::Finished);
+        assert_eq!(game.current_round.target_word, "words");
+        assert!(game.start_round("crane").is_err());
+    }
+
+    #[test]
+    fn guesses_are_compared_letter_by_letter() {
+        let mut game = Game::new_game("Años");
+
+        // "n" followed by a combining tilde is still 4 letters
+        let solve = update(&game, "an\u{303}os", 0b11_11_11_11, Player::P2);
+        game.play_turn(solve).unwrap();
+
+        assert_eq!(game.current_round.target_word, "años");
+        assert_eq!(game.current_round.solved_by, Some(Player::P1));
+    }
+
+    #[test]
+    fn co_op_guesses_are_confirmed_by_partner() {
+        let mut game = Game::new_game("words");
+        game.config.mode = GameMode::CoOp;
+
+        let miss = update(&game, "crane", 0b01_01_10_01_01, Player::P2);
+        assert!(game.play_turn(miss).is_err());
+
+        let miss = update(&game, "crane", 0b01_01_10_01_01, Player::P2);
+        game.propose_guess(Player::P1, miss).unwrap();
+        // only the partner can confirm, and can't propose over the guess
+        assert!(game.confirm_guess(Player::P1).is_err());
+        // the proposer can't confirm as their partner without the partner's token
+        let p1_token = game.p1_token.clone();
+        assert!(game.check_token(Player::P2, p1_token.as_deref()).is_err());
+        assert!(game.check_token(Player::P1, p1_token.as_deref()).is_ok());
+        let other = update(&game, "words", 0b11_11_11_11_11, Player::P2);
+        assert!(game.propose_guess(Player::P2, other).is_err());
+
+        game.reject_guess(Player::P2).unwrap();
+        assert!(game.current_round.proposal.is_none());
+        assert!(game.current_round.turns.is_empty());
+
+        let solve = update(&game, "words", 0b11_11_11_11_11, Player::P2);
+        game.propose_guess(Player::P1, solve).unwrap();
+        game.confirm_guess(Player::P2).unwrap();
+
+        assert!(game.current_round.is_over());
+        assert_eq!(game.current_round.solved_by, Some(Player::P1));
+        assert_eq!(game.team_points, 1);
+        assert_eq!(game.p1_points + game.p2_points, 0);
+        assert!(Game::from_export(game.to_export()).is_ok());
+    }
+
+    #[test]
+    fn race_is_won_in_fewest_guesses() {
+        let mut game = Game::new_game("words");
+        game.config.mode = GameMode::Race;
+        let miss = 0b01_01_10_01_01;
+        let solve = 0b11_11_11_11_11;
+
+        assert!(game
+            .play_turn(update(&game, "crane", miss, Player::P2))
+            .is_err());
+
+        for _ in 0..2 {
+            let guess = update(&game, "crane", miss, Player::P2);
+            game.play_race_turn(Player::P1, guess).unwrap();
+        }
+        let guess = update(&game, "crane", miss, Player::P1);
+        game.play_race_turn(Player::P2, guess).unwrap();
+
+        // player 2 can still guess the word in fewer guesses
+        let guess = update(&game, "words", solve, Player::P2);
+        game.play_race_turn(Player::P1, guess).unwrap();
+        assert!(!game.current_round.is_over());
+        let late = update(&game, "words", solve, Player::P2);
+        assert!(game.play_race_turn(Player::P1, late).is_err());
+
+        let guess = update(&game, "words", solve, Player::P1);
+        game.play_race_turn(Player::P2, guess).unwrap();
+
+        assert!(game.current_round.is_over());
+        assert_eq!(game.current_round.solved_by, Some(Player::P2));
+        assert_eq!((game.p1_points, game.p2_points), (0, 1));
+        assert_eq!(game.current_round.board(Player::P1).unwrap().guess_num, 3);
+        assert!(Game::from_export(game.to_export()).is_ok());
+    }
+
+    #[test]
+    fn race_ends_once_other_player_cant_win() {
+        let mut game = Game::new_game("words");
+        game.config.mode = GameMode::Race;
+        let solve = 0b11_11_11_11_11;
+
+        let guess = update(&game, "words", solve, Player::P1);
+        game.play_race_turn(Player::P2, guess).unwrap();
+
+        // player 1 could only tie on guesses, and would be slower
+        assert!(game.current_round.is_over());
+        assert_eq!(game.current_round.solved_by, Some(Player::P2));
+    }
+
+    #[test]
+    fn secret_words_are_hidden_and_scored_in_pairs() {
+        let mut game = Game::new_game("");
+        game.set_config(GameConfig {
+            mode: GameMode::SecretWord,
+            ..GameConfig::default()
+        });
+
+        let early = update(&game, "crane", 0, Player::P1);
+        assert!(game.play_turn(early).is_err());
+        assert!(game.set_secret_word(Player::P2, "words").is_err());
+        game.set_secret_word(Player::P1, "Words").unwrap();
+        assert_eq!(game.current_round.target_word, "");
+        assert!(game.hides_word());
+
+        // letter states are worked out by the server, and the guesser keeps guessing
+        let miss = update(&game, "crane", 0, Player::P1);
+        game.play_turn(miss).unwrap();
+        assert_eq!(game.current_round.turns[0].letter_state, 0b01_01_01_10_01);
+        assert_eq!(game.current_round.current_player, Player::P2);
+
+        let solve = update(&game, "words", 0, Player::P1);
+        game.play_turn(solve).unwrap();
+        assert_eq!(game.current_round.target_word, "words");
+        assert_eq!((game.p1_points, game.p2_points), (0, 0));
+
+        assert!(game.start_round("crane").is_err());
+        game.start_round("").unwrap();
+        assert_eq!(game.current_round.setter, Some(Player::P2));
+        game.set_secret_word(Player::P2, "crane").unwrap();
+        let solve = update(&game, "crane", 0, Player::P2);
+        game.play_turn(solve).unwrap();
+
+        // player 1 guessed their word in fewer guesses
+        assert_eq!((game.p1_points, game.p2_points), (1, 0));
+        assert!(Game::from_export(game.to_export()).is_ok());
+    }
+
+    #[test]
+    fn hints_are_recorded_and_cost_points() {
+        let mut game = Game::new_game("words");
+        game.play_turn(update(&game, "boats", 0b10_01_01_11_01, Player::P2))
+            .unwrap();
+
+        assert!(game
+            .request_hint(Player::P1, HintKind::RevealLetter, 0)
+            .is_err());
+        game.request_hint(Player::P2, HintKind::RevealLetter, 0)
+            .unwrap();
+        game.request_hint(Player::P2, HintKind::EliminateLetters, 0)
+            .unwrap();
+
+        // "o" and "s" were guessed in the right spot, and "b", "a" and "t" already guessed
+        let hints = &game.current_round.hints;
+        assert_eq!(
+            (hints[0].position, hints[0].letters.clone()),
+            (Some(0), vec!["w".to_string()])
+        );
+        assert_eq!(hints[1].letters, vec!["c", "e", "f"]);
+        assert_eq!((game.p1_points, game.p2_points), (0, -2));
+        // the eliminated letters are ruled out in the letterpool
+        let eliminated = 1 << (b'c' - b'a') | 1 << (b'e' - b'a') | 1 << (b'f' - b'a');
+        assert_eq!(game.current_round.letterpool_state & eliminated, eliminated);
+
+        game.request_hint(Player::P2, HintKind::RevealLetter, 0)
+            .unwrap();
+        assert!(game
+            .request_hint(Player::P2, HintKind::RevealLetter, 0)
+            .is_err());
+        assert!(Game::from_export(game.to_export()).is_ok());
+    }
+
+    #[test]
+    fn eliminated_letters_are_ruled_out_on_own_race_board() {
+        let mut game = Game::new_game("words");
+        game.config.mode = GameMode::Race;
+
+        game.request_hint(Player::P1, HintKind::EliminateLetters, 0)
+            .unwrap();
+
+        // "a", "b" and "c" are the first letters not in "words"
+        let eliminated = 0b111;
+        let board = game.current_round.board(Player::P1).unwrap();
+        assert_eq!(board.letterpool_state, eliminated);
+        assert!(game.current_round.board(Player::P2).is_none());
+        assert_eq!(game.current_round.letterpool_state, 0);
+    }
+
+    #[test]
+    fn best_of_ends_once_clinched_or_after_tiebreak() {
+        let solve = 0b11_11_11_11_11;
+        let miss = 0b01_01_01_01_01;
+
+        let mut game = Game::new_game("words");
+        game.config.best_of = Some(3);
+        game.play_turn(update(&game, "words", solve, Player::P2))
+            .unwrap();
+        game.start_round("crane").unwrap();
+        game.play_turn(update(&game, "crane", solve, Player::P1))
+            .unwrap();
+        game.start_round("words").unwrap();
+        assert!(!game.current_round.tiebreak);
+        game.play_turn(update(&game, "words", solve, Player::P1))
+            .unwrap();
+
+        // player 1 has 2 of 3 rounds
+        assert_eq!(game.status, GameStatus::Finished);
+        assert!(Game::from_export(game.to_export()).is_ok());
+
+        let mut game = Game::new_game("words");
+        game.config.best_of = Some(1);
+        for _ in 0..MAX_GUESSES {
+            let next_player = game.current_round.current_player.other();
+            game.play_turn(update(&game, "crane", miss, next_player))
+                .unwrap();
+        }
+        assert_eq!(game.status, GameStatus::Active);
+
+        game.start_round("crane").unwrap();
+        assert!(game.current_round.tiebreak);
+        game.play_turn(update(&game, "crane", solve, Player::P1))
+            .unwrap();
+        assert_eq!(game.status, GameStatus::Finished);
+    }
+}
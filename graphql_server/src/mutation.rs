@@ -1,66 +1,245 @@
 use super::{
-    errors::{GraphqlServerError, CODE500},
+    chat::{send_message, ChatMessage, Reaction},
+    context::Context,
+    daily::{check_not_played, daily_word},
+    export::import_json,
+    errors::{GraphqlServerError, GraphqlServerResult, CODE409, CODE500},
     game_collection,
-    models::Game,
+    hints::HintKind,
+    models::{
+        Game, GameMode, GameSettings, NewGame, NewPlayer, Player, PlayerProfile,
+        ProfileCredentials, UpdateRound,
+    },
+    packs::{check_guess, check_target, create_pack, find_pack, remove_pack, WordPack},
+    player_collection,
+    rating::rate_game,
+    selection::{select_round_word, select_word},
+    spectator::{spectate_game, Spectator},
+    idempotency::{apply_once, IdempotencyKey},
+    store::{find_game, find_player, save_game},
+    words::{check_dictionary, check_word},
     MongoClient,
 };
+use chrono::Utc;
 use juniper::{graphql_object, FieldError, FieldResult, IntoFieldError};
-use rocket_db_pools::mongodb::{bson::doc, Collection};
+use rocket_db_pools::mongodb::{
+    bson::doc,
+    error::{Error, ErrorKind, WriteFailure},
+    Collection,
+};
+use std::future::Future;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// The error code mongodb uses when inserting a duplicate key.
+const DUPLICATE_KEY_CODE: i32 = 11000;
 
 /// Root Mutation node
 pub struct Mutation;
 
 #[graphql_object]
-#[graphql(context = MongoClient)]
+#[graphql(context = Context)]
 impl Mutation {
     fn api_version() -> &'static str {
         "1.0"
     }
 
-    /// Creates a new game. Returns true if successful.
+    /// Starts spectating a game. Returns the spectator, whose token gives read-only access.
+    /// Spectators can query games, but every other mutation rejects them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// or failed to save the spectator.
+    async fn spectate_game(context: &Context, id: String) -> FieldResult<Spectator> {
+        // the only mutation spectators can make, as it only gives read-only access,
+        // so a spectator of one game can also spectate other games
+        let game_id = Game::parse_id(&id)?;
+        find_game(context, &game_id).await?;
+
+        Ok(spectate_game(context, &game_id).await?)
+    }
+
+    /// Creates a profile for a new player. Returns the profile created, and the secret of the player.
+    /// The secret is only given out once, and is needed to add the profile to a game.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the display name is not valid,
+    /// or failed to save the profile.
+    async fn create_player(context: &Context, display_name: String) -> FieldResult<NewPlayer> {
+        context.check_player()?;
+
+        let players: Collection<PlayerProfile> = player_collection(context);
+
+        let player = PlayerProfile::new_player(&display_name)?;
+
+        match players.insert_one(&player, None).await {
+            Err(_) => Err(GraphqlServerError::new(
+                "Failed to execute insert".to_string(),
+                &CODE500,
+            )
+            .into_field_error()),
+            Ok(_) => Ok(player.to_new_player()),
+        }
+    }
+
+    /// Gives the player with the id a new secret, e.g. for a profile created before profiles had secrets,
+    /// or whose secret was lost. The old secret can no longer add the profile to games.
+    /// Returns the profile, and its new secret. Only admins can reset secrets.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request wasn't made by an admin,
+    /// there is no player with the id, or failed to save the secret.
+    async fn reset_player_secret(context: &Context, id: String) -> FieldResult<NewPlayer> {
+        context.check_admin()?;
+
+        let player_id = PlayerProfile::parse_id(&id)?;
+        let mut player = find_player(context, &player_id).await?;
+        player.new_secret();
+        let new_player = player.to_new_player();
+
+        let players: Collection<PlayerProfile> = player_collection(context);
+        let update = doc! {"$set": {"secret": new_player.secret()}};
+        match players
+            .update_one(doc! {"_id": &player_id}, update, None)
+            .await
+        {
+            Err(_) => Err(GraphqlServerError::new(
+                "Failed to execute update".to_string(),
+                &CODE500,
+            )
+            .into_field_error()),
+            Ok(_) => Ok(new_player),
+        }
+    }
+
+    /// Creates a new game. Returns the id of the game created, and the tokens of its players.
+    /// Each player sends their token in the ```Player-Token``` header to make changes to the game.
+    ///
+    /// ```p1``` and ```p2``` are the profiles of the players, if they have one,
+    /// each given with the secret of the player so no one else can add them to games.
+    /// If no ```word``` is given, one is picked that neither player has recently played.
+    /// ```settings``` are how the game is played, see ```GameSettings```.
+    /// If a word pack is given, every word of the game is picked from the pack, and guesses have to be in the pack.
+    /// Otherwise words are picked from the dictionary of the language, only from words with the difficulty if given.
+    ///
+    /// If ```clientMutationId``` is given, retrying with the same id returns the id of the same game
+    /// instead of creating another one, but not the tokens of its players, which are only given out once.
     ///
     /// # Errors
     ///
     /// This function will return an error if failed to create a new game.
     /// Most likely cause is being unable to connect to the database.
-    async fn new_game(context: &MongoClient,
-                      
-                      #[graphql(default = "words")] 
-                      word: String) -> FieldResult<String> {
-        let games: Collection<Game> = game_collection(context);
+    /// Will also return an error if a player id given has no profile, there is no word pack with the name,
+    /// the word isn't in the word pack, or the settings can't be used together (see ```GameSettings::check```).
+    /// A 422 error is also returned if the dictionary of the language has no words yet.
+    /// A 403 error means the secret of a player given wasn't theirs.
+    async fn new_game(context: &Context,
+                      word: Option<String>,
+                      settings: Option<GameSettings>,
+                      p1: Option<ProfileCredentials>,
+                      p2: Option<ProfileCredentials>,
+                      client_mutation_id: Option<String>) -> FieldResult<NewGame> {
+        context.check_player()?;
 
-        let mut new_game = Game::new_game(word.as_str());
+        let settings = settings.unwrap_or_default();
+        settings.check(word.as_deref())?;
+
+        let config = settings.into_config();
+
+        // make sure the word pack, or the dictionary of the language, exists before creating a game with it
+        match config.word_pack() {
+            Some(word_pack) => {
+                find_pack(context, &WordPack::parse_name(word_pack)?).await?;
+            }
+            None => check_dictionary(context, config.language()).await?,
+        }
 
-        let mut attempt: u8 = 0;
-        let max_retry: u8 = 2;
-        loop {
-            let insert_one_result = games.insert_one(&new_game, None).await;
-            return match insert_one_result {
-                Err(_) => {
-                    // let it retry with new id, as it is possible if try to insert duplicate id
-                    // duplicate id can occur, though rare
-                    if attempt < max_retry {
-                        attempt += 1;
-                        new_game.new_id();
-                        continue;
-                    }
-
-                    Err(
-                        GraphqlServerError::new("Failed to execute insert".to_string(), &CODE500)
-                            .into_field_error(),
-                    )
+        // make sure the players exist, and agreed to play, before creating a game for them
+        let p1_id = existing_player_id(context, p1).await?;
+        let p2_id = existing_player_id(context, p2).await?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &(&word, &config, &p1_id, &p2_id))?;
+
+        let create = || async {
+            let word = match word {
+                Some(word) => {
+                    check_target(context, config.word_pack(), &word).await?;
+                    word
+                }
+                // the players set the words of secret word games themselves
+                None if config.mode() == GameMode::SecretWord => String::new(),
+                None => {
+                    let player_ids = [p1_id.as_deref(), p2_id.as_deref()];
+                    select_word(context, None, player_ids, &config).await?
                 }
-                // simply return the id of the game created
-                Ok(_) => Ok(new_game.id()),
             };
-        }
+
+            let mut new_game = Game::new_game(word.as_str());
+            new_game.set_config(config);
+            new_game.set_players(p1_id, p2_id)?;
+
+            insert_new_game(context, &mut new_game).await?;
+            Ok(new_game.to_new_game())
+        };
+
+        Ok(create_once(context, "newGame", key, create).await?)
+    }
+
+    /// Creates a game playing today's daily challenge (UTC), which every pair of players plays with the same word.
+    /// The game ends with its only round, after which it is ranked on the daily board.
+    /// Both players need a profile, as each player can only play the daily challenge once,
+    /// and give their secret like in ```newGame```.
+    /// The word is hidden until the round ends, so the letter states of guesses are worked out by the server.
+    /// Returns the id of the game, and the tokens of its players.
+    /// Like ```newGame```, a retry with the same ```clientMutationId``` only returns the id of the game.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a player doesn't exist,
+    /// the word of the day can't be found, or failed to insert the game.
+    /// A 403 error means the secret of a player wasn't theirs.
+    /// A 409 error means a player already has a game of today's daily challenge.
+    async fn new_daily_challenge(
+        context: &Context,
+        p1: ProfileCredentials,
+        p2: ProfileCredentials,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<NewGame> {
+        context.check_player()?;
+
+        let p1_id = existing_player_id(context, Some(p1)).await?;
+        let p2_id = existing_player_id(context, Some(p2)).await?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &(&p1_id, &p2_id))?;
+
+        let create = || async {
+            let today = Utc::now().date_naive();
+
+            // profiles are required, so both players have an id
+            let player_ids: Vec<&str> = p1_id.iter().chain(&p2_id).map(String::as_str).collect();
+            check_not_played(context, today, &player_ids).await?;
+
+            let word = daily_word(context, today).await?;
+            let mut new_game = Game::new_daily_challenge(&word, today);
+            new_game.set_players(p1_id.clone(), p2_id.clone())?;
+
+            insert_new_game(context, &mut new_game).await?;
+            Ok(new_game.to_new_game())
+        };
+
+        Ok(create_once(context, "newDailyChallenge", key, create).await?)
     }
 
     /// Testing creation of new game by providing a id instead of letting program generate one.
     /// Also testing default arguments.
-    async fn test_new_game(context: &MongoClient, id: String,
+    async fn test_new_game(context: &Context, id: String,
                            #[graphql(default = "words")]
                            word: String) -> FieldResult<String> {
+        context.check_player()?;
+
         let games: Collection<Game> = game_collection(context);
         let mut new_game = Game::new_game(word.as_str());
         new_game.set_id(&id);
@@ -73,16 +252,583 @@ impl Mutation {
         }
     }
 
-    /// Removes a game from the database.
+    /// Plays a turn in a game. Returns the game after the turn is played.
+    /// If the turn ends the game, the ratings of the players are updated.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the guess isn't in the game's word pack, the turn can't be played (see ```Game::play_turn```),
+    /// or failed to save the game or ratings.
+    /// A 409 error means another change to the game was saved first, and the turn can be retried.
+    /// Give a ```clientMutationId``` so that a retry of a turn that was already played isn't played again.
+    /// A 403 error means the request wasn't made by the player whose turn it is,
+    /// who sends their token in the ```Player-Token``` header.
+    async fn play_turn(
+        context: &Context,
+        update: UpdateRound,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(update.game_id())?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &update)?;
+
+        let play = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, game.current_round().current_player())?;
+
+            check_guess(context, game.config().word_pack(), update.guess()).await?;
+            game.play_turn(update)?;
+
+            save_game(context, &mut game).await?;
+            // a turn that ends the game, e.g. by deciding a best-of match, ends it like ```endGame```
+            rate_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "playTurn", key, play).await?)
+    }
+
+    /// Sets the word of the current round of a secret word game on behalf of ```player```, who sets it.
+    /// The word has to be in the game's word pack, or the dictionary of the game's language.
+    /// Returns the game, where the word is hidden until the round ends.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id, the word isn't in the word pack
+    /// or dictionary, the word can't be set (see ```Game::set_secret_word```), or failed to save the game.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn set_secret_word(
+        context: &Context,
+        game_id: String,
+        player: Player,
+        word: String,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&game_id)?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &(player, &word))?;
+
+        let set = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, player)?;
+
+            match game.config().word_pack() {
+                Some(word_pack) => check_target(context, Some(word_pack), &word).await?,
+                None => check_word(context, game.config().language(), &word).await?,
+            }
+            game.set_secret_word(player, &word)?;
+
+            save_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "setSecretWord", key, set).await?)
+    }
+
+    /// Plays a guess of ```player``` on their own board in a race game.
+    /// Returns the game after the guess is played.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the guess isn't in the game's word pack, the guess can't be played (see ```Game::play_race_turn```),
+    /// or failed to save the game or ratings.
+    /// A 409 error means another change to the game was saved first, and the guess can be retried.
+    /// Give a ```clientMutationId``` so that a retry of a guess that was already played isn't played again.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn play_race_turn(
+        context: &Context,
+        update: UpdateRound,
+        player: Player,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(update.game_id())?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &(&update, player))?;
+
+        let play = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, player)?;
+
+            check_guess(context, game.config().word_pack(), update.guess()).await?;
+            game.play_race_turn(player, update)?;
+
+            save_game(context, &mut game).await?;
+            // a turn that ends the game, e.g. by deciding a best-of match, ends it like ```endGame```
+            rate_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "playRaceTurn", key, play).await?)
+    }
+
+    /// Proposes a guess in a co-op game on behalf of ```player```, see ```Game::propose_guess```.
+    /// Returns the game with the proposed guess, which is played once the partner confirms it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the guess isn't in the game's word pack, the guess can't be proposed, or failed to save the game.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn propose_guess(
+        context: &Context,
+        update: UpdateRound,
+        player: Player,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(update.game_id())?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &(&update, player))?;
+
+        let propose = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, player)?;
+
+            check_guess(context, game.config().word_pack(), update.guess()).await?;
+            game.propose_guess(player, update)?;
+
+            save_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "proposeGuess", key, propose).await?)
+    }
+
+    /// Confirms the guess proposed by the partner of ```player``` in a co-op game, playing it.
+    /// Returns the game after the guess is played.
+    /// The request needs the token of ```player```, so a player can't confirm their own guess as their partner.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the partner hasn't proposed a guess (see ```Game::confirm_guess```), or failed to save the game.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// Give a ```clientMutationId``` so that a retry of a guess that was already confirmed isn't played again.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn confirm_guess(
+        context: &Context,
+        game_id: String,
+        player: Player,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&game_id)?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &player)?;
+
+        let confirm = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, player)?;
+
+            game.confirm_guess(player)?;
+
+            save_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "confirmGuess", key, confirm).await?)
+    }
+
+    /// Rejects the guess proposed by the partner of ```player``` in a co-op game.
+    /// Returns the game without the proposed guess.
+    /// The request needs the token of ```player```, like ```confirmGuess```.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the partner hasn't proposed a guess (see ```Game::reject_guess```), or failed to save the game.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn reject_guess(
+        context: &Context,
+        game_id: String,
+        player: Player,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&game_id)?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &player)?;
+
+        let reject = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, player)?;
+
+            game.reject_guess(player)?;
+
+            save_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "rejectGuess", key, reject).await?)
+    }
+
+    /// Requests a hint on behalf of ```player```, who is stuck on the word of the current round.
+    /// The hint reveals a letter of the word, unless ```kind``` is given, and costs points (see ```Game::request_hint```).
+    /// Returns the game with the hint recorded in the current round.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// no hint can be given (see ```Game::request_hint```), or failed to save the game.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn request_hint(
+        context: &Context,
+        game_id: String,
+        player: Player,
+        kind: Option<HintKind>,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&game_id)?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &(player, kind))?;
+
+        let request = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, player)?;
+
+            // eliminated letters are picked at random
+            let seed = Uuid::new_v4().as_u128() as u64;
+            game.request_hint(player, kind.unwrap_or_default(), seed)?;
+
+            save_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "requestHint", key, request).await?)
+    }
+
+    /// Starts the next round of a game, once the current round has ended.
+    /// Returns the game with the new round.
+    /// If no ```word``` is given, one is picked that hasn't been played in the game or recently by either player.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the word isn't in the game's word pack, the current round is still being played, or failed to save the game.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// A 403 error means the request wasn't made by a player of the game,
+    /// who sends their token in the ```Player-Token``` header.
+    async fn new_round(
+        context: &Context,
+        id: String,
+        word: Option<String>,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&id)?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &word)?;
+
+        let start = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_either_player_of(&game)?;
+
+            let word = match word {
+                Some(word) => {
+                    check_target(context, game.config().word_pack(), &word).await?;
+                    word
+                }
+                None if game.config().mode() == GameMode::SecretWord => String::new(),
+                None => select_round_word(context, &game).await?,
+            };
+
+            game.start_round(word.as_str())?;
+
+            save_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "newRound", key, start).await?)
+    }
+
+    /// Ends a game, so it is no longer being played.
+    /// Returns true if the game was ended, false if it had already ended.
+    /// The ratings of the players are updated when the game ends,
+    /// or when ending it again if updating them failed.
+    ///
+    /// If ```clientMutationId``` is given, retrying with the same id returns the same result.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// or failed to save the game or ratings.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// A 403 error means the request wasn't made by a player of the game,
+    /// who sends their token in the ```Player-Token``` header.
+    async fn end_game(
+        context: &Context,
+        id: String,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<bool> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&id)?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &())?;
+
+        let end = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_either_player_of(&game)?;
+
+            let ended = game.finish();
+            if ended {
+                save_game(context, &mut game).await?;
+            }
+
+            // a game whose ratings failed to update is rated when ending it is retried
+            rate_game(context, &mut game).await?;
+            Ok(ended)
+        };
+
+        Ok(apply_once(context, &game_id, "endGame", key, end).await?)
+    }
+
+    /// Asks for a rematch of a game that has ended, on behalf of ```player```.
+    /// Returns the game with the rematch asked for.
+    /// Once the other player accepts with ```acceptRematch```, the rematch is created.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the rematch can't be asked for (see ```Game::request_rematch```), or failed to save the game.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn request_rematch(
+        context: &Context,
+        game_id: String,
+        player: Player,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&game_id)?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &player)?;
+
+        let request = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, player)?;
+
+            game.request_rematch(player)?;
+
+            save_game(context, &mut game).await?;
+            Ok(game)
+        };
+
+        Ok(apply_once(context, &game_id, "requestRematch", key, request).await?)
+    }
+
+    /// Accepts the rematch of a game asked for by the other player of ```player```.
+    /// Returns the rematch, a new game between the same players linked to the game by ```previousGameId```.
+    /// The player who didn't start the first round of the game starts the rematch.
+    /// If no ```word``` is given, one is picked that hasn't been played in the game or recently by either player.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the rematch can't be accepted (see ```Game::accept_rematch```), or failed to save either game.
+    /// A 409 error means another change to the game was saved first, and this can be retried.
+    /// The rematch is only kept if the game is saved with the link to it.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn accept_rematch(
+        context: &Context,
+        game_id: String,
+        player: Player,
+        word: Option<String>,
+        client_mutation_id: Option<String>,
+    ) -> FieldResult<Game> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&game_id)?;
+
+        let key = IdempotencyKey::new(client_mutation_id, &(player, &word))?;
+
+        let accept = || async {
+            let mut game = find_game(context, &game_id).await?;
+            context.check_player_of(&game, player)?;
+
+            // the rematch avoids the words of this game, as well as the players' recent games
+            let word = match word {
+                Some(word) => {
+                    check_target(context, game.config().word_pack(), &word).await?;
+                    word
+                }
+                None if game.config().mode() == GameMode::SecretWord => String::new(),
+                None => select_round_word(context, &game).await?,
+            };
+
+            let rematch = game.accept_rematch(player, word.as_str())?;
+
+            // the rematch is inserted first, so the game never links to a rematch that doesn't exist
+            let games: Collection<Game> = game_collection(context);
+            if games.insert_one(&rematch, None).await.is_err() {
+                return Err(GraphqlServerError::new(
+                    "Failed to execute insert".to_string(),
+                    &CODE500,
+                ));
+            }
+
+            // only one request can save the link, so any other rematch inserted meanwhile is removed
+            if let Err(e) = save_game(context, &mut game).await {
+                if games.delete_one(doc! {"_id": rematch.id()}, None).await.is_err() {
+                    warn!("Failed to remove rematch {} of game {}", rematch.id(), game.id());
+                }
+                return Err(e);
+            }
+
+            Ok(rematch)
+        };
+
+        Ok(apply_once(context, &game_id, "acceptRematch", key, accept).await?)
+    }
+
+    /// Sends a message in the chat of a game on behalf of ```player```. Returns the message sent.
+    /// Profanity in the message is hidden.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id,
+    /// the message is empty or too long, or failed to save the message.
+    /// A 429 error means the player has sent too many messages recently, and this can be retried later.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn send_message(
+        context: &Context,
+        game_id: String,
+        player: Player,
+        text: String,
+    ) -> FieldResult<ChatMessage> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&game_id)?;
+        let game = find_game(context, &game_id).await?;
+        context.check_player_of(&game, player)?;
+
+        let message = ChatMessage::new_text(&game_id, player, &text)?;
+        send_message(context, &message).await?;
+
+        Ok(message)
+    }
+
+    /// Sends a quick reaction in the chat of a game on behalf of ```player```. Returns the message sent.
+    /// Reactions count towards the same limit as messages.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no game with the id, or failed to save the message.
+    /// A 429 error means the player has sent too many messages recently, and this can be retried later.
+    /// A 403 error means the request wasn't made by the player, who sends their token in the ```Player-Token``` header.
+    async fn send_reaction(
+        context: &Context,
+        game_id: String,
+        player: Player,
+        reaction: Reaction,
+    ) -> FieldResult<ChatMessage> {
+        context.check_player()?;
+
+        let game_id = Game::parse_id(&game_id)?;
+        let game = find_game(context, &game_id).await?;
+        context.check_player_of(&game, player)?;
+
+        let message = ChatMessage::new_reaction(&game_id, player, reaction);
+        send_message(context, &message).await?;
+
+        Ok(message)
+    }
+
+    /// Imports a game exported with ```exportGame```. Returns the game imported, which has a new id.
+    /// Only admins can import games.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request wasn't made by an admin,
+    /// the document isn't a valid export (see ```export::import_json```), or failed to save the game.
+    async fn import_game(context: &Context, json: String) -> FieldResult<Game> {
+        context.check_admin()?;
+
+        let game = import_json(&json)?;
+
+        let games: Collection<Game> = game_collection(context);
+        match games.insert_one(&game, None).await {
+            Ok(_) => Ok(game),
+            Err(_) => Err(GraphqlServerError::new(
+                "Failed to execute insert".to_string(),
+                &CODE500,
+            )
+            .into_field_error()),
+        }
+    }
+
+    /// Creates a word pack named ```name```, whose ```targets``` can be picked as target words,
+    /// and whose ```guesses``` are only allowed as guesses. Returns the pack created.
+    /// Only admins can create word packs.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request wasn't made by an admin,
+    /// the pack isn't valid (see ```WordPack::new_pack```), there is already a pack with the name,
+    /// or failed to save the pack.
+    async fn create_word_pack(
+        context: &Context,
+        name: String,
+        targets: Vec<String>,
+        #[graphql(default = Vec::new())] guesses: Vec<String>,
+    ) -> FieldResult<WordPack> {
+        context.check_admin()?;
+
+        let pack = WordPack::new_pack(&name, targets, guesses)?;
+        create_pack(context, &pack).await?;
+
+        Ok(pack)
+    }
+
+    /// Removes the word pack named ```name```. Returns true if there was a pack to remove.
+    /// Only admins can remove word packs.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request wasn't made by an admin,
+    /// or failed to remove the pack.
+    /// A 409 error means games still being played use the pack, and it can be removed once they finish.
+    async fn remove_word_pack(context: &Context, name: String) -> FieldResult<bool> {
+        context.check_admin()?;
+
+        Ok(remove_pack(context, &name).await?)
+    }
+
+    /// Removes a game from the database. Only the players of the game, or an admin, can remove it.
     ///
     /// # Errors
     ///
     /// This function will return an error if failed to delete the query.
     /// Most likely cause is id given not existing.
-    async fn remove_game(context: &MongoClient, id: String) -> FieldResult<bool> {
+    /// A 403 error means the request wasn't made by a player of the game.
+    async fn remove_game(context: &Context, id: String) -> FieldResult<bool> {
+        context.check_player()?;
+
         let games: Collection<Game> = game_collection(context);
 
         let game_id = Game::parse_id(&id)?;
+        let game = find_game(context, &game_id).await?;
+        context.check_either_player_of(&game)?;
+
         let delete_query = doc! {"_id": &game_id};
         let delete_one_result = games.delete_one(delete_query, None).await;
 
@@ -99,13 +845,16 @@ impl Mutation {
         }
     }
 
-    /// Removes all games from the database.
+    /// Removes all games from the database. Only admins can remove every game.
     ///
     /// # Errors
     ///
-    /// This function will return an error if failed to delete the query.
+    /// This function will return an error if the request wasn't made by an admin,
+    /// or failed to delete the query.
     /// Most likely cause is a connection error to database.
-    async fn remove_games(context: &MongoClient) -> FieldResult<bool> {
+    async fn remove_games(context: &Context) -> FieldResult<bool> {
+        context.check_admin()?;
+
         let games: Collection<Game> = game_collection(context);
         let delete_many_result = games.delete_many(doc! {}, None).await;
 
@@ -121,3 +870,111 @@ impl Mutation {
         }
     }
 }
+
+/// Parses the id of ```profile``` and checks that a player with the id exists, and that the secret is theirs.
+/// Returns the id of the player.
+///
+/// # Errors
+///
+/// Will throw a 422 error if the id is not valid, a 404 error if there is no player with the id,
+/// or a 403 error if the secret isn't the player's.
+async fn existing_player_id(
+    context: &MongoClient,
+    profile: Option<ProfileCredentials>,
+) -> GraphqlServerResult<Option<String>> {
+    match profile {
+        None => Ok(None),
+        Some(profile) => {
+            let player_id = PlayerProfile::parse_id(&profile.id)?;
+            let player = find_player(context, &player_id).await?;
+            player.check_secret(Some(&profile.secret))?;
+            Ok(Some(player.id()))
+        }
+    }
+}
+
+/// Creates a game with ```create``` at most once for ```key```, see ```apply_once```.
+///
+/// Only the id of the game is stored with the key, as anyone retrying with the key would otherwise get the tokens of its players.
+/// So a retry of a game that was already created returns its id without tokens.
+///
+/// # Errors
+///
+/// Will throw any error thrown by ```apply_once``` or ```create```.
+async fn create_once<F, Fut>(
+    context: &MongoClient,
+    mutation_name: &str,
+    key: Option<IdempotencyKey>,
+    create: F,
+) -> GraphqlServerResult<NewGame>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = GraphqlServerResult<NewGame>>,
+{
+    let created = Mutex::new(None);
+
+    let create_id = || async {
+        let new_game = create().await?;
+        let game_id = new_game.game_id().to_string();
+        if let Ok(mut created) = created.lock() {
+            *created = Some(new_game);
+        }
+        Ok(game_id)
+    };
+    let game_id: String = apply_once(context, "newGame", mutation_name, key, create_id).await?;
+
+    match created.into_inner() {
+        Ok(Some(new_game)) => Ok(new_game),
+        _ => Ok(NewGame::without_tokens(game_id)),
+    }
+}
+
+/// Inserts ```new_game```, giving it a new id if its id is already taken. Returns the id of the game.
+///
+/// # Errors
+///
+/// Will throw a 409 error if the game is a daily challenge a player already has a game of,
+/// which the index on the players of daily challenges catches when both games are created at once,
+/// or a 500 error if failed to execute the insert.
+async fn insert_new_game(context: &MongoClient, new_game: &mut Game) -> GraphqlServerResult<String> {
+    let games: Collection<Game> = game_collection(context);
+
+    let mut attempt: u8 = 0;
+    let max_retry: u8 = 2;
+    loop {
+        let insert_one_result = games.insert_one(&*new_game, None).await;
+        return match insert_one_result {
+            Err(e) if new_game.daily_challenge().is_some() && is_duplicate_key(&e) => {
+                Err(GraphqlServerError::new(
+                    "a player already has a game of today's daily challenge".to_string(),
+                    &CODE409,
+                ))
+            }
+            Err(_) => {
+                // let it retry with new id, as it is possible if try to insert duplicate id
+                // duplicate id can occur, though rare
+                if attempt < max_retry {
+                    attempt += 1;
+                    new_game.new_id();
+                    continue;
+                }
+
+                Err(GraphqlServerError::new(
+                    "Failed to execute insert".to_string(),
+                    &CODE500,
+                ))
+            }
+            // simply return the id of the game created
+            Ok(_) => Ok(new_game.id()),
+        };
+    }
+}
+
+/// Checks if ```error``` is from inserting a document with a key that is already taken.
+fn is_duplicate_key(error: &Error) -> bool {
+    matches!(
+        *error.kind,
+        ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+            if write_error.code == DUPLICATE_KEY_CODE
+    )
+}
@@ -0,0 +1,514 @@
+use bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use chrono::{DateTime, Utc};
+use juniper::GraphQLObject;
+use rocket_db_pools::mongodb::{
+    bson::{doc, from_document, to_bson, Document},
+    error::{ErrorKind, WriteFailure},
+    options::{FindOptions, ReplaceOptions},
+    Collection,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use super::{
+    errors::{
+        GraphqlServerError, GraphqlServerResult, CODE400, CODE404, CODE409, CODE422, CODE500,
+    },
+    game_collection,
+    letters::{is_word, letter_count, normalize},
+    models::{GameStatus, MAX_WORD_LENGTH},
+    MongoClient,
+};
+
+// This file contains word packs, which are themed lists of words games can be played with instead of the dictionary
+// A pack has the words that can be picked as target words, and extra words that are only allowed as guesses
+// Packs are created by admins, or loaded from the JSON files in WORD_PACK_DIR when the server starts:
+// <WORD_PACK_DIR>/<pack name>.json = { "targets": ["rusty", ...], "guesses": ["cargo", ...] }
+
+/// The max length of the name of a pack.
+pub const MAX_PACK_NAME_LENGTH: usize = 32;
+
+/// The error code mongodb uses when inserting a duplicate key.
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+/// A named list of words games can be played with.
+#[derive(Debug, Clone, PartialEq, GraphQLObject, Serialize, Deserialize)]
+pub struct WordPack {
+    /// The name of the pack, made of lowercase letters, digits and dashes.
+    #[serde(rename = "_id")]
+    name: String,
+
+    /// The words that can be picked as target words, in alphabetical order.
+    targets: Vec<String>,
+
+    /// The words, besides the targets, that can be guessed, in alphabetical order.
+    guesses: Vec<String>,
+
+    /// When the pack was created.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    created_at: DateTime<Utc>,
+}
+
+/// The words of a pack, as they are written in a pack file.
+#[derive(Debug, Deserialize)]
+struct PackFile {
+    targets: Vec<String>,
+    #[serde(default)]
+    guesses: Vec<String>,
+}
+
+/// Checks that every word of ```words``` can be played, and returns them normalized without duplicates in alphabetical order.
+///
+/// # Errors
+///
+/// Will throw a 422 error if a word has anything but letters,
+/// or is longer than ```MAX_WORD_LENGTH```.
+fn check_words(words: Vec<String>) -> GraphqlServerResult<Vec<String>> {
+    let mut checked = BTreeSet::new();
+
+    for word in words {
+        let word = normalize(&word);
+        let length = letter_count(&word);
+
+        if length == 0 || length > MAX_WORD_LENGTH || !is_word(&word) {
+            return Err(GraphqlServerError::new(
+                format!(
+                    "word {:?} should be between 1 and {} letters",
+                    word, MAX_WORD_LENGTH
+                ),
+                &CODE422,
+            ));
+        }
+        checked.insert(word);
+    }
+
+    Ok(checked.into_iter().collect())
+}
+
+impl WordPack {
+    /// Creates a pack named ```name```, with ```targets``` that can be picked as target words,
+    /// and ```guesses``` that are only allowed as guesses.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the name isn't valid, there are no targets, or a word isn't valid.
+    pub fn new_pack(
+        name: &str,
+        targets: Vec<String>,
+        guesses: Vec<String>,
+    ) -> GraphqlServerResult<Self> {
+        let name = WordPack::parse_name(name)?;
+
+        let targets = check_words(targets)?;
+        if targets.is_empty() {
+            return Err(GraphqlServerError::new(
+                "word pack should have at least 1 target word".to_string(),
+                &CODE422,
+            ));
+        }
+
+        // targets can already be guessed, so they aren't kept twice
+        let guesses = check_words(guesses)?
+            .into_iter()
+            .filter(|guess| targets.binary_search(guess).is_err())
+            .collect();
+
+        Ok(WordPack {
+            name,
+            targets,
+            guesses,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Checks if ```name``` can be the name of a pack.
+    /// Returns the name if it is valid.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 422 error if the name is empty, longer than ```MAX_PACK_NAME_LENGTH```,
+    /// or has characters other than lowercase letters, digits and dashes.
+    pub fn parse_name(name: &str) -> GraphqlServerResult<String> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_PACK_NAME_LENGTH
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+        if !valid {
+            return Err(GraphqlServerError::new(
+                format!(
+                    "word pack name should be up to {} lowercase letters, digits and dashes",
+                    MAX_PACK_NAME_LENGTH
+                ),
+                &CODE422,
+            ));
+        }
+
+        Ok(name.to_string())
+    }
+
+    /// Gets the name of the pack.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the words of the pack that can be picked as target words, in alphabetical order.
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Checks if ```word``` is one of the target words of the pack.
+    pub fn has_target(&self, word: &str) -> bool {
+        self.targets
+            .binary_search_by(|target| target.as_str().cmp(word))
+            .is_ok()
+    }
+
+    /// Checks if ```guess``` can be guessed in a game played with the pack.
+    pub fn allows_guess(&self, guess: &str) -> bool {
+        self.has_target(guess)
+            || self
+                .guesses
+                .binary_search_by(|word| word.as_str().cmp(guess))
+                .is_ok()
+    }
+}
+
+/// Gets the collection of word packs.
+fn pack_collection<T: Send + Sync>(client: &MongoClient) -> Collection<T> {
+    client.database("Games").collection("WordPacks")
+}
+
+/// Finds the pack named ```name```.
+///
+/// # Errors
+///
+/// Will throw a 404 error if there is no pack with the name,
+/// or a 500 error if failed to execute the query.
+pub async fn find_pack(client: &MongoClient, name: &str) -> GraphqlServerResult<WordPack> {
+    let packs: Collection<WordPack> = pack_collection(client);
+
+    match packs.find_one(doc! {"_id": name}, None).await {
+        Ok(Some(pack)) => Ok(pack),
+        Ok(None) => Err(GraphqlServerError::new(
+            format!("no word pack named {}", name),
+            &CODE404,
+        )),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute find word pack query".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Checks that ```word``` can be a target word of a game played with the pack named ```word_pack```.
+/// Any word can be a target word of a game played with the dictionary.
+///
+/// # Errors
+///
+/// Will throw a 422 error if the word isn't a target word of the pack,
+/// a 404 error if there is no pack with the name, or a 500 error if failed to query the pack.
+pub async fn check_target(
+    client: &MongoClient,
+    word_pack: Option<&str>,
+    word: &str,
+) -> GraphqlServerResult<()> {
+    let Some(word_pack) = word_pack else {
+        return Ok(());
+    };
+
+    if !find_pack(client, word_pack)
+        .await?
+        .has_target(&normalize(word))
+    {
+        return Err(GraphqlServerError::new(
+            format!("{} isn't a target word of word pack {}", word, word_pack),
+            &CODE422,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that ```guess``` can be guessed in a game played with the pack named ```word_pack```.
+/// Guesses of games played with the dictionary are checked by clients.
+///
+/// # Errors
+///
+/// Will throw a 422 error if the pack doesn't allow the guess,
+/// a 404 error if there is no pack with the name, or a 500 error if failed to query the pack.
+pub async fn check_guess(
+    client: &MongoClient,
+    word_pack: Option<&str>,
+    guess: &str,
+) -> GraphqlServerResult<()> {
+    let Some(word_pack) = word_pack else {
+        return Ok(());
+    };
+
+    if !find_pack(client, word_pack)
+        .await?
+        .allows_guess(&normalize(guess))
+    {
+        return Err(GraphqlServerError::new(
+            format!("{} isn't a word in word pack {}", guess, word_pack),
+            &CODE422,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Gets every pack, in order of name.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to execute the query.
+pub async fn word_packs(client: &MongoClient) -> GraphqlServerResult<Vec<WordPack>> {
+    let packs: Collection<WordPack> = pack_collection(client);
+
+    let find_options = FindOptions::builder().sort(doc! {"_id": 1}).build();
+
+    let find_error = || {
+        GraphqlServerError::new(
+            "Failed to execute find word packs query".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = packs
+        .find(doc! {}, find_options)
+        .await
+        .map_err(|_| find_error())?;
+
+    let mut all = Vec::new();
+    while cursor.advance().await.map_err(|_| find_error())? {
+        all.push(cursor.deserialize_current().map_err(|_| find_error())?);
+    }
+
+    Ok(all)
+}
+
+/// Saves a new ```pack```.
+///
+/// # Errors
+///
+/// Will throw a 409 error if there is already a pack with the same name,
+/// or a 500 error if failed to save the pack.
+pub async fn create_pack(client: &MongoClient, pack: &WordPack) -> GraphqlServerResult<()> {
+    let packs: Collection<WordPack> = pack_collection(client);
+
+    match packs.insert_one(pack, None).await {
+        Ok(_) => Ok(()),
+        Err(e) => match *e.kind {
+            ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                if write_error.code == DUPLICATE_KEY_CODE =>
+            {
+                Err(GraphqlServerError::new(
+                    format!("there is already a word pack named {}", pack.name),
+                    &CODE409,
+                ))
+            }
+            _ => Err(GraphqlServerError::new(
+                "Failed to save word pack".to_string(),
+                &CODE500,
+            )),
+        },
+    }
+}
+
+/// Removes the pack named ```name```. Returns true if there was a pack to remove.
+/// Finished games played with the pack keep its name.
+///
+/// # Errors
+///
+/// Will throw a 409 error if a game still being played uses the pack, as it couldn't check guesses or pick words,
+/// or a 500 error if failed to remove the pack.
+pub async fn remove_pack(client: &MongoClient, name: &str) -> GraphqlServerResult<bool> {
+    let games: Collection<Document> = game_collection(client);
+    let filter = doc! {
+        "config.word_pack": name,
+        "status": to_bson(&GameStatus::Active).expect("status should serialize"),
+    };
+    match games.count_documents(filter, None).await {
+        Ok(0) => (),
+        Ok(_) => {
+            return Err(GraphqlServerError::new(
+                format!("word pack {} is used by games still being played", name),
+                &CODE409,
+            ))
+        }
+        Err(_) => {
+            return Err(GraphqlServerError::new(
+                "Failed to execute count games query".to_string(),
+                &CODE500,
+            ))
+        }
+    }
+
+    let packs: Collection<WordPack> = pack_collection(client);
+
+    match packs.delete_one(doc! {"_id": name}, None).await {
+        Ok(result) => Ok(result.deleted_count > 0),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to remove word pack".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Reads the pack in the file at ```path```, named after the file.
+///
+/// # Errors
+///
+/// Will throw a 400 error if the file can't be read or isn't a pack file,
+/// or a 422 error if the pack isn't valid (see ```WordPack::new_pack```).
+fn read_pack_file(path: &Path) -> GraphqlServerResult<WordPack> {
+    let read_error = |reason: String| {
+        GraphqlServerError::new(
+            format!(
+                "word pack file {} can't be read: {}",
+                path.display(),
+                reason
+            ),
+            &CODE400,
+        )
+    };
+
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| read_error("file name isn't valid".to_string()))?;
+    let json = std::fs::read_to_string(path).map_err(|e| read_error(e.to_string()))?;
+    let file: PackFile = serde_json::from_str(&json).map_err(|e| read_error(e.to_string()))?;
+
+    WordPack::new_pack(name, file.targets, file.guesses)
+}
+
+/// Loads the packs in the JSON files of ```dir```, replacing any saved packs with the same names,
+/// so changes to the files are picked up when the server restarts.
+/// Returns the errors of the files that couldn't be loaded, so the other packs are still loaded.
+pub async fn load_pack_files(client: &MongoClient, dir: &Path) -> Vec<GraphqlServerError> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        // no packs to load
+        return Vec::new();
+    };
+
+    let packs: Collection<WordPack> = pack_collection(client);
+    let mut errors = Vec::new();
+
+    for path in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+            continue;
+        }
+
+        let pack = match read_pack_file(&path) {
+            Ok(pack) => pack,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        let options = ReplaceOptions::builder().upsert(true).build();
+        if packs
+            .replace_one(doc! {"_id": pack.name()}, &pack, options)
+            .await
+            .is_err()
+        {
+            errors.push(GraphqlServerError::new(
+                format!("Failed to save word pack {}", pack.name()),
+                &CODE500,
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Gets a random target word of the pack named ```name``` that isn't in ```excluded```.
+/// Returns None if every target word is excluded, or there is no pack with the name.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to execute the aggregation.
+pub async fn random_pack_word(
+    client: &MongoClient,
+    name: &str,
+    excluded: &[String],
+) -> GraphqlServerResult<Option<String>> {
+    let packs: Collection<Document> = pack_collection(client);
+
+    let pipeline = [
+        doc! {"$match": {"_id": name}},
+        doc! {"$project": {"word": {"$setDifference": ["$targets", excluded]}}},
+        doc! {"$unwind": "$word"},
+        doc! {"$sample": {"size": 1}},
+    ];
+
+    let sample_error = || {
+        GraphqlServerError::new(
+            "Failed to execute random pack word aggregation".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = packs
+        .aggregate(pipeline, None)
+        .await
+        .map_err(|_| sample_error())?;
+
+    if !cursor.advance().await.map_err(|_| sample_error())? {
+        return Ok(None);
+    }
+
+    let document = cursor.deserialize_current().map_err(|_| sample_error())?;
+    let word: PackWord = from_document(document).map_err(|_| sample_error())?;
+    Ok(Some(word.word))
+}
+
+/// A word picked from a pack, as returned by the random pack word aggregation.
+#[derive(Debug, Deserialize)]
+struct PackWord {
+    word: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn pack_allows_targets_and_guesses() {
+        let pack = WordPack::new_pack(
+            "programming",
+            words(&["rusty", "cargo", "Rusty"]),
+            words(&["crate", "cargo"]),
+        )
+        .unwrap();
+
+        assert_eq!(pack.targets, words(&["cargo", "rusty"]));
+        assert_eq!(pack.guesses, words(&["crate"]));
+
+        assert!(pack.has_target("rusty"));
+        assert!(!pack.has_target("crate"));
+        assert!(pack.allows_guess("crate"));
+        assert!(!pack.allows_guess("words"));
+    }
+
+    #[test]
+    fn invalid_packs_are_rejected() {
+        assert!(WordPack::new_pack("Programming", words(&["rusty"]), vec![]).is_err());
+        assert!(WordPack::new_pack("programming", vec![], words(&["rusty"])).is_err());
+        assert!(WordPack::new_pack("programming", words(&["c3po"]), vec![]).is_err());
+        assert!(WordPack::new_pack("programming", words(&["rust y"]), vec![]).is_err());
+    }
+}
@@ -0,0 +1,363 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bson::{doc, Bson, Document};
+use chrono::{DateTime, Utc};
+use juniper::{GraphQLEnum, GraphQLInputObject, GraphQLObject};
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE400},
+    models::{Game, GameStatus, Player, PlayerProfile},
+};
+
+// This file contains the types used to paginate, filter and sort queries
+// Pagination follows the relay cursor connections spec
+
+/// The default number of games in a page.
+pub const DEFAULT_PAGE_SIZE: i32 = 20;
+
+/// The max number of games that can be requested in a page.
+pub const MAX_PAGE_SIZE: i32 = 100;
+
+/// Info about a page of results.
+#[derive(Debug, GraphQLObject)]
+pub struct PageInfo {
+    /// Whether there are more results after this page.
+    has_next_page: bool,
+
+    /// Whether there are results before this page.
+    has_previous_page: bool,
+
+    /// The cursor of the first result in this page.
+    start_cursor: Option<String>,
+
+    /// The cursor of the last result in this page.
+    /// Pass this as ```after``` to get the next page.
+    end_cursor: Option<String>,
+}
+
+/// A game in a page, along with the cursor pointing to it.
+#[derive(Debug, GraphQLObject)]
+pub struct GameEdge {
+    /// The cursor of the game.
+    cursor: String,
+
+    /// The game.
+    node: Game,
+}
+
+/// A page of games.
+#[derive(Debug, GraphQLObject)]
+pub struct GameConnection {
+    /// The games in this page.
+    edges: Vec<GameEdge>,
+
+    /// Info about this page.
+    page_info: PageInfo,
+
+    /// The total number of games matching the filter, ignoring pagination.
+    total_count: i32,
+}
+
+impl GameConnection {
+    /// Creates a page from the games found.
+    ///
+    /// ```games``` is expected to have been fetched with a limit of ```first + 1```,
+    /// so that the extra game tells if there is a next page.
+    pub fn new(
+        mut games: Vec<Game>,
+        first: i32,
+        sort: &GameSort,
+        has_previous_page: bool,
+        total_count: i32,
+    ) -> Self {
+        let has_next_page = games.len() > first as usize;
+        games.truncate(first as usize);
+
+        let edges: Vec<GameEdge> = games
+            .into_iter()
+            .map(|game| GameEdge {
+                cursor: Cursor::from_game(&game, sort.field).encode(),
+                node: game,
+            })
+            .collect();
+
+        let page_info = PageInfo {
+            has_next_page,
+            has_previous_page,
+            start_cursor: edges.first().map(|edge| edge.cursor.clone()),
+            end_cursor: edges.last().map(|edge| edge.cursor.clone()),
+        };
+
+        GameConnection {
+            edges,
+            page_info,
+            total_count,
+        }
+    }
+}
+
+/// Filters for which games to get.
+/// Only games matching all filters given are returned.
+#[derive(Debug, Default, GraphQLInputObject)]
+pub struct GameFilter {
+    /// Only get games with this status.
+    status: Option<GameStatus>,
+
+    /// Only get games created at or after this time.
+    created_after: Option<DateTime<Utc>>,
+
+    /// Only get games created before this time.
+    created_before: Option<DateTime<Utc>>,
+
+    /// Only get games where it is this player's turn.
+    current_player: Option<Player>,
+
+    /// Only get games played by the player with this profile id.
+    player_id: Option<String>,
+}
+
+impl GameFilter {
+    /// Converts the filter into a document to query the database with.
+    pub fn to_document(&self) -> Document {
+        let mut filter = Document::new();
+
+        if let Some(status) = self.status {
+            filter.insert(
+                "status",
+                bson::to_bson(&status).expect("status should serialize"),
+            );
+        }
+
+        let mut created_at = Document::new();
+        if let Some(after) = self.created_after {
+            created_at.insert("$gte", bson::DateTime::from_chrono(after));
+        }
+        if let Some(before) = self.created_before {
+            created_at.insert("$lt", bson::DateTime::from_chrono(before));
+        }
+        if !created_at.is_empty() {
+            filter.insert("created_at", created_at);
+        }
+
+        if let Some(player) = &self.current_player {
+            filter.insert(
+                "current_round.current_player",
+                bson::to_bson(player).expect("player should serialize"),
+            );
+        }
+
+        if let Some(player_id) = &self.player_id {
+            // an id that can't be parsed simply matches no games
+            let player_id = PlayerProfile::parse_id(player_id).unwrap_or(player_id.clone());
+            filter.insert(
+                "$or",
+                vec![doc! {"p1_id": &player_id}, doc! {"p2_id": &player_id}],
+            );
+        }
+
+        filter
+    }
+}
+
+/// The field to sort games by.
+#[derive(Debug, Clone, Copy, GraphQLEnum)]
+pub enum GameSortField {
+    /// Sort by when the game was created.
+    CreatedAt,
+    /// Sort by the round number of the game.
+    RoundNum,
+}
+
+impl GameSortField {
+    /// The name of the field in the database.
+    fn key(&self) -> &'static str {
+        match self {
+            GameSortField::CreatedAt => "created_at",
+            GameSortField::RoundNum => "round_num",
+        }
+    }
+
+    /// Converts a value from a cursor back into the type stored in the database.
+    fn to_bson(self, value: i64) -> Bson {
+        match self {
+            GameSortField::CreatedAt => Bson::DateTime(bson::DateTime::from_millis(value)),
+            GameSortField::RoundNum => Bson::Int32(value as i32),
+        }
+    }
+}
+
+/// The direction to sort games in.
+#[derive(Debug, Clone, Copy, GraphQLEnum)]
+pub enum SortDirection {
+    /// Smallest or oldest first.
+    Asc,
+    /// Largest or newest first.
+    Desc,
+}
+
+/// How to sort games.
+#[derive(Debug, GraphQLInputObject)]
+pub struct GameSort {
+    /// The field to sort by.
+    field: GameSortField,
+
+    /// The direction to sort in.
+    direction: SortDirection,
+}
+
+impl Default for GameSort {
+    /// Newest games first.
+    fn default() -> Self {
+        GameSort {
+            field: GameSortField::CreatedAt,
+            direction: SortDirection::Desc,
+        }
+    }
+}
+
+impl GameSort {
+    /// Converts into a document to sort the database query with.
+    /// Ties are broken by id, so that the order is always the same.
+    pub fn to_document(&self) -> Document {
+        let direction = match self.direction {
+            SortDirection::Asc => 1,
+            SortDirection::Desc => -1,
+        };
+
+        doc! {self.field.key(): direction, "_id": direction}
+    }
+
+    /// Creates a filter that only matches games that come after ```cursor``` in this sort order.
+    pub fn after_document(&self, cursor: &Cursor) -> Document {
+        let operator = match self.direction {
+            SortDirection::Asc => "$gt",
+            SortDirection::Desc => "$lt",
+        };
+        let key = self.field.key();
+        let value = self.field.to_bson(cursor.value);
+
+        doc! {
+            "$or": [
+                {key: {operator: value.clone()}},
+                {key: value, "_id": {operator: &cursor.id}},
+            ]
+        }
+    }
+
+    /// Creates a filter that only matches games that come at or before ```cursor``` in this sort order,
+    /// which are the games on the pages before the page after ```cursor```.
+    pub fn before_document(&self, cursor: &Cursor) -> Document {
+        doc! {"$nor": [self.after_document(cursor)]}
+    }
+}
+
+/// Points to a game within a sorted list of games.
+/// Clients should treat the encoded cursor as an opaque string.
+#[derive(Debug, PartialEq)]
+pub struct Cursor {
+    /// The value of the sorted field of the game.
+    value: i64,
+
+    /// The id of the game.
+    id: String,
+}
+
+impl Cursor {
+    /// Creates a cursor pointing to ```game```.
+    fn from_game(game: &Game, field: GameSortField) -> Self {
+        let value = match field {
+            GameSortField::CreatedAt => game.created_at().timestamp_millis(),
+            GameSortField::RoundNum => game.round_num() as i64,
+        };
+
+        Cursor {
+            value,
+            id: game.id(),
+        }
+    }
+
+    /// Encodes the cursor into a string to give to clients.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.value, self.id))
+    }
+
+    /// Decodes a cursor given by a client.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 400 error if ```cursor``` was not made by ```Cursor::encode```.
+    pub fn decode(cursor: &str) -> GraphqlServerResult<Self> {
+        let invalid =
+            || GraphqlServerError::new("cursor provided is not valid".to_string(), &CODE400);
+
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let decoded = String::from_utf8(bytes).map_err(|_| invalid())?;
+
+        let (value, id) = decoded.split_once(':').ok_or_else(invalid)?;
+        let value: i64 = value.parse().map_err(|_| invalid())?;
+        let id = Game::parse_id(id).map_err(|_| invalid())?;
+
+        Ok(Cursor { value, id })
+    }
+}
+
+/// Checks the number of games asked for in a page.
+///
+/// # Errors
+///
+/// Will throw a 400 error if ```first``` is negative or more than ```MAX_PAGE_SIZE```.
+pub fn check_page_size(first: i32) -> GraphqlServerResult<i32> {
+    if !(0..=MAX_PAGE_SIZE).contains(&first) {
+        return Err(GraphqlServerError::new(
+            format!("first should be between 0 and {}", MAX_PAGE_SIZE),
+            &CODE400,
+        ));
+    }
+
+    Ok(first)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = Cursor {
+            value: 1_720_000_000_000,
+            id: Game::new_game("words").id(),
+        };
+
+        let decoded = Cursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(cursor, decoded)
+    }
+
+    #[test]
+    fn invalid_cursor_is_rejected() {
+        assert!(Cursor::decode("not a cursor").is_err());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("12:not-a-uuid")).is_err());
+    }
+
+    #[test]
+    fn player_filter_matches_either_player() {
+        let player_id = PlayerProfile::new_player("name").unwrap().id();
+        let filter = GameFilter {
+            player_id: Some(player_id.clone()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            filter.to_document(),
+            doc! {"$or": [{"p1_id": &player_id}, {"p2_id": &player_id}]}
+        )
+    }
+
+    #[test]
+    fn filter_only_includes_given_fields() {
+        let filter = GameFilter {
+            status: Some(GameStatus::Finished),
+            ..Default::default()
+        };
+
+        assert_eq!(filter.to_document(), doc! {"status": "Finished"})
+    }
+}
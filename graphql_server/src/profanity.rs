@@ -0,0 +1,64 @@
+// This file contains a basic profanity filter for chat messages
+// It only knows a short list of words, and hides them rather than rejecting the message
+
+/// Words hidden by the filter. Words are compared ignoring case.
+const BLOCKED_WORDS: [&str; 16] = [
+    "arse", "ass", "asshole", "bastard", "bitch", "bollocks", "crap", "damn", "dick", "fuck",
+    "piss", "prick", "shit", "slut", "twat", "wanker",
+];
+
+/// Endings that can be added to a blocked word and still be hidden, e.g. "damned".
+/// Only whole words are checked, so words that merely contain a blocked word (e.g. "class") are kept.
+const BLOCKED_ENDINGS: [&str; 8] = ["", "s", "es", "ed", "er", "ers", "ing", "y"];
+
+/// Checks if ```word``` is a blocked word, or a blocked word with one of the blocked endings.
+fn is_blocked(word: &str) -> bool {
+    let word = word.to_lowercase();
+
+    BLOCKED_WORDS.iter().any(|blocked| {
+        word.strip_prefix(blocked)
+            .is_some_and(|ending| BLOCKED_ENDINGS.contains(&ending))
+    })
+}
+
+/// Hides the blocked words in ```text``` by replacing each of their letters with ```*```.
+pub fn censor(text: &str) -> String {
+    let mut censored = String::with_capacity(text.len());
+    let mut word = String::new();
+
+    // words are the runs of alphanumeric characters, everything else is kept as is
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() {
+            word.push(c);
+            continue;
+        }
+
+        if is_blocked(&word) {
+            censored.extend(word.chars().map(|_| '*'));
+        } else {
+            censored.push_str(&word);
+        }
+        word.clear();
+        censored.push(c);
+    }
+
+    // remove the space added to end the last word
+    censored.pop();
+    censored
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hides_blocked_words() {
+        assert_eq!(censor("oh Crap, damned word!"), "oh ****, ****** word!");
+    }
+
+    #[test]
+    fn keeps_words_containing_blocked_words() {
+        let text = "assume the class passed the scrapbook";
+        assert_eq!(censor(text), text);
+    }
+}
@@ -1,64 +1,311 @@
 use super::{
-    errors::{GraphqlServerError, CODE404, CODE500},
+    chat::{messages, ChatMessage},
+    context::{Context, Role},
+    daily::{daily_board, DailyBoardEntry, DEFAULT_DAILY_BOARD_SIZE},
+    export::export_json,
+    replay::{replay, ReplaySnapshot},
+    solver::{bot_turn, BotTurn},
+    errors::{GraphqlServerError, CODE422, CODE500},
     game_collection,
-    models::Game,
-    MongoClient,
+    leaderboard::{
+        coop_leaderboard, leaderboard, CoopLeaderboardEntry, LeaderboardEntry, LeaderboardPeriod,
+        DEFAULT_LEADERBOARD_SIZE,
+    },
+    models::{Game, PlayerProfile},
+    packs::{find_pack, word_packs, WordPack},
+    pagination::{
+        check_page_size, Cursor, GameConnection, GameFilter, GameSort, DEFAULT_PAGE_SIZE,
+    },
+    spectator::{count_spectators, keep_watching},
+    stats::{player_stats, PlayerStats},
+    store::{find_game, find_player},
 };
+use chrono::{NaiveDate, Utc};
 use juniper::{graphql_object, FieldResult, IntoFieldError};
-use rocket_db_pools::mongodb::{bson::doc, Collection};
+use rocket_db_pools::mongodb::{
+    bson::doc,
+    options::{CountOptions, FindOptions},
+    Collection,
+};
+use uuid::Uuid;
 
 /// Root query node
 pub struct Query;
 
 #[graphql_object]
-#[graphql(context = MongoClient)]
+#[graphql(context = Context)]
 impl Query {
     fn api_version() -> &'static str {
         "1.0"
     }
 
-    /// Get all games
-    async fn games(context: &MongoClient) -> FieldResult<Vec<Game>> {
+    /// Get a page of games.
+    ///
+    /// Games can be filtered by ```filter``` and sorted by ```sort```, newest games are first by default.
+    /// To get the next page, pass the ```endCursor``` of the current page as ```after```.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 400 error if ```first``` is out of range or ```after``` is not a valid cursor.
+    /// Will throw a 500 error if failed to query the database.
+    async fn games(
+        context: &Context,
+        #[graphql(default = DEFAULT_PAGE_SIZE)] first: i32,
+        after: Option<String>,
+        filter: Option<GameFilter>,
+        sort: Option<GameSort>,
+    ) -> FieldResult<GameConnection> {
         let games: Collection<Game> = game_collection(context);
 
-        let query_result = games.find(doc! {}, None).await;
+        let first = check_page_size(first)?;
+        let filter = filter.unwrap_or_default().to_document();
+        let sort = sort.unwrap_or_default();
+
+        let total_count = games
+            .count_documents(filter.clone(), None)
+            .await
+            .map_err(|_| {
+                GraphqlServerError::new("Failed to execute count games query".to_string(), &CODE500)
+                    .into_field_error()
+            })?;
 
-        match query_result {
-            Err(_e) => Ok(Vec::<Game>::new()),
-            Ok(mut cursor) => {
-                let mut all_games: Vec<Game> = Vec::new();
+        // only games after the cursor belong in this page, and games up to the cursor were on earlier pages
+        let (page_filter, has_previous_page) = match &after {
+            None => (filter, false),
+            Some(after) => {
+                let cursor = Cursor::decode(after)?;
+                let previous_filter =
+                    doc! {"$and": [filter.clone(), sort.before_document(&cursor)]};
+                let previous_options = CountOptions::builder().limit(1).build();
 
-                while cursor.advance().await? {
-                    all_games.push(cursor.deserialize_current()?);
-                }
+                let previous_count = games
+                    .count_documents(previous_filter, previous_options)
+                    .await
+                    .map_err(|_| {
+                        GraphqlServerError::new(
+                            "Failed to execute count games query".to_string(),
+                            &CODE500,
+                        )
+                        .into_field_error()
+                    })?;
 
-                Ok(all_games)
+                (
+                    doc! {"$and": [filter, sort.after_document(&cursor)]},
+                    previous_count > 0,
+                )
             }
+        };
+
+        // get one extra game to tell if there is a next page
+        let options = FindOptions::builder()
+            .sort(sort.to_document())
+            .limit(first as i64 + 1)
+            .build();
+
+        let find_error = || {
+            GraphqlServerError::new("Failed to execute find games query".to_string(), &CODE500)
+                .into_field_error()
+        };
+
+        let mut cursor = games
+            .find(page_filter, options)
+            .await
+            .map_err(|_| find_error())?;
+
+        let mut page_games: Vec<Game> = Vec::new();
+        while cursor.advance().await.map_err(|_| find_error())? {
+            let mut game = cursor.deserialize_current().map_err(|_| find_error())?;
+            context.hide_daily_answer(&mut game);
+            page_games.push(game);
         }
+        count_spectators(context, &mut page_games).await?;
+
+        Ok(GameConnection::new(
+            page_games,
+            first,
+            &sort,
+            has_previous_page,
+            total_count as i32,
+        ))
     }
 
-    /// Get a game
-    async fn game(context: &MongoClient, id: String) -> FieldResult<Game> {
+    /// Get a game.
+    /// Spectators are counted as watching the game they spectate for as long as they keep getting it.
+    /// The answer of a daily challenge is hidden from everyone but its players until the day is over.
+    async fn game(context: &Context, id: String) -> FieldResult<Game> {
         // get game_id
         let game_id = Game::parse_id(&id)?;
 
-        let games: Collection<Game> = game_collection(context);
+        if let Role::Spectator(token) = context.role() {
+            keep_watching(context, token, &game_id).await?;
+        }
+
+        let mut game = find_game(context, &game_id).await?;
+        context.hide_daily_answer(&mut game);
+        count_spectators(context, std::slice::from_mut(&mut game)).await?;
+        Ok(game)
+    }
 
-        let query_result = games.find_one(doc! {"_id": &game_id}, None).await;
-
-        match query_result {
-            Ok(game) => match game {
-                None => Err(
-                    GraphqlServerError::new("No game with id found".to_string(), &CODE404)
-                        .into_field_error(),
-                ),
-                Some(game) => Ok(game),
-            },
-            Err(_) => Err(GraphqlServerError::new(
-                "Failed to execute find game query".to_string(),
-                &CODE500,
+    /// Gets the next turn of the bot playing as player 2 of a game, see ```solver```.
+    /// The bot plays the turn with ```playTurn```, passing on the letterpool state returned,
+    /// which is worked out from the letter states of the guess.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 404 error if there is no game with the id.
+    /// Will throw a 422 error if the game has no bot, or it isn't the bot's turn (see ```bot_turn```).
+    async fn bot_turn(context: &Context, game_id: String) -> FieldResult<BotTurn> {
+        let game_id = Game::parse_id(&game_id)?;
+        let game = find_game(context, &game_id).await?;
+
+        // random valid bots pick a different word every time
+        let seed = Uuid::new_v4().as_u128() as u64;
+        Ok(bot_turn(context, &game, seed).await?)
+    }
+
+    /// Get a game as a JSON document that can be shared, and imported with ```importGame```.
+    /// The document has a format version, the game config, and every round with its turns and timestamps.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 404 error if there is no game with the id.
+    /// Will throw a 422 error if the word of the current round is hidden, see ```Game::hides_word```,
+    /// or the game plays a daily challenge that isn't over yet, unless requested by its players.
+    async fn export_game(context: &Context, id: String) -> FieldResult<String> {
+        let game_id = Game::parse_id(&id)?;
+        let game = find_game(context, &game_id).await?;
+
+        // the export has every word, so it would give away a secret word or the answer of the daily challenge
+        let today = Utc::now().date_naive();
+        let hides_daily_answer =
+            game.has_daily_answer(today) && context.check_either_player_of(&game).is_err();
+        if game.hides_word() || hides_daily_answer {
+            return Err(GraphqlServerError::new(
+                "game can't be exported while the word of its round is hidden".to_string(),
+                &CODE422,
             )
-            .into_field_error()),
+            .into_field_error());
         }
+
+        Ok(export_json(&game))
+    }
+
+    /// Get a snapshot of a finished game after every turn, in the order the turns were played.
+    /// Each snapshot has the board and letterpool of the round, the points and whose turn is next.
+    /// The words of a daily challenge are hidden from everyone but its players until the day is over.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 404 error if there is no game with the id,
+    /// or a 422 error if the game is still being played.
+    async fn replay(context: &Context, game_id: String) -> FieldResult<Vec<ReplaySnapshot>> {
+        let game_id = Game::parse_id(&game_id)?;
+
+        let mut game = find_game(context, &game_id).await?;
+        context.hide_daily_answer(&mut game);
+
+        Ok(replay(&game)?)
+    }
+
+    /// Get the chat messages of a game in the order they were sent, up to 100 at a time.
+    /// Pass the id of the last message received as ```after``` to get the messages sent after it.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 404 error if there is no game with the id, or ```after``` isn't a message of the game.
+    /// Will throw a 500 error if failed to query the database.
+    async fn messages(
+        context: &Context,
+        game_id: String,
+        after: Option<String>,
+    ) -> FieldResult<Vec<ChatMessage>> {
+        let game_id = Game::parse_id(&game_id)?;
+        find_game(context, &game_id).await?;
+
+        Ok(messages(context, &game_id, after).await?)
+    }
+
+    /// Get the profile of a player
+    async fn player(context: &Context, id: String) -> FieldResult<PlayerProfile> {
+        let player_id = PlayerProfile::parse_id(&id)?;
+
+        Ok(find_player(context, &player_id).await?)
+    }
+
+    /// Get the statistics of a player over the games they finished
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 404 error if there is no player with the id.
+    /// Will throw a 500 error if failed to query the database.
+    async fn player_stats(context: &Context, player_id: String) -> FieldResult<PlayerStats> {
+        let player_id = PlayerProfile::parse_id(&player_id)?;
+        find_player(context, &player_id).await?;
+
+        Ok(player_stats(context, &player_id).await?)
+    }
+
+    /// Get the best players of the versus and race games finished in ```period```, ranked by wins then points.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 400 error if ```limit``` is out of range.
+    /// Will throw a 500 error if failed to query the database.
+    async fn leaderboard(
+        context: &Context,
+        #[graphql(default = LeaderboardPeriod::All)] period: LeaderboardPeriod,
+        #[graphql(default = DEFAULT_LEADERBOARD_SIZE)] limit: i32,
+    ) -> FieldResult<Vec<LeaderboardEntry>> {
+        Ok(leaderboard(context, period, limit).await?)
+    }
+
+    /// Get the best teams of the co-op games finished in ```period```, ranked by team points.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 400 error if ```limit``` is out of range.
+    /// Will throw a 500 error if failed to query the database.
+    async fn coop_leaderboard(
+        context: &Context,
+        #[graphql(default = LeaderboardPeriod::All)] period: LeaderboardPeriod,
+        #[graphql(default = DEFAULT_LEADERBOARD_SIZE)] limit: i32,
+    ) -> FieldResult<Vec<CoopLeaderboardEntry>> {
+        Ok(coop_leaderboard(context, period, limit).await?)
+    }
+
+    /// Get the pairs who finished the daily challenge of ```date```, which defaults to today (UTC).
+    /// Pairs who guessed the word rank first, then pairs with fewer guesses, then quicker pairs.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 400 error if ```limit``` is out of range.
+    /// Will throw a 500 error if failed to query the database.
+    async fn daily_board(
+        context: &Context,
+        date: Option<NaiveDate>,
+        #[graphql(default = DEFAULT_DAILY_BOARD_SIZE)] limit: i32,
+    ) -> FieldResult<Vec<DailyBoardEntry>> {
+        let date = date.unwrap_or_else(|| Utc::now().date_naive());
+
+        Ok(daily_board(context, date, limit).await?)
+    }
+
+    /// Get every word pack, in order of name.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 500 error if failed to query the database.
+    async fn word_packs(context: &Context) -> FieldResult<Vec<WordPack>> {
+        Ok(word_packs(context).await?)
+    }
+
+    /// Get the word pack named ```name```.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 404 error if there is no word pack with the name.
+    /// Will throw a 500 error if failed to query the database.
+    async fn word_pack(context: &Context, name: String) -> FieldResult<WordPack> {
+        Ok(find_pack(context, &name).await?)
     }
 }
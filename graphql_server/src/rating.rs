@@ -0,0 +1,177 @@
+use rocket_db_pools::mongodb::{bson::doc, Collection};
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE500},
+    models::{Game, GameMode, Player, PlayerProfile},
+    player_collection,
+    store::{find_player, save_game},
+    MongoClient,
+};
+
+// This file contains the Elo rating system used to rate players by their game results
+
+/// The rating a new player starts with.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// The number of games a player's rating is provisional for.
+/// Provisional ratings change faster, so new players quickly reach a rating that fits them.
+pub const PROVISIONAL_GAMES: i32 = 30;
+
+/// The number of ids of the latest games that changed a player's rating kept with the player.
+pub const RATED_GAME_IDS_KEPT: i32 = 100;
+
+/// How much a rating can change after a game while provisional.
+const PROVISIONAL_K: f64 = 40.0;
+
+/// How much a rating can change after a game once established.
+const ESTABLISHED_K: f64 = 20.0;
+
+/// The result of a game for a player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// The player won.
+    Win,
+    /// The players had the same points.
+    Draw,
+    /// The player lost.
+    Loss,
+}
+
+impl Outcome {
+    /// The score of the outcome used by Elo.
+    fn score(&self) -> f64 {
+        match self {
+            Outcome::Win => 1.0,
+            Outcome::Draw => 0.5,
+            Outcome::Loss => 0.0,
+        }
+    }
+
+    /// The outcome for the opponent.
+    fn opposite(&self) -> Outcome {
+        match self {
+            Outcome::Win => Outcome::Loss,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss => Outcome::Win,
+        }
+    }
+}
+
+/// The chance of a player with ```rating``` beating a player with ```opponent_rating```.
+pub fn expected_score(rating: f64, opponent_rating: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0))
+}
+
+/// The change in rating of a player after a game.
+///
+/// ```rated_games``` is the number of rated games the player played before this game.
+pub fn rating_change(rating: f64, rated_games: i32, opponent_rating: f64, outcome: Outcome) -> f64 {
+    let k = if rated_games < PROVISIONAL_GAMES {
+        PROVISIONAL_K
+    } else {
+        ESTABLISHED_K
+    };
+
+    k * (outcome.score() - expected_score(rating, opponent_rating))
+}
+
+/// Updates the ratings of the players of ```game``` if it has finished and hasn't been rated yet,
+/// then saves the game as rated.
+/// A player's rating only changes once for a game, so this can be retried if it failed partway through.
+///
+/// # Errors
+///
+/// Will throw an error if a player no longer exists, or failed to save a rating or the game.
+/// A 409 error means another change to the game was saved first, and this can be retried.
+pub async fn rate_game(client: &MongoClient, game: &mut Game) -> GraphqlServerResult<()> {
+    if !game.needs_rating() {
+        return Ok(());
+    }
+
+    update_ratings(client, game).await?;
+
+    game.set_rated();
+    save_game(client, game).await
+}
+
+/// Updates the ratings of the players of a game that finished.
+/// Nothing happens if either player didn't play with a profile, or the game was co-op as no one won.
+/// A player whose rating was already changed by the game is skipped.
+///
+/// # Errors
+///
+/// Will throw an error if a player no longer exists or failed to save a rating.
+async fn update_ratings(client: &MongoClient, game: &Game) -> GraphqlServerResult<()> {
+    if game.config().mode() == GameMode::CoOp {
+        return Ok(());
+    }
+
+    let (Some(p1_id), Some(p2_id)) = (game.player_id(Player::P1), game.player_id(Player::P2))
+    else {
+        return Ok(());
+    };
+
+    let p1 = find_player(client, p1_id).await?;
+    let p2 = find_player(client, p2_id).await?;
+
+    let p1_outcome = game.outcome(Player::P1);
+    let p1_change = rating_change(p1.rating(), p1.rated_games(), p2.rating(), p1_outcome);
+    let p2_change = rating_change(
+        p2.rating(),
+        p2.rated_games(),
+        p1.rating(),
+        p1_outcome.opposite(),
+    );
+
+    let players: Collection<PlayerProfile> = player_collection(client);
+    for (player_id, change) in [(p1_id, p1_change), (p2_id, p2_change)] {
+        // only players the game hasn't rated yet match
+        let filter = doc! {"_id": player_id, "rated_game_ids": {"$ne": game.id()}};
+        // increment, so a rating changed by another game finishing at the same time isn't overwritten
+        let update = doc! {
+            "$inc": {"rating": change, "rated_games": 1},
+            "$push": {"rated_game_ids": {"$each": [game.id()], "$slice": -RATED_GAME_IDS_KEPT}},
+        };
+
+        if players.update_one(filter, update, None).await.is_err() {
+            return Err(GraphqlServerError::new(
+                "Failed to execute update rating".to_string(),
+                &CODE500,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn equal_ratings_are_even() {
+        assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+
+        let win = rating_change(1500.0, PROVISIONAL_GAMES, 1500.0, Outcome::Win);
+        let loss = rating_change(1500.0, PROVISIONAL_GAMES, 1500.0, Outcome::Loss);
+        assert_eq!(win, ESTABLISHED_K / 2.0);
+        assert_eq!(loss, -win);
+    }
+
+    #[test]
+    fn beating_stronger_player_gains_more() {
+        let upset = rating_change(1400.0, PROVISIONAL_GAMES, 1800.0, Outcome::Win);
+        let expected_win = rating_change(1800.0, PROVISIONAL_GAMES, 1400.0, Outcome::Win);
+
+        assert!(upset > expected_win);
+        assert!(rating_change(1800.0, PROVISIONAL_GAMES, 1400.0, Outcome::Draw) < 0.0);
+    }
+
+    #[test]
+    fn provisional_ratings_change_faster() {
+        let provisional = rating_change(1500.0, 0, 1500.0, Outcome::Win);
+        let established = rating_change(1500.0, PROVISIONAL_GAMES, 1500.0, Outcome::Win);
+
+        assert!(provisional > established);
+    }
+}
@@ -0,0 +1,349 @@
+use chrono::{DateTime, Utc};
+use juniper::GraphQLObject;
+use std::collections::BTreeMap;
+
+use super::{
+    encoding::{decode, LetterState},
+    errors::{GraphqlServerError, GraphqlServerResult, CODE422},
+    letters::{letter_count, letters},
+    models::{Game, GameMode, GameStatus, Player, Round, Turn},
+    scoring::{hint_score, score_rounds, RoundScore},
+};
+
+// This file contains the replay of a finished game, made of a snapshot after every turn
+// Snapshots are worked out from the turns of the game, so only the turns have to be stored
+// In a race each player guesses on their own board, so a snapshot shows the board of the player who made the turn
+
+/// A guess on the board of a round.
+#[derive(Debug, Clone, PartialEq, GraphQLObject)]
+pub struct BoardRow {
+    /// The player who made the guess.
+    player: Player,
+
+    /// The word guessed.
+    guessed_word: String,
+
+    /// The state of each letter of the guess.
+    letter_states: Vec<LetterState>,
+}
+
+/// The best state known for a letter after the guesses so far.
+#[derive(Debug, Clone, PartialEq, GraphQLObject)]
+pub struct LetterHint {
+    /// The letter guessed.
+    letter: String,
+
+    /// The best state the letter has had in any guess, green being the best.
+    state: LetterState,
+}
+
+/// The state of a game right after a turn.
+#[derive(Debug, Clone, PartialEq, GraphQLObject)]
+pub struct ReplaySnapshot {
+    /// The number of the round the turn was played in, starting at 0.
+    round_num: i32,
+
+    /// The number of the turn in its round, starting at 1.
+    /// Is 0 for the snapshot of a round without turns, taken after its hints.
+    turn_num: i32,
+
+    /// When the turn was played.
+    played_at: DateTime<Utc>,
+
+    /// The word players were trying to guess in the round.
+    target_word: String,
+
+    /// Every guess made in the round so far, including this turn.
+    /// In a race, only the guesses on the board of the player who made this turn.
+    board: Vec<BoardRow>,
+
+    /// The letters guessed on ```board``` so far, in alphabetical order.
+    letterpool: Vec<LetterHint>,
+
+    /// The points of player 1 after the turn.
+    p1_points: i32,
+
+    /// The points of player 2 after the turn.
+    p2_points: i32,
+
+    /// The points the players of a co-op game scored together after the turn.
+    team_points: i32,
+
+    /// The player whose turn it is after the turn.
+    /// Is null if the turn ended the round, or the game is a race, where players don't take turns.
+    current_player: Option<Player>,
+}
+
+/// Decodes the states of the letters of a turn.
+///
+/// # Errors
+///
+/// Will throw a 422 error if the letter state of the turn can't be decoded.
+fn board_row(turn: &Turn) -> GraphqlServerResult<BoardRow> {
+    let word_length = letter_count(turn.guessed_word());
+
+    let Some(letter_states) = decode(turn.letter_state(), word_length) else {
+        return Err(GraphqlServerError::new(
+            format!(
+                "letter state of guess {} can't be decoded",
+                turn.guessed_word()
+            ),
+            &CODE422,
+        ));
+    };
+
+    Ok(BoardRow {
+        player: turn.player(),
+        guessed_word: turn.guessed_word().to_string(),
+        letter_states,
+    })
+}
+
+/// Works out the best state of each letter on ```board```.
+fn letterpool(board: &[BoardRow]) -> Vec<LetterHint> {
+    let mut best_states: BTreeMap<&str, LetterState> = BTreeMap::new();
+
+    for row in board {
+        for (letter, state) in letters(&row.guessed_word)
+            .into_iter()
+            .zip(&row.letter_states)
+        {
+            let best = best_states.entry(letter).or_insert(*state);
+            *best = (*best).max(*state);
+        }
+    }
+
+    best_states
+        .into_iter()
+        .map(|(letter, state)| LetterHint {
+            letter: letter.to_string(),
+            state,
+        })
+        .collect()
+}
+
+/// Adds ```score``` to the points of the game so far.
+fn add_points(points: &mut RoundScore, score: RoundScore) {
+    points.p1 += score.p1;
+    points.p2 += score.p2;
+    points.team += score.team;
+}
+
+/// Replays a finished game, returning a snapshot after every turn in the order they were played.
+///
+/// # Errors
+///
+/// Will throw a 422 error if the game is still being played, so its target words aren't given away,
+/// or if a turn's letter state can't be decoded.
+pub fn replay(game: &Game) -> GraphqlServerResult<Vec<ReplaySnapshot>> {
+    if game.status() != GameStatus::Finished {
+        return Err(GraphqlServerError::new(
+            "only finished games can be replayed".to_string(),
+            &CODE422,
+        ));
+    }
+
+    let mut snapshots = Vec::new();
+    let mut points = RoundScore::default();
+
+    let rounds: Vec<&Round> = game.all_rounds().collect();
+    let scores = score_rounds(game.config(), &rounds);
+    let race = game.config().mode() == GameMode::Race;
+
+    for (round_num, (round, score)) in rounds.into_iter().zip(scores).enumerate() {
+        let turns = round.turns();
+        let mut shared_board = Vec::with_capacity(turns.len());
+        let (mut p1_board, mut p2_board) = (Vec::new(), Vec::new());
+        let mut hints = round.hints().iter().peekable();
+
+        for (i, turn) in turns.iter().enumerate() {
+            let board = match (race, turn.player()) {
+                (false, _) => &mut shared_board,
+                (true, Player::P1) => &mut p1_board,
+                (true, Player::P2) => &mut p2_board,
+            };
+            board.push(board_row(turn)?);
+
+            // hints cost points when they are requested, so are taken off from the turn after them,
+            // and hints requested after the last turn are taken off with the last turn
+            let is_last_turn = i + 1 == turns.len();
+            while let Some(hint) =
+                hints.next_if(|hint| is_last_turn || hint.requested_at() <= turn.played_at())
+            {
+                add_points(&mut points, hint_score(game.config(), hint));
+            }
+
+            if is_last_turn {
+                add_points(&mut points, score);
+            }
+
+            let current_player = match turns.get(i + 1) {
+                _ if race => None,
+                Some(next_turn) => Some(next_turn.player()),
+                None if round.is_over() => None,
+                None => Some(round.current_player()),
+            };
+
+            snapshots.push(ReplaySnapshot {
+                round_num: round_num as i32,
+                turn_num: i as i32 + 1,
+                played_at: turn.played_at(),
+                target_word: round.target_word().to_string(),
+                board: board.clone(),
+                letterpool: letterpool(board),
+                p1_points: points.p1,
+                p2_points: points.p2,
+                team_points: points.team,
+                current_player,
+            });
+        }
+
+        if !turns.is_empty() {
+            continue;
+        }
+
+        // a round without turns still costs its hints, so they are shown in a snapshot without a board
+        for hint in hints {
+            add_points(&mut points, hint_score(game.config(), hint));
+        }
+        add_points(&mut points, score);
+
+        if let Some(last_hint) = round.hints().last() {
+            snapshots.push(ReplaySnapshot {
+                round_num: round_num as i32,
+                turn_num: 0,
+                played_at: last_hint.requested_at(),
+                target_word: round.target_word().to_string(),
+                board: Vec::new(),
+                letterpool: Vec::new(),
+                p1_points: points.p1,
+                p2_points: points.p2,
+                team_points: points.team,
+                current_player: None,
+            });
+        }
+    }
+
+    Ok(snapshots)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hints::HintKind;
+    use crate::models::{NewTurn, UpdateRound};
+
+    fn play(game: &mut Game, guess: &str, letter_state: i32, next_player: Player) {
+        let update = UpdateRound::new(
+            &game.id(),
+            NewTurn::new(guess, letter_state),
+            0,
+            next_player,
+        );
+        game.play_turn(update).unwrap();
+    }
+
+    #[test]
+    fn snapshot_after_every_turn() {
+        let mut game = Game::new_game("words");
+        play(&mut game, "crane", 0b01_01_01_10_01, Player::P2);
+        play(&mut game, "words", 0b11_11_11_11_11, Player::P1);
+        game.start_round("crane").unwrap();
+        play(&mut game, "crate", 0b11_11_11_01_11, Player::P2);
+
+        assert!(replay(&game).is_err());
+        game.finish();
+
+        let snapshots = replay(&game).unwrap();
+        assert_eq!(snapshots.len(), 3);
+
+        assert_eq!(snapshots[0].current_player, Some(Player::P2));
+        assert_eq!(snapshots[0].p2_points, 0);
+        assert_eq!(snapshots[1].current_player, None);
+        assert_eq!(snapshots[1].p2_points, 1);
+        assert_eq!(snapshots[1].board.len(), 2);
+
+        assert_eq!(snapshots[2].round_num, 1);
+        assert_eq!(snapshots[2].board.len(), 1);
+        assert_eq!(snapshots[2].p2_points, 1);
+    }
+
+    #[test]
+    fn race_snapshots_show_own_board() {
+        let mut game = Game::new_game("words");
+        let mut config = serde_json::to_value(game.config()).unwrap();
+        config["mode"] = serde_json::to_value(GameMode::Race).unwrap();
+        game.set_config(serde_json::from_value(config).unwrap());
+
+        let guess = |guess: &str, letter_state: i32| {
+            UpdateRound::new(&game.id(), NewTurn::new(guess, letter_state), 0, Player::P1)
+        };
+        let (crane, words) = (
+            guess("crane", 0b01_01_10_01_01),
+            guess("words", 0b11_11_11_11_11),
+        );
+        game.play_race_turn(Player::P1, crane).unwrap();
+        game.play_race_turn(Player::P2, words).unwrap();
+        game.finish();
+
+        let snapshots = replay(&game).unwrap();
+        assert_eq!(snapshots.len(), 2);
+
+        // player 2 doesn't see the guess of player 1
+        assert_eq!(snapshots[1].board.len(), 1);
+        assert_eq!(snapshots[1].board[0].player, Player::P2);
+        assert!(snapshots[1]
+            .letterpool
+            .iter()
+            .all(|hint| hint.state == LetterState::Green));
+        assert_eq!(snapshots[0].current_player, None);
+    }
+
+    #[test]
+    fn hints_cost_points_after_the_last_turn() {
+        let mut game = Game::new_game("words");
+        play(&mut game, "crane", 0b01_01_10_01_01, Player::P2);
+        game.request_hint(Player::P2, HintKind::RevealLetter, 0)
+            .unwrap();
+        game.finish();
+
+        let snapshots = replay(&game).unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].p2_points, -1);
+    }
+
+    #[test]
+    fn rounds_without_turns_show_their_hints() {
+        let mut game = Game::new_game("words");
+        game.request_hint(Player::P1, HintKind::RevealLetter, 0)
+            .unwrap();
+        game.finish();
+
+        let snapshots = replay(&game).unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].turn_num, 0);
+        assert!(snapshots[0].board.is_empty());
+        assert_eq!(snapshots[0].p1_points, -1);
+    }
+
+    #[test]
+    fn letterpool_keeps_best_state() {
+        let board = [
+            BoardRow {
+                player: Player::P1,
+                guessed_word: "aa".to_string(),
+                letter_states: vec![LetterState::Yellow, LetterState::Black],
+            },
+            BoardRow {
+                player: Player::P2,
+                guessed_word: "ba".to_string(),
+                letter_states: vec![LetterState::Black, LetterState::Green],
+            },
+        ];
+
+        let letters = letterpool(&board);
+        assert_eq!(letters[0].letter, "a");
+        assert_eq!(letters[0].state, LetterState::Green);
+        assert_eq!(letters[1].state, LetterState::Black);
+    }
+}
@@ -0,0 +1,389 @@
+use juniper::{GraphQLInputObject, GraphQLObject};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+
+use super::{
+    encoding::{decode, LetterState},
+    errors::{GraphqlServerError, GraphqlServerResult, CODE400},
+    letters::letters,
+    models::{GameConfig, GameMode, Hint, Player, Round, MAX_GUESSES},
+};
+
+// This file contains the scoring engine, which works out the points of each round from the scoring rules of a game
+// The rules are data in the config of the game, so games can be played with different rules
+// The default rules are a point for whoever guesses the word, so games made before rules existed score the same
+//
+// How each mode is scored:
+// Versus: the solver gets the points for the guesses used, and the bonus if they started the round,
+//         and each player loses the penalty for every letter they revealed that the opponent then used
+// Race: the winner gets the points for the guesses they used on their board
+// Co-op: the team gets the points for the guesses used
+// Secret word: the winner of each pair of rounds gets the points for the guesses they used
+//
+// Hints cost the player who requested them points as soon as they are given, or the team in a co-op game
+
+/// The max number of points a rule can give or take away.
+pub const MAX_RULE_POINTS: i32 = 100;
+
+/// The rules rounds are scored with.
+#[derive(Debug, Clone, PartialEq, GraphQLObject, Serialize, Deserialize)]
+pub struct ScoringRules {
+    /// The points for guessing the word, by the number of guesses used, starting at 1 guess.
+    /// Guessing the word in more guesses than there are entries gives the points of the last entry.
+    points_by_guesses: Vec<i32>,
+
+    /// The bonus for guessing the word on your own turn in a round you started,
+    /// rather than in a round the opponent started.
+    own_turn_bonus: i32,
+
+    /// The points lost for each letter a player revealed, that the opponent then used in a guess.
+    reveal_penalty: i32,
+
+    /// The points lost for each hint requested.
+    #[serde(default = "default_hint_cost")]
+    hint_cost: i32,
+}
+
+/// Used by serde for rules saved before hints had a cost.
+fn default_hint_cost() -> i32 {
+    ScoringRules::default().hint_cost
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            points_by_guesses: vec![1],
+            own_turn_bonus: 0,
+            reveal_penalty: 0,
+            hint_cost: 1,
+        }
+    }
+}
+
+impl ScoringRules {
+    /// Checks that the rules can be played with.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 400 error if there are no points by guesses, more entries than ```MAX_GUESSES```,
+    /// or any points out of the range 0 to ```MAX_RULE_POINTS```.
+    pub fn check(&self) -> GraphqlServerResult<()> {
+        if self.points_by_guesses.is_empty() || self.points_by_guesses.len() > MAX_GUESSES as usize
+        {
+            return Err(GraphqlServerError::new(
+                format!(
+                    "points by guesses should have between 1 and {} entries",
+                    MAX_GUESSES
+                ),
+                &CODE400,
+            ));
+        }
+
+        let all_points = self.points_by_guesses.iter().chain([
+            &self.own_turn_bonus,
+            &self.reveal_penalty,
+            &self.hint_cost,
+        ]);
+        if all_points
+            .into_iter()
+            .any(|points| !(0..=MAX_RULE_POINTS).contains(points))
+        {
+            return Err(GraphqlServerError::new(
+                format!(
+                    "points of scoring rules should be between 0 and {}",
+                    MAX_RULE_POINTS
+                ),
+                &CODE400,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks if these are the default rules, a point for whoever guesses the word.
+    pub fn is_default(&self) -> bool {
+        *self == ScoringRules::default()
+    }
+
+    /// Gets the points for guessing the word in ```guesses``` guesses.
+    fn solve_points(&self, guesses: usize) -> i32 {
+        let index = guesses.clamp(1, self.points_by_guesses.len()) - 1;
+        self.points_by_guesses.get(index).copied().unwrap_or(0)
+    }
+}
+
+/// Scoring rules a new game can be created with. Essentially same as ```ScoringRules```, but used for graphql arguments.
+#[derive(Debug, Clone, GraphQLInputObject)]
+pub struct NewScoringRules {
+    /// The points for guessing the word, by the number of guesses used, starting at 1 guess.
+    points_by_guesses: Vec<i32>,
+
+    /// The bonus for guessing the word on your own turn in a round you started. Defaults to 0.
+    own_turn_bonus: Option<i32>,
+
+    /// The points lost for each letter a player revealed, that the opponent then used in a guess. Defaults to 0.
+    reveal_penalty: Option<i32>,
+
+    /// The points lost for each hint requested. Defaults to 1.
+    hint_cost: Option<i32>,
+}
+
+impl NewScoringRules {
+    /// Converts into the rules of a game, using the defaults for the points that weren't given.
+    pub fn to_rules(&self) -> ScoringRules {
+        ScoringRules {
+            points_by_guesses: self.points_by_guesses.clone(),
+            own_turn_bonus: self.own_turn_bonus.unwrap_or(0),
+            reveal_penalty: self.reveal_penalty.unwrap_or(0),
+            hint_cost: self.hint_cost.unwrap_or_else(default_hint_cost),
+        }
+    }
+}
+
+/// The points scored in a round.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RoundScore {
+    /// The points of player 1.
+    pub p1: i32,
+    /// The points of player 2.
+    pub p2: i32,
+    /// The points the players of a co-op game scored together.
+    pub team: i32,
+}
+
+impl RoundScore {
+    /// Adds ```points``` to the points of ```player```.
+    fn add(&mut self, player: Player, points: i32) {
+        match player {
+            Player::P1 => self.p1 += points,
+            Player::P2 => self.p2 += points,
+        }
+    }
+}
+
+/// Scores every round of a game played with ```config```, ```rounds``` being in the order they were played.
+/// Rounds that haven't ended score nothing, and the first round of a pair of secret word rounds
+/// scores nothing as the pair is scored with its second round.
+pub fn score_rounds(config: &GameConfig, rounds: &[&Round]) -> Vec<RoundScore> {
+    let rules = config.scoring();
+
+    rounds
+        .iter()
+        .enumerate()
+        .map(|(i, round)| {
+            let mut score = RoundScore::default();
+            if !round.is_over() {
+                return score;
+            }
+
+            match config.mode() {
+                GameMode::Versus => score_shared_board(rules, round, &mut score),
+                GameMode::Race => {
+                    if let Some(winner) = round.solved_by() {
+                        let guesses = round.board(winner).map_or(0, |board| board.guess_num());
+                        score.add(winner, rules.solve_points(guesses as usize));
+                    }
+                }
+                GameMode::CoOp => {
+                    if round.solved_by().is_some() {
+                        score.team += rules.solve_points(round.turns().len());
+                    }
+                }
+                GameMode::SecretWord => {
+                    // the second round of a pair is when each player has guessed a word set by the other
+                    if i % 2 == 1 {
+                        if let Some((winner, guesses)) = pair_winner(rounds[i - 1], round) {
+                            score.add(winner, rules.solve_points(guesses));
+                        }
+                    }
+                }
+            }
+
+            score
+        })
+        .collect()
+}
+
+/// Works out the points ```hint``` costs in a game played with ```config```.
+pub fn hint_score(config: &GameConfig, hint: &Hint) -> RoundScore {
+    let mut score = RoundScore::default();
+    match config.mode() {
+        GameMode::CoOp => score.team -= config.scoring().hint_cost,
+        _ => score.add(hint.player(), -config.scoring().hint_cost),
+    }
+    score
+}
+
+/// Scores a round of a versus game, where both players guess on the same board.
+fn score_shared_board(rules: &ScoringRules, round: &Round, score: &mut RoundScore) {
+    if let Some(solver) = round.solved_by() {
+        score.add(solver, rules.solve_points(round.turns().len()));
+
+        if solver == round.starting_player() {
+            score.add(solver, rules.own_turn_bonus);
+        }
+    }
+
+    if rules.reveal_penalty == 0 {
+        return;
+    }
+
+    // the player who first showed each letter is in the word, and if the opponent has used it since
+    let mut revealed: HashMap<&str, (Player, bool)> = HashMap::new();
+
+    for turn in round.turns() {
+        let guess_letters = letters(turn.guessed_word());
+
+        let used: BTreeSet<&str> = guess_letters.iter().copied().collect();
+        for letter in used {
+            if let Some((revealer, penalized)) = revealed.get_mut(letter) {
+                if *revealer != turn.player() && !*penalized {
+                    *penalized = true;
+                    score.add(*revealer, -rules.reveal_penalty);
+                }
+            }
+        }
+
+        let Some(states) = decode(turn.letter_state(), guess_letters.len()) else {
+            continue;
+        };
+        for (letter, state) in guess_letters.into_iter().zip(states) {
+            if state != LetterState::Black {
+                revealed.entry(letter).or_insert((turn.player(), false));
+            }
+        }
+    }
+}
+
+/// Works out who won a pair of rounds of a secret word game, where each player guessed a word set by the other,
+/// and the number of guesses they used.
+/// The player who guessed their word in fewer guesses wins, and no one wins if both took as many guesses.
+fn pair_winner(first: &Round, second: &Round) -> Option<(Player, usize)> {
+    let solve = |round: &Round| {
+        round
+            .solved_by()
+            .map(|player| (player, round.turns().len()))
+    };
+
+    match (solve(first), solve(second)) {
+        (Some(first_solve), Some(second_solve)) => match first_solve.1.cmp(&second_solve.1) {
+            Ordering::Less => Some(first_solve),
+            Ordering::Greater => Some(second_solve),
+            Ordering::Equal => None,
+        },
+        (Some(solve), None) | (None, Some(solve)) => Some(solve),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{Game, NewTurn, UpdateRound};
+
+    const SOLVED: i32 = 0b11_11_11_11_11;
+
+    fn rules(points_by_guesses: &[i32], own_turn_bonus: i32, reveal_penalty: i32) -> ScoringRules {
+        ScoringRules {
+            points_by_guesses: points_by_guesses.to_vec(),
+            own_turn_bonus,
+            reveal_penalty,
+            hint_cost: 1,
+        }
+    }
+
+    /// Plays ```guesses``` against "words", players taking turns starting with player 1.
+    fn round_of(guesses: &[(&str, i32)]) -> Game {
+        let mut game = Game::new_game("words");
+
+        for (i, (guess, letter_state)) in guesses.iter().enumerate() {
+            let next_player = if i % 2 == 0 { Player::P2 } else { Player::P1 };
+            let update = UpdateRound::new(
+                &game.id(),
+                NewTurn::new(guess, *letter_state),
+                0,
+                next_player,
+            );
+            game.play_turn(update).unwrap();
+        }
+
+        game
+    }
+
+    fn score(rules: &ScoringRules, game: &Game) -> RoundScore {
+        let mut score = RoundScore::default();
+        let round = game.all_rounds().last().unwrap();
+        score_shared_board(rules, round, &mut score);
+        score
+    }
+
+    #[test]
+    fn points_depend_on_guesses_used() {
+        let rules = rules(&[5, 3, 1], 0, 0);
+
+        let quick = round_of(&[("words", SOLVED)]);
+        assert_eq!(score(&rules, &quick).p1, 5);
+
+        // guessing after the last entry gives the points of the last entry
+        let slow = round_of(&[
+            ("crane", 0b01_01_01_10_01),
+            ("crane", 0b01_01_01_10_01),
+            ("crane", 0b01_01_01_10_01),
+            ("words", SOLVED),
+        ]);
+        assert_eq!(score(&rules, &slow).p2, 1);
+    }
+
+    #[test]
+    fn starter_gets_own_turn_bonus() {
+        let rules = rules(&[1], 2, 0);
+
+        let starter = round_of(&[("words", SOLVED)]);
+        assert_eq!(score(&rules, &starter).p1, 3);
+
+        let stolen = round_of(&[("crane", 0b01_01_01_10_01), ("words", SOLVED)]);
+        assert_eq!(score(&rules, &stolen).p2, 1);
+    }
+
+    #[test]
+    fn revealing_used_letters_is_penalized() {
+        let rules = rules(&[1], 0, 2);
+
+        // player 1 reveals "r", which player 2 then uses, and "s" only once for both "s" of "sassy"
+        let game = round_of(&[
+            ("crane", 0b01_01_01_10_01),
+            ("roses", 0b10_01_01_11_10),
+            ("sassy", 0b01_01_01_01_01),
+        ]);
+        let score = score(&rules, &game);
+
+        assert_eq!(score.p1, -2);
+        assert_eq!(score.p2, -2);
+    }
+
+    #[test]
+    fn default_rules_give_a_point_per_solve() {
+        let default = ScoringRules::default();
+        let game = round_of(&[("crane", 0b01_01_01_10_01), ("words", SOLVED)]);
+
+        assert!(default.is_default());
+        assert_eq!(
+            score(&default, &game),
+            RoundScore {
+                p1: 0,
+                p2: 1,
+                team: 0
+            }
+        );
+    }
+
+    #[test]
+    fn rules_are_checked() {
+        assert!(ScoringRules::default().check().is_ok());
+        assert!(rules(&[], 0, 0).check().is_err());
+        assert!(rules(&[1; 7], 0, 0).check().is_err());
+        assert!(rules(&[1], -1, 0).check().is_err());
+        assert!(rules(&[1], 0, MAX_RULE_POINTS + 1).check().is_err());
+    }
+}
@@ -0,0 +1,186 @@
+use rocket_db_pools::mongodb::{
+    bson::{doc, Bson, Document},
+    options::FindOptions,
+    Collection,
+};
+use std::collections::BTreeSet;
+
+use super::{
+    difficulty::dictionary_tiers,
+    errors::{GraphqlServerError, GraphqlServerResult, CODE404, CODE500},
+    game_collection,
+    models::{Game, GameConfig, Player},
+    packs::random_pack_word,
+    words::random_word,
+    MongoClient, RECENT_GAMES_WINDOW,
+};
+
+// This file contains how target words are picked for new games and rounds
+// A word is drawn from the dictionary at random, but words already used in the game,
+// or in the recent games of either player, are avoided so players don't see the same word again soon
+
+/// Gets the target words of every round of ```games```.
+fn used_words<'a>(games: impl IntoIterator<Item = &'a Game>) -> BTreeSet<String> {
+    games
+        .into_iter()
+        .flat_map(|game| game.all_rounds())
+        .map(|round| round.target_word().to_string())
+        .collect()
+}
+
+/// Gets the target words of a game read with only its target words, see ```recent_words```.
+fn projected_words(game: &Document) -> impl Iterator<Item = String> + '_ {
+    let rounds = game
+        .get_array("rounds")
+        .map(|rounds| rounds.as_slice())
+        .unwrap_or_default();
+    let current_round = game.get_document("current_round").ok();
+
+    rounds
+        .iter()
+        .filter_map(Bson::as_document)
+        .chain(current_round)
+        .filter_map(|round| round.get_str("target_word").ok())
+        .map(str::to_string)
+}
+
+/// Gets the target words of the ```window``` games the player with ```player_id``` most recently started.
+/// Only the target words of the games are read, not the whole games.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to query the player's games.
+async fn recent_words(
+    client: &MongoClient,
+    player_id: &str,
+    window: i64,
+) -> GraphqlServerResult<Vec<String>> {
+    let games: Collection<Document> = game_collection(client);
+
+    let filter = doc! {"$or": [{"p1_id": player_id}, {"p2_id": player_id}]};
+    let find_options = FindOptions::builder()
+        .sort(doc! {"created_at": -1})
+        .limit(window)
+        .projection(doc! {"_id": 0, "rounds.target_word": 1, "current_round.target_word": 1})
+        .build();
+
+    let find_error = || {
+        GraphqlServerError::new(
+            "Failed to execute find recent games query".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = games
+        .find(filter, find_options)
+        .await
+        .map_err(|_| find_error())?;
+
+    let mut recent = Vec::new();
+    while cursor.advance().await.map_err(|_| find_error())? {
+        let game: Document = cursor.deserialize_current().map_err(|_| find_error())?;
+        recent.extend(projected_words(&game));
+    }
+
+    Ok(recent)
+}
+
+/// Picks the target word of a new round of ```game```, or of a new game if there is no game yet,
+/// for the players with ```player_ids```.
+/// The word is from the word pack of ```config``` if it has one,
+/// otherwise from the dictionary of the language of ```config```,
+/// only picking from the tier with the difficulty of ```config``` if it has one.
+/// Avoids the words of the game's rounds, and of the recent games of either player (see ```RECENT_GAMES_WINDOW```).
+/// If every word has been used, any word can be picked.
+///
+/// # Errors
+///
+/// Will throw a 404 error if the dictionary or word pack has no words,
+/// or a 500 error if failed to query the games or words.
+pub async fn select_word(
+    client: &MongoClient,
+    game: Option<&Game>,
+    player_ids: [Option<&str>; 2],
+    config: &GameConfig,
+) -> GraphqlServerResult<String> {
+    let pool = match config.difficulty() {
+        Some(difficulty) => Some(
+            dictionary_tiers(client, config.language())
+                .await?
+                .words(difficulty),
+        ),
+        None => None,
+    };
+
+    let mut used = used_words(game);
+    for player_id in player_ids.into_iter().flatten() {
+        used.extend(recent_words(client, player_id, *RECENT_GAMES_WINDOW).await?);
+    }
+    let used: Vec<String> = used.into_iter().collect();
+
+    let picked = match config.word_pack() {
+        Some(word_pack) => match random_pack_word(client, word_pack, &used).await? {
+            Some(word) => Some(word),
+            // every word has been used recently, so repeating one can't be avoided
+            None => random_pack_word(client, word_pack, &[]).await?,
+        },
+        None => match random_word(client, config.language(), pool, &used).await? {
+            Some(word) => Some(word),
+            None => random_word(client, config.language(), pool, &[]).await?,
+        },
+    };
+
+    picked.ok_or_else(|| {
+        GraphqlServerError::new("there are no words to pick from".to_string(), &CODE404)
+    })
+}
+
+/// Picks the target word of a new round of ```game```, with the game's settings, see ```select_word```.
+///
+/// # Errors
+///
+/// Will throw a 404 error if the dictionary or word pack has no words,
+/// or a 500 error if failed to query the games or words.
+pub async fn select_round_word(client: &MongoClient, game: &Game) -> GraphqlServerResult<String> {
+    let player_ids = [game.player_id(Player::P1), game.player_id(Player::P2)];
+
+    select_word(client, Some(game), player_ids, game.config()).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{NewTurn, UpdateRound};
+
+    #[test]
+    fn used_words_include_every_round() {
+        let mut game = Game::new_game("words");
+        let update = UpdateRound::new(
+            &game.id(),
+            NewTurn::new("words", 0b11_11_11_11_11),
+            0,
+            Player::P2,
+        );
+        game.play_turn(update).unwrap();
+        game.start_round("crane").unwrap();
+
+        let other_game = Game::new_game("words");
+
+        let used = used_words([&game, &other_game]);
+        assert_eq!(used.into_iter().collect::<Vec<_>>(), vec!["crane", "words"]);
+    }
+
+    #[test]
+    fn projected_words_include_every_round() {
+        let game = doc! {
+            "rounds": [{"target_word": "words"}, {"target_word": "crane"}],
+            "current_round": {"target_word": "pious"},
+        };
+        let words: Vec<String> = projected_words(&game).collect();
+        assert_eq!(words, vec!["words", "crane", "pious"]);
+
+        // games saved before rounds were recorded only have their current round
+        let game = doc! {"current_round": {"target_word": "words"}};
+        assert_eq!(projected_words(&game).collect::<Vec<_>>(), vec!["words"]);
+    }
+}
@@ -0,0 +1,290 @@
+use juniper::{GraphQLEnum, GraphQLObject};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use super::{
+    encoding::{encode, rule_out_letters},
+    errors::{GraphqlServerError, GraphqlServerResult, CODE422},
+    letters::{letter_count, letter_states, letters},
+    models::{Game, GameStatus, Player, Turn},
+    packs::find_pack,
+    words::{all_words, Language},
+    MongoClient,
+};
+
+// This file contains the solver bots play with, which picks guesses from the words of a game
+// A word is a candidate if it gives the letter states of every turn so far when guessed against,
+// so only words that could still be the target word are guessed
+// The bot only picks its guess, it plays it with ```playTurn``` like any other player
+
+/// The max number of candidates compared with each other when maximizing entropy,
+/// as comparing every pair of a whole dictionary would take too long for a turn.
+pub const MAX_ENTROPY_WORDS: usize = 300;
+
+/// How well a bot picks its guesses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, GraphQLEnum, Serialize, Deserialize)]
+pub enum SolverStrength {
+    /// Guesses any word that could still be the target word.
+    RandomValid,
+    /// Guesses the word that could still be the target word with the most common letters.
+    LetterFrequency,
+    /// Guesses the word that could still be the target word, which is expected to rule out the most words.
+    MaxEntropy,
+}
+
+/// The next turn of a bot, ready to be played with ```playTurn```.
+#[derive(Debug, Clone, PartialEq, GraphQLObject)]
+pub struct BotTurn {
+    /// The word the bot guesses.
+    guess: String,
+
+    /// The encoded states of each letter of the guess.
+    letter_state: i32,
+
+    /// The encoded state of all the letters in the round once the guess is played,
+    /// ruling out the letters the guess shows aren't in the word (see ```encoding```).
+    letterpool_state: i32,
+}
+
+/// Checks if ```word``` could be the target word, giving the letter states of each of ```turns```.
+pub fn is_candidate(word: &str, turns: &[&Turn]) -> bool {
+    turns.iter().all(|turn| {
+        letter_count(turn.guessed_word()) == letter_count(word)
+            && encode(&letter_states(turn.guessed_word(), word)) == turn.letter_state()
+    })
+}
+
+/// Gets the words of ```words``` that are ```word_length``` letters long, haven't been guessed in ```turns```,
+/// and could still be the target word.
+pub fn candidates<'a>(words: &'a [String], word_length: usize, turns: &[&Turn]) -> Vec<&'a str> {
+    words
+        .iter()
+        .map(String::as_str)
+        .filter(|word| letter_count(word) == word_length)
+        .filter(|word| !turns.iter().any(|turn| turn.guessed_word() == *word))
+        .filter(|word| is_candidate(word, turns))
+        .collect()
+}
+
+/// Picks up to ```max``` words spread evenly through ```words```.
+fn spread<'a>(words: &[&'a str], max: usize) -> Vec<&'a str> {
+    let step = words.len().div_ceil(max).max(1);
+    words.iter().step_by(step).copied().collect()
+}
+
+/// Works out the entropy of the letter states of ```guess``` against each of ```answers```,
+/// which is higher the more evenly the letter states split the answers.
+fn entropy(guess: &str, answers: &[&str]) -> f64 {
+    let mut splits: HashMap<i32, usize> = HashMap::new();
+    for answer in answers {
+        *splits
+            .entry(encode(&letter_states(guess, answer)))
+            .or_default() += 1;
+    }
+
+    let total = answers.len() as f64;
+    splits
+        .values()
+        .map(|count| {
+            let chance = *count as f64 / total;
+            -chance * chance.log2()
+        })
+        .sum()
+}
+
+/// Scores ```word``` by the ```frequencies``` of its letters among the candidates, counting each letter once.
+fn letter_score(word: &str, frequencies: &HashMap<&str, usize>) -> usize {
+    let unique: HashSet<&str> = letters(word).into_iter().collect();
+    unique
+        .into_iter()
+        .map(|letter| frequencies.get(letter).copied().unwrap_or(0))
+        .sum()
+}
+
+/// Picks the guess a bot of ```strength``` makes from ```candidates```, see ```candidates```.
+/// ```seed``` picks the guess of a random valid bot, and picks between equally good guesses of the others.
+/// Returns None if there are no candidates.
+pub fn pick_guess(candidates: &[&str], strength: SolverStrength, seed: u64) -> Option<String> {
+    if candidates.is_empty() {
+        return None;
+    }
+    let seeded = |index: usize| (index + seed as usize) % candidates.len();
+
+    let picked = match strength {
+        SolverStrength::RandomValid => seeded(0),
+        SolverStrength::LetterFrequency => {
+            let mut frequencies: HashMap<&str, usize> = HashMap::new();
+            for candidate in candidates {
+                let unique: HashSet<&str> = letters(candidate).into_iter().collect();
+                for letter in unique {
+                    *frequencies.entry(letter).or_default() += 1;
+                }
+            }
+
+            (0..candidates.len())
+                .map(seeded)
+                .max_by_key(|i| letter_score(candidates[*i], &frequencies))
+                .unwrap_or(0)
+        }
+        SolverStrength::MaxEntropy => {
+            let sample = spread(candidates, MAX_ENTROPY_WORDS);
+
+            (0..candidates.len())
+                .map(seeded)
+                .filter(|i| sample.contains(&candidates[*i]))
+                .map(|i| (i, entropy(candidates[i], &sample)))
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map_or(0, |(i, _)| i)
+        }
+    };
+
+    Some(candidates[picked].to_string())
+}
+
+/// The English dictionary, read the first time a bot needs it.
+static ENGLISH_WORDS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// The Spanish dictionary, read the first time a bot needs it.
+static SPANISH_WORDS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// The German dictionary, read the first time a bot needs it.
+static GERMAN_WORDS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Gets the words of the dictionary of ```language```.
+/// The dictionary is only read the first time, as bots guess with it every turn.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to read the dictionary.
+async fn dictionary(
+    client: &MongoClient,
+    language: Language,
+) -> GraphqlServerResult<&'static [String]> {
+    let words = match language {
+        Language::English => &ENGLISH_WORDS,
+        Language::Spanish => &SPANISH_WORDS,
+        Language::German => &GERMAN_WORDS,
+    };
+    if let Some(words) = words.get() {
+        return Ok(words);
+    }
+
+    let dictionary = all_words(client, language).await?;
+
+    // another request may have read the dictionary meanwhile, which is fine as it is the same
+    Ok(words.get_or_init(|| dictionary))
+}
+
+/// Works out the next turn of the bot of ```game```, guessing with the words of the game's word pack,
+/// or the dictionary of the game's language.
+///
+/// # Errors
+///
+/// Will throw a 422 error if the game has no bot, the game or round has ended, it isn't the bot's turn,
+/// or no word can be guessed, a 404 error if the word pack no longer exists,
+/// or a 500 error if failed to query the words.
+pub async fn bot_turn(
+    client: &MongoClient,
+    game: &Game,
+    seed: u64,
+) -> GraphqlServerResult<BotTurn> {
+    let Some(strength) = game.config().bot() else {
+        return Err(GraphqlServerError::new(
+            "game isn't played against a bot".to_string(),
+            &CODE422,
+        ));
+    };
+
+    let round = game.current_round();
+    if game.status() != GameStatus::Active
+        || round.is_over()
+        || round.current_player() != Player::P2
+    {
+        return Err(GraphqlServerError::new(
+            "it isn't the bot's turn".to_string(),
+            &CODE422,
+        ));
+    }
+
+    let pack_words;
+    let words = match game.config().word_pack() {
+        Some(word_pack) => {
+            pack_words = find_pack(client, word_pack).await?;
+            pack_words.targets()
+        }
+        None => dictionary(client, game.config().language()).await?,
+    };
+
+    let target_word = round.target_word();
+    let turns: Vec<&Turn> = round.turns().iter().collect();
+    let candidates = candidates(words, letter_count(target_word), &turns);
+
+    let Some(guess) = pick_guess(&candidates, strength, seed) else {
+        return Err(GraphqlServerError::new(
+            "no word is left for the bot to guess".to_string(),
+            &CODE422,
+        ));
+    };
+
+    let states = letter_states(&guess, target_word);
+    Ok(BotTurn {
+        letter_state: encode(&states),
+        letterpool_state: rule_out_letters(round.letterpool_state(), &guess, &states),
+        guess,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{NewTurn, UpdateRound};
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn candidates_match_every_turn() {
+        let mut game = Game::new_game("words");
+        let update = UpdateRound::new(
+            &game.id(),
+            NewTurn::new("crane", 0b01_01_01_10_01),
+            0,
+            Player::P2,
+        );
+        game.play_turn(update).unwrap();
+
+        let words = words(&["crane", "words", "lords", "brick", "cats"]);
+        let turns: Vec<&Turn> = game.current_round().turns().iter().collect();
+
+        // "brick" has the "r" in the same spot as "crane", and "cats" is too short
+        assert_eq!(candidates(&words, 5, &turns), vec!["words", "lords"]);
+    }
+
+    #[test]
+    fn every_strength_guesses_a_candidate() {
+        let candidates = ["words", "lords", "cords", "bored"];
+
+        for strength in [
+            SolverStrength::RandomValid,
+            SolverStrength::LetterFrequency,
+            SolverStrength::MaxEntropy,
+        ] {
+            for seed in 0..4 {
+                let guess = pick_guess(&candidates, strength, seed).unwrap();
+                assert!(candidates.contains(&guess.as_str()));
+            }
+        }
+        assert_eq!(pick_guess(&[], SolverStrength::MaxEntropy, 0), None);
+    }
+
+    #[test]
+    fn entropy_prefers_guesses_that_split_candidates() {
+        // "abcd" tells every other word apart, while "axxx" can't tell the "x" words apart
+        let candidates = ["axxx", "bxxx", "cxxx", "abcd"];
+
+        let guess = pick_guess(&candidates, SolverStrength::MaxEntropy, 0).unwrap();
+        assert_eq!(guess, "abcd");
+    }
+}
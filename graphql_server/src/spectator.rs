@@ -0,0 +1,231 @@
+use bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::{self, Stream};
+use juniper::GraphQLObject;
+use rocket_db_pools::mongodb::{
+    bson::{doc, Document},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE500},
+    models::{Game, GameStatus},
+    store::find_game,
+    MongoClient, SPECTATOR_TTL,
+};
+
+// This file contains the spectators of games
+// A spectator is given a token by spectateGame, and is counted as watching until the token goes unused for the TTL
+
+/// How often a game being watched with ```watch_game``` is checked for changes.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// A spectator of a game.
+#[derive(Debug, GraphQLObject, Serialize, Deserialize)]
+pub struct Spectator {
+    /// The token of the spectator.
+    /// Send it in the ```Spectator-Token``` header to query the game as a spectator.
+    #[serde(rename = "_id")]
+    token: String,
+
+    /// The id of the game being spectated.
+    game_id: String,
+
+    /// When the spectator last queried the game.
+    /// The spectator is no longer counted once this is older than the TTL.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    seen_at: DateTime<Utc>,
+}
+
+/// Gets the collection of spectators.
+fn spectator_collection<T: Send + Sync>(client: &MongoClient) -> Collection<T> {
+    client.database("Games").collection("Spectators")
+}
+
+/// Gets the oldest time a spectator can have been seen at and still be watching.
+fn watching_since() -> DateTime<Utc> {
+    Utc::now() - Duration::seconds(*SPECTATOR_TTL as i64)
+}
+
+/// Creates the indexes used to count the spectators of a game,
+/// and to remove spectators once they are no longer watching.
+pub async fn create_indexes(client: &MongoClient) -> GraphqlServerResult<()> {
+    let spectators: Collection<Spectator> = spectator_collection(client);
+
+    let ttl_index = IndexModel::builder()
+        .keys(doc! {"seen_at": 1})
+        .options(
+            IndexOptions::builder()
+                .expire_after(std::time::Duration::from_secs(*SPECTATOR_TTL))
+                .build(),
+        )
+        .build();
+    let game_index = IndexModel::builder().keys(doc! {"game_id": 1}).build();
+
+    match spectators
+        .create_indexes([ttl_index, game_index], None)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to create spectator indexes".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Starts spectating the game with ```game_id```, which should exist.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to save the spectator.
+pub async fn spectate_game(client: &MongoClient, game_id: &str) -> GraphqlServerResult<Spectator> {
+    let spectators: Collection<Spectator> = spectator_collection(client);
+
+    let spectator = Spectator {
+        token: Uuid::new_v4().simple().to_string(),
+        game_id: game_id.to_string(),
+        seen_at: Utc::now(),
+    };
+
+    match spectators.insert_one(&spectator, None).await {
+        Ok(_) => Ok(spectator),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute insert spectator".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Records that the spectator with ```token``` is still watching the game with ```game_id```.
+/// Does nothing if the token isn't for the game.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to save the spectator.
+pub async fn keep_watching(
+    client: &MongoClient,
+    token: &str,
+    game_id: &str,
+) -> GraphqlServerResult<()> {
+    let spectators: Collection<Spectator> = spectator_collection(client);
+
+    let update = doc! {"$set": {"seen_at": Utc::now()}};
+    match spectators
+        .update_one(doc! {"_id": token, "game_id": game_id}, update, None)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute update spectator".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Counts the spectators currently watching each of ```games```, and sets the count on each game.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to count the spectators.
+pub async fn count_spectators(client: &MongoClient, games: &mut [Game]) -> GraphqlServerResult<()> {
+    if games.is_empty() {
+        return Ok(());
+    }
+
+    let spectators: Collection<Document> = spectator_collection(client);
+    let game_ids: Vec<String> = games.iter().map(|game| game.id()).collect();
+
+    let pipeline = [
+        // the TTL index only removes spectators about once a minute, so check the time as well
+        doc! {"$match": {"game_id": {"$in": game_ids}, "seen_at": {"$gte": watching_since()}}},
+        doc! {"$group": {"_id": "$game_id", "count": {"$sum": 1}}},
+    ];
+
+    let count_error = || {
+        GraphqlServerError::new(
+            "Failed to execute count spectators query".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = spectators
+        .aggregate(pipeline, None)
+        .await
+        .map_err(|_| count_error())?;
+
+    while cursor.advance().await.map_err(|_| count_error())? {
+        let document = cursor.deserialize_current().map_err(|_| count_error())?;
+        let (Ok(game_id), Ok(count)) = (document.get_str("_id"), document.get_i32("count")) else {
+            return Err(count_error());
+        };
+
+        if let Some(game) = games.iter_mut().find(|game| game.id() == game_id) {
+            game.set_spectator_count(count);
+        }
+    }
+
+    Ok(())
+}
+
+/// Streams the game with ```game_id``` every time it is changed, starting with the game as it is now.
+/// A spectator watching with ```spectator_token``` is counted as watching for as long as the stream is open.
+/// The stream ends once the game has finished.
+///
+/// # Errors
+///
+/// Will throw a 404 error if the game doesn't exist.
+/// The stream ends after an error finding the game.
+pub async fn watch_game(
+    client: MongoClient,
+    game_id: String,
+    spectator_token: Option<String>,
+) -> GraphqlServerResult<impl Stream<Item = GraphqlServerResult<Game>> + Send> {
+    let game_id = Game::parse_id(&game_id)?;
+    find_game(&client, &game_id).await?;
+
+    // the version of the game last sent, which is None after the game finished or an error, ending the stream
+    let state = (client, game_id, spectator_token, Some(None));
+
+    Ok(stream::unfold(
+        state,
+        |(client, game_id, spectator_token, sent_version)| async move {
+            let sent_version: Option<i32> = sent_version?;
+
+            let (item, next_version) = loop {
+                if sent_version.is_some() {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+
+                if let Some(token) = &spectator_token {
+                    if let Err(e) = keep_watching(&client, token, &game_id).await {
+                        break (Err(e), None);
+                    }
+                }
+
+                let mut game = match find_game(&client, &game_id).await {
+                    Ok(game) => game,
+                    Err(e) => break (Err(e), None),
+                };
+                if sent_version == Some(game.version()) {
+                    continue;
+                }
+
+                if let Err(e) = count_spectators(&client, std::slice::from_mut(&mut game)).await {
+                    break (Err(e), None);
+                }
+
+                let next_version = match game.status() {
+                    GameStatus::Finished => None,
+                    _ => Some(Some(game.version())),
+                };
+                break (Ok(game), next_version);
+            };
+
+            Some((item, (client, game_id, spectator_token, next_version)))
+        },
+    ))
+}
@@ -0,0 +1,262 @@
+use juniper::GraphQLObject;
+use rocket_db_pools::mongodb::{
+    bson::{doc, to_bson},
+    options::FindOptions,
+    Collection,
+};
+use std::collections::HashMap;
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE500},
+    game_collection,
+    models::{Game, GameMode, GameStatus, MAX_GUESSES},
+    rating::Outcome,
+    MongoClient,
+};
+
+// This file contains the statistics of a player, worked out from the games they finished
+
+/// The max number of starting words in a player's statistics.
+pub const MAX_STARTING_WORDS: usize = 5;
+
+/// The number of rounds solved in some number of guesses.
+#[derive(Debug, PartialEq, GraphQLObject)]
+pub struct GuessCount {
+    /// The number of guesses made in the round, by both players.
+    guesses: i32,
+
+    /// The number of rounds the player solved in that many guesses.
+    rounds: i32,
+}
+
+/// A word a player started rounds with.
+#[derive(Debug, PartialEq, GraphQLObject)]
+pub struct StartingWord {
+    /// The word guessed.
+    word: String,
+
+    /// The number of rounds the player started with the word.
+    uses: i32,
+}
+
+/// The statistics of a player over the games they finished.
+#[derive(Debug, GraphQLObject)]
+pub struct PlayerStats {
+    /// The id of the player's profile.
+    player_id: String,
+
+    /// The number of games the player finished.
+    games_played: i32,
+
+    /// The number of those games the player won.
+    wins: i32,
+
+    /// The number of those games that ended in a draw.
+    draws: i32,
+
+    /// The number of those games the player lost.
+    losses: i32,
+
+    /// The share of games the player won, between 0 and 1.
+    /// Is 0 if the player hasn't finished any games.
+    win_rate: f64,
+
+    /// How many rounds the player solved in each number of guesses, from 1 to the max guesses.
+    guess_distribution: Vec<GuessCount>,
+
+    /// The number of games the player won in a row, up to their latest game.
+    current_streak: i32,
+
+    /// The most games the player has won in a row.
+    best_streak: i32,
+
+    /// The words the player most often made their first guess of a round with, most used first.
+    starting_words: Vec<StartingWord>,
+}
+
+impl PlayerStats {
+    /// Works out the statistics of the player with ```player_id``` from ```games```.
+    /// ```games``` should be in the order they finished, games the player didn't play in are ignored.
+    pub fn from_games(player_id: &str, games: &[Game]) -> Self {
+        let mut stats = PlayerStats {
+            player_id: player_id.to_string(),
+            games_played: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            win_rate: 0.0,
+            guess_distribution: (1..=MAX_GUESSES)
+                .map(|guesses| GuessCount { guesses, rounds: 0 })
+                .collect(),
+            current_streak: 0,
+            best_streak: 0,
+            starting_words: Vec::new(),
+        };
+        let mut word_uses: HashMap<&str, i32> = HashMap::new();
+
+        for game in games {
+            let Some(player) = game.player_of(player_id) else {
+                continue;
+            };
+
+            stats.games_played += 1;
+            match game.outcome(player) {
+                Outcome::Win => {
+                    stats.wins += 1;
+                    stats.current_streak += 1;
+                    stats.best_streak = stats.best_streak.max(stats.current_streak);
+                }
+                Outcome::Draw => {
+                    stats.draws += 1;
+                    stats.current_streak = 0;
+                }
+                Outcome::Loss => {
+                    stats.losses += 1;
+                    stats.current_streak = 0;
+                }
+            }
+
+            for round in game.all_rounds() {
+                if round.solved_by() == Some(player) {
+                    // in a race, only the player's own guesses are on their board
+                    let guesses = match round.board(player) {
+                        Some(board) => board.guess_num() as usize,
+                        None => round.turns().len(),
+                    };
+                    if let Some(count) = stats.guess_distribution.get_mut(guesses - 1) {
+                        count.rounds += 1;
+                    }
+                }
+
+                let first_guess = round.turns().iter().find(|turn| turn.player() == player);
+                if let Some(turn) = first_guess {
+                    *word_uses.entry(turn.guessed_word()).or_default() += 1;
+                }
+            }
+        }
+
+        if stats.games_played > 0 {
+            stats.win_rate = stats.wins as f64 / stats.games_played as f64;
+        }
+
+        let mut starting_words: Vec<StartingWord> = word_uses
+            .into_iter()
+            .map(|(word, uses)| StartingWord {
+                word: word.to_string(),
+                uses,
+            })
+            .collect();
+        // ties are broken by the word, so the order doesn't change between queries
+        starting_words.sort_by(|a, b| b.uses.cmp(&a.uses).then_with(|| a.word.cmp(&b.word)));
+        starting_words.truncate(MAX_STARTING_WORDS);
+        stats.starting_words = starting_words;
+
+        stats
+    }
+}
+
+/// Gets the statistics of the player with ```player_id```.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to query the player's games.
+pub async fn player_stats(
+    client: &MongoClient,
+    player_id: &str,
+) -> GraphqlServerResult<PlayerStats> {
+    let games: Collection<Game> = game_collection(client);
+
+    let finished = to_bson(&GameStatus::Finished).expect("status should serialize");
+    // co-op games aren't won or lost, so they don't count towards a player's record
+    let co_op = to_bson(&GameMode::CoOp).expect("mode should serialize");
+    let filter = doc! {
+        "status": finished,
+        "config.mode": {"$ne": co_op},
+        "$or": [{"p1_id": player_id}, {"p2_id": player_id}],
+    };
+    let find_options = FindOptions::builder()
+        .sort(doc! {"finished_at": 1, "_id": 1})
+        .build();
+
+    let find_error = || {
+        GraphqlServerError::new(
+            "Failed to execute find player games query".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = games
+        .find(filter, find_options)
+        .await
+        .map_err(|_| find_error())?;
+
+    let mut player_games = Vec::new();
+    while cursor.advance().await.map_err(|_| find_error())? {
+        player_games.push(cursor.deserialize_current().map_err(|_| find_error())?);
+    }
+
+    Ok(PlayerStats::from_games(player_id, &player_games))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{NewTurn, Player, UpdateRound};
+
+    const SOLVED: i32 = 0b11_11_11_11_11;
+    const MISSED: i32 = 0b01_01_01_01_01;
+
+    /// Plays a finished game where ```p1``` and ```p2``` take turns guessing ```guesses```.
+    fn finished_game(p1: &str, p2: &str, guesses: &[(&str, i32)]) -> Game {
+        let mut game = Game::new_game("words");
+        game.set_players(Some(p1.to_string()), Some(p2.to_string()))
+            .unwrap();
+
+        // player 1 starts, so player 2 plays the turns after even turns
+        for (i, (guess, letter_state)) in guesses.iter().enumerate() {
+            let next_player = if i % 2 == 0 { Player::P2 } else { Player::P1 };
+            let turn = NewTurn::new(guess, *letter_state);
+            game.play_turn(UpdateRound::new(&game.id(), turn, 0, next_player))
+                .unwrap();
+        }
+
+        game.finish();
+        game
+    }
+
+    #[test]
+    fn counts_results_and_streaks() {
+        let games = [
+            finished_game("me", "them", &[("words", SOLVED)]),
+            finished_game("them", "me", &[("crane", MISSED), ("words", SOLVED)]),
+            finished_game("me", "them", &[("crane", MISSED), ("words", SOLVED)]),
+            finished_game("me", "them", &[("words", SOLVED)]),
+            finished_game("other", "them", &[("words", SOLVED)]),
+        ];
+
+        let stats = PlayerStats::from_games("me", &games);
+
+        assert_eq!(stats.games_played, 4);
+        assert_eq!((stats.wins, stats.draws, stats.losses), (3, 0, 1));
+        assert_eq!(stats.win_rate, 0.75);
+        assert_eq!(stats.best_streak, 2);
+        assert_eq!(stats.current_streak, 1);
+    }
+
+    #[test]
+    fn distributes_solves_by_guesses() {
+        let games = [
+            finished_game("me", "them", &[("words", SOLVED)]),
+            finished_game("them", "me", &[("crane", MISSED), ("words", SOLVED)]),
+            finished_game("me", "them", &[("crane", MISSED), ("words", SOLVED)]),
+        ];
+
+        let stats = PlayerStats::from_games("me", &games);
+
+        assert_eq!(stats.guess_distribution.len(), MAX_GUESSES as usize);
+        assert_eq!(stats.guess_distribution[0].rounds, 1);
+        assert_eq!(stats.guess_distribution[1].rounds, 1);
+        assert_eq!(stats.starting_words[0].word, "words");
+        assert_eq!(stats.starting_words[0].uses, 2);
+    }
+}
@@ -0,0 +1,129 @@
+use rocket_db_pools::mongodb::{
+    bson::{doc, DateTime, Document},
+    Collection,
+};
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE404, CODE409, CODE500},
+    game_collection,
+    models::{Game, PlayerProfile},
+    player_collection,
+    MongoClient,
+};
+
+// This file contains the reads and writes of games and players to the database
+// Writes use the version of a game to make sure they don't overwrite a change they haven't seen
+
+/// Finds the game with ```game_id```.
+///
+/// # Errors
+///
+/// Will throw a 404 error if there is no game with the id,
+/// or a 500 error if failed to execute the query.
+pub async fn find_game(client: &MongoClient, game_id: &str) -> GraphqlServerResult<Game> {
+    let games: Collection<Game> = game_collection(client);
+
+    match games.find_one(doc! {"_id": game_id}, None).await {
+        Ok(Some(game)) => Ok(game),
+        Ok(None) => Err(GraphqlServerError::new(
+            "No game with id found".to_string(),
+            &CODE404,
+        )),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute find game query".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Saves changes made to ```game```, which should have been found with ```find_game```.
+///
+/// The save only happens if the stored game is still on the version ```game``` was found with,
+/// then the version is incremented.
+///
+/// # Errors
+///
+/// Will throw a 409 error if the game was changed since it was found.
+/// This error can be retried by finding the game again and redoing the changes.
+/// Will throw a 404 error if the game no longer exists,
+/// or a 500 error if failed to execute the save.
+pub async fn save_game(client: &MongoClient, game: &mut Game) -> GraphqlServerResult<()> {
+    let games: Collection<Game> = game_collection(client);
+
+    let found_version = game.version();
+    game.touch();
+
+    // games saved before versions were added have no version, which a query for version 0 doesn't match
+    let save_query = if found_version == 0 {
+        doc! {
+            "_id": game.id(),
+            "$or": [{"version": 0}, {"version": {"$exists": false}}],
+        }
+    } else {
+        doc! {"_id": game.id(), "version": found_version}
+    };
+    let replace_result = games.replace_one(save_query, &*game, None).await;
+
+    match replace_result {
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute replace".to_string(),
+            &CODE500,
+        )),
+        Ok(update_result) if update_result.matched_count == 1 => Ok(()),
+        // nothing matched, so either the version changed or the game is gone
+        Ok(_) => {
+            find_game(client, &game.id()).await?;
+
+            Err(GraphqlServerError::new(
+                "Game was changed by another request, get the game again and retry".to_string(),
+                &CODE409,
+            ))
+        }
+    }
+}
+
+/// Finds the player with ```player_id```.
+///
+/// # Errors
+///
+/// Will throw a 404 error if there is no player with the id,
+/// or a 500 error if failed to execute the query.
+pub async fn find_player(client: &MongoClient, player_id: &str) -> GraphqlServerResult<PlayerProfile> {
+    let players: Collection<PlayerProfile> = player_collection(client);
+
+    match players.find_one(doc! {"_id": player_id}, None).await {
+        Ok(Some(player)) => Ok(player),
+        Ok(None) => Err(GraphqlServerError::new(
+            "No player with id found".to_string(),
+            &CODE404,
+        )),
+        Err(_) => Err(GraphqlServerError::new(
+            "Failed to execute find player query".to_string(),
+            &CODE500,
+        )),
+    }
+}
+
+/// Sets the ```created_at``` and ```updated_at``` of games saved before they were recorded to the unix epoch,
+/// which is what they are read as, so the games are found when filtering and paginating by when they were created.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to update the games.
+pub async fn backfill_timestamps(client: &MongoClient) -> GraphqlServerResult<()> {
+    let games: Collection<Document> = game_collection(client);
+
+    for field in ["created_at", "updated_at"] {
+        let filter = doc! {field: {"$exists": false}};
+        let update = doc! {"$set": {field: DateTime::from_millis(0)}};
+
+        if games.update_many(filter, update, None).await.is_err() {
+            return Err(GraphqlServerError::new(
+                "Failed to backfill timestamps of games".to_string(),
+                &CODE500,
+            ));
+        }
+    }
+
+    Ok(())
+}
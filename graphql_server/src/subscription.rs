@@ -0,0 +1,69 @@
+use super::{
+    chat::{watch_messages, ChatMessage},
+    context::{Context, Role},
+    models::Game,
+    spectator::watch_game,
+    MongoClient,
+};
+use futures::{Stream, StreamExt};
+use juniper::{graphql_subscription, FieldError, FieldResult, IntoFieldError};
+use std::pin::Pin;
+
+/// A stream of chat messages
+type MessageStream = Pin<Box<dyn Stream<Item = Result<ChatMessage, FieldError>> + Send>>;
+
+/// A stream of the changes to a game
+type GameStream = Pin<Box<dyn Stream<Item = Result<Game, FieldError>> + Send>>;
+
+/// Root Subscription node
+pub struct Subscription;
+
+#[graphql_subscription(context = Context)]
+impl Subscription {
+    /// Streams the chat messages of a game as they are sent.
+    /// Starts after the message with id ```after``` if given, otherwise with the next message sent.
+    /// Spectators can subscribe as well as players.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 404 error if there is no game with the id,
+    /// or ```after``` isn't a message of the game.
+    async fn message_sent(
+        context: &Context,
+        game_id: String,
+        after: Option<String>,
+    ) -> FieldResult<MessageStream> {
+        // the stream outlives the request, so it needs its own connection
+        let client = MongoClient(context.0.clone());
+        let messages = watch_messages(client, game_id, after).await?;
+
+        Ok(Box::pin(
+            messages.map(|message| message.map_err(|e| e.into_field_error())),
+        ))
+    }
+
+    /// Streams a game every time it is changed, starting with the game as it is now, until the game finishes.
+    /// Spectators are counted as watching the game for as long as they are subscribed.
+    ///
+    /// # Errors
+    ///
+    /// Will throw a 404 error if there is no game with the id.
+    async fn game_updated(context: &Context, game_id: String) -> FieldResult<GameStream> {
+        let spectator_token = match context.role() {
+            Role::Spectator(token) => Some(token.clone()),
+            _ => None,
+        };
+
+        // the stream outlives the request, so it needs its own connection
+        let client = MongoClient(context.0.clone());
+        let games = watch_game(client, game_id, spectator_token).await?;
+
+        // the answer of a daily challenge is hidden from everyone but its players until the day is over
+        let role = context.role().clone();
+        Ok(Box::pin(games.map(move |game| {
+            let mut game = game.map_err(|e| e.into_field_error())?;
+            role.hide_daily_answer(&mut game);
+            Ok(game)
+        })))
+    }
+}
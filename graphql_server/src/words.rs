@@ -0,0 +1,237 @@
+use juniper::GraphQLEnum;
+use rocket_db_pools::mongodb::{
+    bson::{doc, from_document, Document},
+    options::FindOptions,
+    Collection,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    errors::{GraphqlServerError, GraphqlServerResult, CODE404, CODE422, CODE500},
+    letters::{letters, normalize},
+    MongoClient,
+};
+
+// This file contains the dictionaries of words, one for each language
+// The words are in the Words database, the same one the word server reads
+// The English dictionary is the one the word server fills, the others have to be loaded into their collections
+// Games can't be played in a language until its dictionary has words
+
+/// The language of a dictionary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, GraphQLEnum, Serialize, Deserialize)]
+pub enum Language {
+    /// English words.
+    #[default]
+    English,
+    /// Spanish words, which can have accents and "ñ".
+    Spanish,
+    /// German words, which can have umlauts and "ß".
+    German,
+}
+
+impl Language {
+    /// Gets the name of the collection the dictionary of the language is in.
+    fn collection_name(&self) -> &'static str {
+        match self {
+            Language::English => "FiveLetters",
+            Language::Spanish => "FiveLettersSpanish",
+            Language::German => "FiveLettersGerman",
+        }
+    }
+
+    /// Gets the letters words of the language are written with, normalized (see ```letters```).
+    pub fn alphabet(&self) -> Vec<&'static str> {
+        let extra_letters: &[&str] = match self {
+            Language::English => &[],
+            Language::Spanish => &["á", "é", "í", "ñ", "ó", "ú", "ü"],
+            Language::German => &["ä", "ö", "ß", "ü"],
+        };
+
+        letters("abcdefghijklmnopqrstuvwxyz")
+            .into_iter()
+            .chain(extra_letters.iter().copied())
+            .collect()
+    }
+}
+
+/// A word of the dictionary.
+#[derive(Debug, Deserialize)]
+struct Word {
+    word: String,
+}
+
+/// Gets the collection of five letter words in ```language``` from the Words database.
+fn word_collection<T: Send + Sync>(client: &MongoClient, language: Language) -> Collection<T> {
+    client
+        .database("Words")
+        .collection(language.collection_name())
+}
+
+/// Counts the words in the dictionary of ```language```.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to execute the query.
+pub async fn word_count(client: &MongoClient, language: Language) -> GraphqlServerResult<u64> {
+    let words: Collection<Document> = word_collection(client, language);
+
+    words.count_documents(doc! {}, None).await.map_err(|_| {
+        GraphqlServerError::new("Failed to execute count words query".to_string(), &CODE500)
+    })
+}
+
+/// Checks that the dictionary of ```language``` has words, so games can be played in the language.
+///
+/// # Errors
+///
+/// Will throw a 422 error if the dictionary has no words,
+/// or a 500 error if failed to execute the query.
+pub async fn check_dictionary(client: &MongoClient, language: Language) -> GraphqlServerResult<()> {
+    if word_count(client, language).await? == 0 {
+        return Err(GraphqlServerError::new(
+            format!("there is no dictionary for {:?} yet", language),
+            &CODE422,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that ```word``` is in the dictionary of ```language```.
+///
+/// # Errors
+///
+/// Will throw a 422 error if the word isn't in the dictionary,
+/// or a 500 error if failed to execute the query.
+pub async fn check_word(
+    client: &MongoClient,
+    language: Language,
+    word: &str,
+) -> GraphqlServerResult<()> {
+    let words: Collection<Document> = word_collection(client, language);
+
+    let count = words
+        .count_documents(doc! {"word": normalize(word)}, None)
+        .await
+        .map_err(|_| {
+            GraphqlServerError::new("Failed to execute count words query".to_string(), &CODE500)
+        })?;
+
+    if count == 0 {
+        return Err(GraphqlServerError::new(
+            format!("{} isn't in the dictionary", word),
+            &CODE422,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Gets the word at ```index``` of the dictionary of ```language```, when the words are in alphabetical order.
+///
+/// # Errors
+///
+/// Will throw a 404 error if there are not that many words,
+/// or a 500 error if failed to execute the query.
+pub async fn word_at(
+    client: &MongoClient,
+    language: Language,
+    index: u64,
+) -> GraphqlServerResult<String> {
+    let words: Collection<Word> = word_collection(client, language);
+
+    let options = FindOptions::builder()
+        .sort(doc! {"word": 1})
+        .skip(index)
+        .limit(1)
+        .build();
+
+    let find_error =
+        || GraphqlServerError::new("Failed to execute find word query".to_string(), &CODE500);
+
+    let mut cursor = words
+        .find(doc! {}, options)
+        .await
+        .map_err(|_| find_error())?;
+
+    if !cursor.advance().await.map_err(|_| find_error())? {
+        return Err(GraphqlServerError::new(
+            "No word at index in the dictionary".to_string(),
+            &CODE404,
+        ));
+    }
+
+    let word: Word = cursor.deserialize_current().map_err(|_| find_error())?;
+    Ok(normalize(&word.word))
+}
+
+/// Gets every word of the dictionary of ```language```.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to execute the query.
+pub async fn all_words(
+    client: &MongoClient,
+    language: Language,
+) -> GraphqlServerResult<Vec<String>> {
+    let words: Collection<Word> = word_collection(client, language);
+
+    let find_error =
+        || GraphqlServerError::new("Failed to execute find words query".to_string(), &CODE500);
+
+    let mut cursor = words.find(doc! {}, None).await.map_err(|_| find_error())?;
+
+    let mut all = Vec::new();
+    while cursor.advance().await.map_err(|_| find_error())? {
+        let word: Word = cursor.deserialize_current().map_err(|_| find_error())?;
+        all.push(normalize(&word.word));
+    }
+
+    Ok(all)
+}
+
+/// Gets a random word of the dictionary of ```language``` that isn't in ```excluded```.
+/// If ```pool``` is given, the word is also one of the words in it.
+/// Returns None if every word is excluded.
+///
+/// # Errors
+///
+/// Will throw a 500 error if failed to execute the aggregation.
+pub async fn random_word(
+    client: &MongoClient,
+    language: Language,
+    pool: Option<&[String]>,
+    excluded: &[String],
+) -> GraphqlServerResult<Option<String>> {
+    let words: Collection<Document> = word_collection(client, language);
+
+    let mut filter = doc! {"$nin": excluded};
+    if let Some(pool) = pool {
+        filter.insert("$in", pool);
+    }
+
+    let pipeline = [
+        doc! {"$match": {"word": filter}},
+        doc! {"$sample": {"size": 1}},
+    ];
+
+    let sample_error = || {
+        GraphqlServerError::new(
+            "Failed to execute random word aggregation".to_string(),
+            &CODE500,
+        )
+    };
+
+    let mut cursor = words
+        .aggregate(pipeline, None)
+        .await
+        .map_err(|_| sample_error())?;
+
+    if !cursor.advance().await.map_err(|_| sample_error())? {
+        return Ok(None);
+    }
+
+    let document = cursor.deserialize_current().map_err(|_| sample_error())?;
+    let word: Word = from_document(document).map_err(|_| sample_error())?;
+    Ok(Some(normalize(&word.word)))
+}
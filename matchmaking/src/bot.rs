@@ -0,0 +1,262 @@
+use super::join_game::Player;
+use super::GRAPHQL_PORT;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// How long the bot waits before each turn, so players can follow its guesses.
+const BOT_THINK_TIME: Duration = Duration::from_secs(2);
+
+/// How long the bot keeps waiting for its turn before it stops playing,
+/// e.g. because the player left without ending the game.
+const BOT_MAX_IDLE: Duration = Duration::from_secs(30 * 60);
+
+/// The header the graphql server expects the token of the player making a change in.
+const PLAYER_TOKEN_HEADER: &str = "Player-Token";
+
+/// How well a bot guesses, the same strengths as the solver of the graphql server.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, FromFormField)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BotStrength {
+    /// Guesses any word that could still be the target word.
+    #[field(value = "random_valid")]
+    RandomValid,
+    /// Guesses the word that could still be the target word with the most common letters.
+    #[field(value = "letter_frequency")]
+    LetterFrequency,
+    /// Guesses the word expected to rule out the most words.
+    #[field(value = "max_entropy")]
+    MaxEntropy,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GraphqlQuery<V> {
+    query: String,
+    variables: V,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GameVariables {
+    id: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct BotRound {
+    currentPlayer: Player,
+    endedAt: Option<String>,
+    guessNum: i32,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct BotGame {
+    status: String,
+    roundNum: i32,
+    currentRound: BotRound,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GameData {
+    game: BotGame,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GameResponse {
+    data: GameData,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct BotTurnVariables {
+    gameId: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct BotTurn {
+    guess: String,
+    letterState: i32,
+    letterpoolState: i32,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct BotTurnData {
+    botTurn: BotTurn,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BotTurnResponse {
+    data: BotTurnData,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct NewTurn {
+    guess: String,
+    letterState: i32,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct UpdateRound {
+    gameId: String,
+    turn: NewTurn,
+    letterpool: i32,
+    nextPlayer: Player,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayTurnVariables {
+    update: UpdateRound,
+    clientMutationId: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayTurnStatus {
+    status: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayTurnData {
+    playTurn: PlayTurnStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayTurnResponse {
+    data: PlayTurnData,
+}
+
+/// Plays the turns of the bot, player 2 of the game with ```game_id```, until the game ends.
+/// The bot gets its guess from the graphql server's solver, then plays it with ```playTurn``` like any player.
+/// Stops if the bot has waited ```BOT_MAX_IDLE``` for its turn.
+///
+/// ```player_token``` is the token of player 2, which the bot sends to play its turns.
+pub async fn play_bot(game_id: String, player_token: String) {
+    let client = reqwest::Client::new();
+    let url: String = format!("http://localhost:{}/graphql", *GRAPHQL_PORT);
+
+    let mut idle = Duration::ZERO;
+    while idle < BOT_MAX_IDLE {
+        sleep(BOT_THINK_TIME).await;
+        idle += BOT_THINK_TIME;
+
+        let Some(game) = get_game(&client, &url, &game_id).await else {
+            continue;
+        };
+        if game.status == "FINISHED" {
+            return;
+        }
+
+        // the player starts the next round, and the bot waits for its turn
+        let round = &game.currentRound;
+        if round.endedAt.is_some() || round.currentPlayer != Player::P2 {
+            continue;
+        }
+
+        if play_turn(&client, &url, &game_id, &player_token, &game).await {
+            idle = Duration::ZERO;
+        }
+    }
+}
+
+/// Gets the state of the game with ```game_id``` the bot needs to play.
+/// If there was an error with communications with the graphql server, then this returns None.
+async fn get_game(client: &reqwest::Client, url: &str, game_id: &str) -> Option<BotGame> {
+    let data = GraphqlQuery {
+        query: String::from(
+            "query($id: String!){game(id: $id){status roundNum currentRound{currentPlayer endedAt guessNum}}}",
+        ),
+        variables: GameVariables {
+            id: game_id.to_string(),
+        },
+    };
+
+    let res = client.post(url).json(&data).send().await.ok()?;
+    // fails to deserialize if graphql server responded with an error
+    let game_response = res.json::<GameResponse>().await.ok()?;
+
+    Some(game_response.data.game)
+}
+
+/// Plays the bot's next turn in ```game```, with the letter pool the solver worked out for the guess.
+/// The turn is played with an idempotency key for its spot in the game, so it is never played twice.
+/// Returns false if the turn couldn't be played, e.g. the player ended the game first.
+async fn play_turn(
+    client: &reqwest::Client,
+    url: &str,
+    game_id: &str,
+    player_token: &str,
+    game: &BotGame,
+) -> bool {
+    let data = GraphqlQuery {
+        query: String::from(
+            "query($gameId: String!){botTurn(gameId: $gameId){guess letterState letterpoolState}}",
+        ),
+        variables: BotTurnVariables {
+            gameId: game_id.to_string(),
+        },
+    };
+
+    let Ok(res) = client.post(url).json(&data).send().await else {
+        return false;
+    };
+    let Ok(bot_turn) = res.json::<BotTurnResponse>().await else {
+        return false;
+    };
+    let bot_turn = bot_turn.data.botTurn;
+
+    let round = &game.currentRound;
+    let data = GraphqlQuery {
+        query: String::from(
+            "mutation($update: UpdateRound!, $clientMutationId: String){playTurn(update: $update, clientMutationId: $clientMutationId){status}}",
+        ),
+        variables: PlayTurnVariables {
+            update: UpdateRound {
+                gameId: game_id.to_string(),
+                turn: NewTurn {
+                    guess: bot_turn.guess,
+                    letterState: bot_turn.letterState,
+                },
+                letterpool: bot_turn.letterpoolState,
+                nextPlayer: Player::P1,
+            },
+            clientMutationId: turn_key(game_id, game.roundNum, round.guessNum),
+        },
+    };
+
+    let res = client
+        .post(url)
+        .header(PLAYER_TOKEN_HEADER, player_token)
+        .json(&data)
+        .send()
+        .await;
+    match res {
+        Ok(res) => res.json::<PlayTurnResponse>().await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Gets the idempotency key of the bot's turn at ```guess_num``` of round ```round_num``` of a game.
+fn turn_key(game_id: &str, round_num: i32, guess_num: i32) -> String {
+    format!("bot-{}-{}-{}", game_id, round_num, guess_num)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strength_is_sent_as_graphql_enum() {
+        let strength = rocket::serde::json::to_string(&BotStrength::MaxEntropy).unwrap();
+        assert_eq!(strength, "\"MAX_ENTROPY\"");
+    }
+
+    #[test]
+    fn each_turn_has_its_own_key() {
+        assert_ne!(turn_key("game", 0, 1), turn_key("game", 1, 0));
+        assert_eq!(turn_key("game", 2, 3), "bot-game-2-3");
+    }
+}
@@ -1,15 +1,24 @@
-use super::{GRAPHQL_PORT, WORD_PORT};
-use crossbeam_channel;
-use crossbeam_channel::{Receiver, Sender};
+use super::bot::{play_bot, BotStrength};
+use super::queue::{Joined, MatchmakingQueue, Opponent, DEFAULT_RATING};
+use super::GRAPHQL_PORT;
+use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::State;
-use std::sync::Arc;
-use tokio::sync::{Barrier, Semaphore};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// How often a waiting player checks again for an opponent, as their rating window grows.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a player who asked for a bot waits for another player, before playing the bot.
+const BOT_WAIT: Duration = Duration::from_secs(10);
 
 /// Player1 or Player2 label
-#[derive(Debug, Serialize, Deserialize, Clone)]
-enum Player {
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Player {
+    /// The player who starts the game.
     P1,
+    /// The other player, who is the bot in a game against a bot.
     P2,
 }
 
@@ -18,76 +27,210 @@ enum Player {
 pub struct JoinGameResponse {
     game_id: Option<String>,
     player_type: Player,
+    /// The token the player sends in the ```Player-Token``` header to play their turns.
+    player_token: Option<String>,
 }
 
 /// Endpoint used by clients when trying to join a new game
 /// If game_id returned is None, then an error occurred with connecting to
 /// the graphql server.
-#[get("/join_game")]
+///
+/// player_id is the id of the player's profile in the graphql server, if they have one,
+/// and player_secret is the secret of the profile, needed for the profile to be added to the game.
+/// word_pack is the name of the word pack the player wants to play with, if any.
+/// bot is the strength of the bot to play if no one else is looking for a game,
+/// one of random_valid, letter_frequency or max_entropy.
+/// Players are paired with players of a similar rating who want the same word pack,
+/// though the longer a player waits the wider the range of ratings they can be paired with.
+/// A player who asked for a bot plays it as P1 once they have waited ```BOT_WAIT``` without being paired.
+/// Responds with 404 Not Found if there is no word pack named word_pack, as no game could be created with it.
+#[get("/join_game?<player_id>&<player_secret>&<word_pack>&<bot>")]
 pub async fn join_game(
-    barrier: &State<Arc<Barrier>>,
-    sem: &State<Arc<Semaphore>>,
-    sender: &State<Arc<Sender<Option<String>>>>,
-    receiver: &State<Arc<Receiver<Option<String>>>>,
-) -> Json<JoinGameResponse> {
-    // use barrier as our "queue"
-    // it will let us pair up 2 clients
-
-    // leader calls create_game, sends game_id and is P1
-    // other recv game_id and is P2
-    let barrier_result = barrier.wait().await;
-
-    // use semaphore to only allow a single pair of threads to use a crossbeam_channel at once
-    let sem_permit = sem
-        .acquire()
-        .await
-        .expect("Join Game semaphore should be open.");
-
-    let json = match barrier_result.is_leader() {
-        true => {
-            let game_id = create_game().await;
-
-            println!("{:?}", game_id);
-
-            sender
-                .send(game_id.clone())
-                .expect("game_id sender should work");
-
-            let player_type = Player::P1;
-
-            Json(JoinGameResponse {
-                game_id,
-                player_type,
-            })
+    player_id: Option<String>,
+    player_secret: Option<String>,
+    word_pack: Option<String>,
+    bot: Option<BotStrength>,
+    queue: &State<MatchmakingQueue>,
+) -> Result<Json<JoinGameResponse>, Status> {
+    // players who want a pack that doesn't exist would otherwise wait to be paired into a game that can't be created
+    if let Some(word_pack) = &word_pack {
+        if word_pack_exists(word_pack).await == Some(false) {
+            return Err(Status::NotFound);
         }
-        false => {
-            let game_id = receiver.recv().expect("game_id receiver should work");
+    }
 
-            let player_type = Player::P2;
+    let rating = match &player_id {
+        Some(player_id) => get_rating(player_id).await.unwrap_or(DEFAULT_RATING),
+        None => DEFAULT_RATING,
+    };
 
-            Json(JoinGameResponse {
-                game_id,
-                player_type,
-            })
+    loop {
+        let joined_at = Instant::now();
+
+        // whoever finds the pair creates the game and sends it to the other player
+        let joined = queue.join(
+            player_id.clone(),
+            player_secret.clone(),
+            word_pack.clone(),
+            rating,
+            joined_at,
+        );
+        let (ticket, mut receiver) = match joined {
+            Joined::Paired(opponent) => match pair(profile(&player_id, &player_secret), &word_pack, joined_at, opponent).await {
+                Some(response) => return Ok(Json(response)),
+                // opponent left, so join the queue again
+                None => continue,
+            },
+            Joined::Waiting(ticket, receiver) => (ticket, receiver),
+        };
+
+        let paired = loop {
+            tokio::select! {
+                response = &mut receiver => break response.ok(),
+                _ = sleep(RECHECK_INTERVAL) => {
+                    if let Some(opponent) = queue.try_pair(ticket, Instant::now()) {
+                        break pair(profile(&player_id, &player_secret), &word_pack, joined_at, opponent).await;
+                    }
+                    // nobody else is looking for a game, unless someone paired with the player meanwhile
+                    if let Some(strength) = bot {
+                        if joined_at.elapsed() >= BOT_WAIT && queue.leave(ticket) {
+                            return Ok(Json(pair_with_bot(profile(&player_id, &player_secret), &word_pack, strength).await));
+                        }
+                    }
+                }
+            }
+        };
+
+        // None if the other player left before the game could be given out
+        if let Some(response) = paired {
+            return Ok(Json(response));
         }
+    }
+}
+
+/// Creates a game played with ```word_pack``` for a player and the opponent they were paired with,
+/// and sends the game to the opponent.
+/// Whoever joined the queue first is P1.
+///
+/// Returns the game for the player, or None if the opponent left the queue.
+async fn pair(
+    player: Option<ProfileCredentials>,
+    word_pack: &Option<String>,
+    joined_at: Instant,
+    opponent: Opponent,
+) -> Option<JoinGameResponse> {
+    // don't leave a game behind for an opponent who already left
+    if !opponent.is_waiting() {
+        return None;
+    }
+
+    let joined_first = joined_at <= opponent.joined_at;
+
+    let opponent_profile = profile(&opponent.player_id, &opponent.player_secret);
+    let (p1, p2) = match joined_first {
+        true => (player, opponent_profile),
+        false => (opponent_profile, player),
     };
+    let (player_type, opponent_type) = match joined_first {
+        true => (Player::P1, Player::P2),
+        false => (Player::P2, Player::P1),
+    };
+
+    let new_game = create_game(p1, p2, word_pack.clone(), None).await;
 
-    // release semaphore
-    drop(sem_permit);
+    let game_id = new_game.as_ref().map(|new_game| new_game.gameId.clone());
+    let token = |player| {
+        new_game.as_ref().map(|new_game| match player {
+            Player::P1 => new_game.p1Token.clone(),
+            Player::P2 => new_game.p2Token.clone(),
+        })
+    };
+
+    let opponent_response = JoinGameResponse {
+        game_id: game_id.clone(),
+        player_type: opponent_type,
+        player_token: token(opponent_type),
+    };
 
-    // return
-    json
+    match opponent.send(opponent_response) {
+        true => Some(JoinGameResponse {
+            game_id,
+            player_type,
+            player_token: token(player_type),
+        }),
+        false => None,
+    }
+}
+
+/// Creates a game played with ```word_pack``` for a player and a bot of ```strength```,
+/// and starts the bot playing its turns.
+/// The player is P1, and the bot is P2.
+async fn pair_with_bot(
+    player: Option<ProfileCredentials>,
+    word_pack: &Option<String>,
+    strength: BotStrength,
+) -> JoinGameResponse {
+    let Some(new_game) = create_game(player, None, word_pack.clone(), Some(strength)).await else {
+        return JoinGameResponse {
+            game_id: None,
+            player_type: Player::P1,
+            player_token: None,
+        };
+    };
+
+    tokio::spawn(play_bot(new_game.gameId.clone(), new_game.p2Token));
+
+    JoinGameResponse {
+        game_id: Some(new_game.gameId),
+        player_type: Player::P1,
+        player_token: Some(new_game.p1Token),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct MutationQuery {
     query: String,
+    variables: NewGameVariables,
+}
+
+/// The profile of a player, along with its secret, as the graphql server takes it.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProfileCredentials {
+    id: String,
+    secret: String,
+}
+
+/// Gets the profile of a player with the id, if they have one.
+/// A missing secret is sent as empty, so the graphql server refuses the profile.
+fn profile(player_id: &Option<String>, player_secret: &Option<String>) -> Option<ProfileCredentials> {
+    player_id.as_ref().map(|id| ProfileCredentials {
+        id: id.clone(),
+        secret: player_secret.clone().unwrap_or_default(),
+    })
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct NewGameVariables {
+    p1: Option<ProfileCredentials>,
+    p2: Option<ProfileCredentials>,
+    wordPack: Option<String>,
+    bot: Option<BotStrength>,
+    // must be these names as they are the variable names in the mutation
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct NewGame {
+    gameId: String,
+    p1Token: String,
+    p2Token: String,
 }
 
 #[allow(non_snake_case)]
 #[derive(Debug, Serialize, Deserialize)]
 struct MutationData {
-    newGame: String,
+    newGame: NewGame,
     // must be this name as that is field name in graphql server response
     // not doing so makes this fail to properly deserialize
 }
@@ -96,21 +239,29 @@ struct MutationResponse {
     data: MutationData,
 }
 
-/// Attempts to create a game in the graphql server. Returns the game id.
+/// Attempts to create a game in the graphql server for the players with the profiles given,
+/// played with the word pack named ```word_pack``` if given, and against a bot of ```bot``` strength if given.
+/// Returns the game id and the tokens of its players.
 /// If there was an error with communications with the graphql server, then this returns None.
-async fn create_game() -> Option<String> {
-    let word_future_response = get_word();
+/// The graphql server picks the word, so the players don't get a word they have recently played.
+async fn create_game(
+    p1: Option<ProfileCredentials>,
+    p2: Option<ProfileCredentials>,
+    word_pack: Option<String>,
+    bot: Option<BotStrength>,
+) -> Option<NewGame> {
     let client = reqwest::Client::new();
 
     let url: String = format!("http://localhost:{}/graphql", *GRAPHQL_PORT);
 
-    let query: String = match word_future_response.await {
-        Some(word) => format!("mutation{{newGame(word:{word})}}"),
-        None => String::from("mutation{newGame}")
-    };
+    // profiles and the word pack are passed as variables, as they come from clients
+    let query: String = String::from(
+        "mutation($p1: ProfileCredentials, $p2: ProfileCredentials, $wordPack: String, $bot: SolverStrength){newGame(p1: $p1, p2: $p2, settings: {wordPack: $wordPack, bot: $bot}){gameId p1Token p2Token}}"
+    );
     
     let data = MutationQuery {
-        query
+        query,
+        variables: NewGameVariables { p1, p2, wordPack: word_pack, bot },
     };
 
     let res = client.post(url).json(&data).send().await;
@@ -129,22 +280,100 @@ async fn create_game() -> Option<String> {
     }
 }
 
-async fn get_word() -> Option<String> {
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayerQuery {
+    query: String,
+    variables: PlayerVariables,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayerVariables {
+    id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayerRating {
+    rating: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayerData {
+    player: PlayerRating,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayerResponse {
+    data: PlayerData,
+}
+
+/// Gets the rating of the player with the profile id from the graphql server.
+/// If there was an error with communications with the graphql server, or there is no such player,
+/// then this returns None.
+async fn get_rating(player_id: &str) -> Option<f64> {
     let client = reqwest::Client::new();
-    let url: String = format!("http://localhost:{}/word/five_letter", *WORD_PORT);
 
-    let res = client.get(url).send().await;
-    match res {
-        Ok(res) => {
-            match res.text().await {
-                Ok(word) => Some(word),
-                // error in parsing
-                Err(_) => None
-            }
-        }
-        // error in getting response
-        Err(_) => None
-    }
+    let url: String = format!("http://localhost:{}/graphql", *GRAPHQL_PORT);
+
+    let data = PlayerQuery {
+        query: String::from("query($id: String!){player(id: $id){rating}}"),
+        variables: PlayerVariables {
+            id: player_id.to_string(),
+        },
+    };
+
+    let res = client.post(url).json(&data).send().await.ok()?;
+    // fails to deserialize if graphql server responded with an error
+    let player_response = res.json::<PlayerResponse>().await.ok()?;
+
+    Some(player_response.data.player.rating)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WordPackQuery {
+    query: String,
+    variables: WordPackVariables,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WordPackVariables {
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WordPackName {
+    name: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+struct WordPackData {
+    wordPack: WordPackName,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WordPackResponse {
+    // null if graphql server responded with an error, e.g. there is no such pack
+    data: Option<WordPackData>,
+}
+
+/// Checks if there is a word pack named ```name``` in the graphql server.
+/// If there was an error with communications with the graphql server, then this returns None.
+async fn word_pack_exists(name: &str) -> Option<bool> {
+    let client = reqwest::Client::new();
+
+    let url: String = format!("http://localhost:{}/graphql", *GRAPHQL_PORT);
+
+    let data = WordPackQuery {
+        query: String::from("query($name: String!){wordPack(name: $name){name}}"),
+        variables: WordPackVariables {
+            name: name.to_string(),
+        },
+    };
+
+    let res = client.post(url).json(&data).send().await.ok()?;
+    let word_pack_response = res.json::<WordPackResponse>().await.ok()?;
+
+    Some(word_pack_response.data.is_some())
 }
 
 #[cfg(test)]
@@ -157,16 +386,8 @@ mod test {
     #[tokio::test]
     async fn get_game_id_from_create_game() {
 
-        let game_id = create_game().await;
-
-        assert_ne!(game_id, None, "Expected create_game to provide an actual game_id.");
-    }
-
-    // test will fail if word server is not online
-    #[tokio::test]
-    async fn get_word_from_get_word() {
-        let word = get_word().await;
+        let new_game = create_game(None, None, None, None).await;
 
-        assert_ne!(word, None, "Expected get_word to return a word.")
+        assert!(new_game.is_some(), "Expected create_game to provide an actual game_id.");
     }
-}
\ No newline at end of file
+}
@@ -15,8 +15,6 @@ use lazy_static::lazy_static;
 use rocket::Build;
 use rocket_db_pools::mongodb::bson::doc;
 use std::env;
-use std::sync::Arc;
-use tokio::sync::{Barrier, Semaphore};
 
 lazy_static! {
     /// Port the graphql server is hosted on
@@ -30,40 +28,30 @@ lazy_static! {
         .unwrap_or("3000".to_string())
         .parse()
         .expect("CLIENT_PORT should be an unsigned integer.");
-    
-    /// Port the word server is hosted on
-    pub static ref WORD_PORT: u32 = env::var("WORD_PORT")
-        .unwrap_or("10002".to_string())
-        .parse()
-        .expect("WORD_PORT should be an unsigned integer.");
 }
 
 
 /// The join_game endpoint used by clients to join a matchmaking queue when trying to join a game
 pub mod join_game;
 
+/// The queue that pairs up players of similar ratings
+pub mod queue;
+
+/// The bot that plays lone players, with guesses from the graphql server's solver
+pub mod bot;
+
 /// cors header definitions to attach to server
 pub mod cors;
 
 /// Builds a rocket server, so all main has to do is launch it
 pub fn build_rocket() -> rocket::Rocket<Build> {
-    // barrier to only allow 2 people (not less or more) to get out of queue at once
-    // this ensures 2 players will be paired up when leaving the queue
-    let barrier = Arc::new(Barrier::new(2));
-
-    let sem = Arc::new(Semaphore::new(2));
-
-    let (tx, rx) = crossbeam_channel::bounded::<Option<String>>(0);
-    let tx = Arc::new(tx);
-    let rx = Arc::new(rx);
+    // players waiting to be paired into a game
+    let queue = queue::MatchmakingQueue::new();
 
     let cors = cors::cors_options();
 
     rocket::build()
-        .manage(barrier)
-        .manage(sem)
-        .manage(tx)
-        .manage(rx)
+        .manage(queue)
         .mount("/", routes![index, join_game::join_game])
         .attach(cors)
 }
@@ -0,0 +1,312 @@
+use super::join_game::JoinGameResponse;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// The rating used for players without a profile, or whose rating couldn't be found.
+pub const DEFAULT_RATING: f64 = 1500.0;
+
+/// The difference in rating allowed between paired players as soon as they join the queue.
+pub const INITIAL_RATING_WINDOW: f64 = 100.0;
+
+/// How much the difference in rating allowed grows for every second a player waits.
+pub const RATING_WINDOW_GROWTH: f64 = 20.0;
+
+/// The difference in rating a player is willing to be paired with, after waiting ```waited```.
+/// The longer someone waits, the wider their window, so no one waits forever.
+pub fn rating_window(waited: Duration) -> f64 {
+    INITIAL_RATING_WINDOW + RATING_WINDOW_GROWTH * waited.as_secs_f64()
+}
+
+/// A player waiting in the queue to be paired.
+struct Waiting {
+    /// Identifies the player's place in the queue.
+    ticket: u64,
+    player_id: Option<String>,
+    /// The secret of the player's profile, needed to add the profile to the game they are paired into.
+    player_secret: Option<String>,
+    /// The word pack the player wants to play with, or None for the dictionary.
+    word_pack: Option<String>,
+    rating: f64,
+    joined_at: Instant,
+    /// Used to tell the player which game they were paired into.
+    sender: oneshot::Sender<JoinGameResponse>,
+}
+
+/// A player taken out of the queue to be paired with.
+/// Whoever takes them out creates the game and sends it to them.
+pub struct Opponent {
+    /// The id of the opponent's profile, if they have one.
+    pub player_id: Option<String>,
+    /// The secret of the opponent's profile.
+    pub player_secret: Option<String>,
+    /// When the opponent joined the queue.
+    pub joined_at: Instant,
+    sender: oneshot::Sender<JoinGameResponse>,
+}
+
+impl Opponent {
+    /// Tells the opponent which game they were paired into.
+    /// Returns false if the opponent is no longer waiting for a game.
+    pub fn send(self, response: JoinGameResponse) -> bool {
+        self.sender.send(response).is_ok()
+    }
+
+    /// Checks if the opponent is still waiting for a game.
+    pub fn is_waiting(&self) -> bool {
+        !self.sender.is_closed()
+    }
+}
+
+/// The result of joining the queue.
+pub enum Joined {
+    /// An opponent was found right away.
+    Paired(Opponent),
+    /// No opponent was found, so the player is waiting in the queue.
+    /// The receiver gets the game once another player pairs with them.
+    Waiting(u64, oneshot::Receiver<JoinGameResponse>),
+}
+
+/// The queue of players waiting to be paired into a game.
+/// Players are paired with the closest rated player within their rating window who wants the same word pack.
+#[derive(Default)]
+pub struct MatchmakingQueue {
+    waiting: Mutex<Vec<Waiting>>,
+    next_ticket: AtomicU64,
+}
+
+impl MatchmakingQueue {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a player to the queue, unless there is already an opponent for them.
+    pub fn join(
+        &self,
+        player_id: Option<String>,
+        player_secret: Option<String>,
+        word_pack: Option<String>,
+        rating: f64,
+        now: Instant,
+    ) -> Joined {
+        let mut waiting = self
+            .waiting
+            .lock()
+            .expect("queue lock should not be poisoned");
+
+        // players who stopped waiting (e.g. disconnected) can't be paired
+        waiting.retain(|player| !player.sender.is_closed());
+
+        if let Some(index) =
+            find_opponent(&waiting, None, &player_id, &word_pack, rating, now, now)
+        {
+            return Joined::Paired(take_opponent(&mut waiting, index));
+        }
+
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+
+        let (sender, receiver) = oneshot::channel();
+        waiting.push(Waiting {
+            ticket,
+            player_id,
+            player_secret,
+            word_pack,
+            rating,
+            joined_at: now,
+            sender,
+        });
+
+        Joined::Waiting(ticket, receiver)
+    }
+
+    /// Checks again for an opponent for a waiting player, as their rating window grows.
+    /// If one is found, both are taken out of the queue.
+    ///
+    /// Returns None if there is still no opponent,
+    /// or the player is no longer waiting because someone else paired with them.
+    pub fn try_pair(&self, ticket: u64, now: Instant) -> Option<Opponent> {
+        let mut waiting = self
+            .waiting
+            .lock()
+            .expect("queue lock should not be poisoned");
+
+        waiting.retain(|player| !player.sender.is_closed());
+
+        let own_index = waiting.iter().position(|player| player.ticket == ticket)?;
+        let own = &waiting[own_index];
+
+        let index = find_opponent(
+            &waiting,
+            Some(ticket),
+            &own.player_id,
+            &own.word_pack,
+            own.rating,
+            own.joined_at,
+            now,
+        )?;
+        let opponent = take_opponent(&mut waiting, index);
+
+        // the player pairing is no longer waiting either
+        waiting.retain(|player| player.ticket != ticket);
+
+        Some(opponent)
+    }
+
+    /// Takes a waiting player out of the queue, e.g. to pair them with a bot instead.
+    ///
+    /// Returns false if the player is no longer waiting because someone else paired with them,
+    /// in which case they get the game from their receiver.
+    pub fn leave(&self, ticket: u64) -> bool {
+        let mut waiting = self
+            .waiting
+            .lock()
+            .expect("queue lock should not be poisoned");
+
+        let Some(index) = waiting.iter().position(|player| player.ticket == ticket) else {
+            return false;
+        };
+        waiting.remove(index);
+        true
+    }
+}
+
+/// Finds the closest rated player that can be paired with a player, and returns their index.
+///
+/// Players can be paired if they want the same word pack,
+/// and their difference in rating fits in either of their rating windows,
+/// so a player who has waited long can be paired with anyone who just joined.
+/// A player with a profile is never paired with themself.
+fn find_opponent(
+    waiting: &[Waiting],
+    ticket: Option<u64>,
+    player_id: &Option<String>,
+    word_pack: &Option<String>,
+    rating: f64,
+    joined_at: Instant,
+    now: Instant,
+) -> Option<usize> {
+    let window = rating_window(now.duration_since(joined_at));
+
+    waiting
+        .iter()
+        .enumerate()
+        .filter(|(_, other)| Some(other.ticket) != ticket)
+        .filter(|(_, other)| player_id.is_none() || other.player_id != *player_id)
+        .filter(|(_, other)| other.word_pack == *word_pack)
+        .filter(|(_, other)| {
+            let other_window = rating_window(now.duration_since(other.joined_at));
+            (other.rating - rating).abs() <= window.max(other_window)
+        })
+        .min_by(|(_, a), (_, b)| {
+            let a_difference = (a.rating - rating).abs();
+            let b_difference = (b.rating - rating).abs();
+            a_difference.total_cmp(&b_difference)
+        })
+        .map(|(index, _)| index)
+}
+
+/// Takes the player at ```index``` out of the queue.
+fn take_opponent(waiting: &mut Vec<Waiting>, index: usize) -> Opponent {
+    let opponent = waiting.remove(index);
+
+    Opponent {
+        player_id: opponent.player_id,
+        player_secret: opponent.player_secret,
+        joined_at: opponent.joined_at,
+        sender: opponent.sender,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn waiting_ticket(joined: Joined) -> (u64, oneshot::Receiver<JoinGameResponse>) {
+        match joined {
+            Joined::Waiting(ticket, receiver) => (ticket, receiver),
+            Joined::Paired(_) => panic!("Expected player to wait in the queue."),
+        }
+    }
+
+    #[test]
+    fn window_widens_while_waiting() {
+        assert_eq!(rating_window(Duration::ZERO), INITIAL_RATING_WINDOW);
+        assert!(rating_window(Duration::from_secs(30)) > rating_window(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn pairs_closest_rating_in_window() {
+        let queue = MatchmakingQueue::new();
+        let now = Instant::now();
+
+        let (_, _far) = waiting_ticket(queue.join(Some("far".to_string()), None, None, 1580.0, now));
+        let (_, _close) = waiting_ticket(queue.join(Some("close".to_string()), None, None, 1440.0, now));
+
+        match queue.join(Some("new".to_string()), None, None, 1500.0, now) {
+            Joined::Paired(opponent) => assert_eq!(opponent.player_id.as_deref(), Some("close")),
+            Joined::Waiting(..) => panic!("Expected player to be paired."),
+        }
+    }
+
+    #[test]
+    fn far_ratings_pair_after_waiting() {
+        let queue = MatchmakingQueue::new();
+        let joined_at = Instant::now();
+
+        let (veteran, _veteran_receiver) = waiting_ticket(queue.join(None, None, None, 2000.0, joined_at));
+        let (_, _newcomer_receiver) = waiting_ticket(queue.join(None, None, None, 1500.0, joined_at));
+
+        assert!(queue
+            .try_pair(veteran, joined_at + Duration::from_secs(5))
+            .is_none());
+        assert!(queue
+            .try_pair(veteran, joined_at + Duration::from_secs(30))
+            .is_some());
+    }
+
+    #[test]
+    fn leaving_is_refused_once_paired() {
+        let queue = MatchmakingQueue::new();
+        let now = Instant::now();
+
+        let (lonely, _lonely_receiver) = waiting_ticket(queue.join(None, None, None, 1500.0, now));
+        assert!(queue.leave(lonely));
+        assert!(!queue.leave(lonely));
+
+        let (paired, _paired_receiver) = waiting_ticket(queue.join(None, None, None, 1500.0, now));
+        assert!(matches!(queue.join(None, None, None, 1500.0, now), Joined::Paired(_)));
+        assert!(!queue.leave(paired));
+    }
+
+    #[test]
+    fn opponent_stops_waiting_when_they_leave() {
+        let queue = MatchmakingQueue::new();
+        let now = Instant::now();
+
+        let (_, receiver) = waiting_ticket(queue.join(None, None, None, 1500.0, now));
+        let Joined::Paired(opponent) = queue.join(None, None, None, 1500.0, now) else {
+            panic!("Expected player to be paired.");
+        };
+        assert!(opponent.is_waiting());
+
+        drop(receiver);
+        assert!(!opponent.is_waiting());
+    }
+
+    #[test]
+    fn only_pairs_same_word_pack() {
+        let queue = MatchmakingQueue::new();
+        let now = Instant::now();
+
+        let programming = Some("programming".to_string());
+        let (_, _receiver) = waiting_ticket(queue.join(None, None, programming.clone(), 1500.0, now));
+
+        let (_, _dictionary_receiver) = waiting_ticket(queue.join(None, None, None, 1500.0, now));
+        assert!(matches!(
+            queue.join(None, None, programming, 1500.0, now),
+            Joined::Paired(_)
+        ));
+    }
+}